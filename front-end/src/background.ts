@@ -4,7 +4,11 @@ import type {
   SitePrice,
 } from "./type/item";
 import { getCurrencyPreference } from "./utils/storage";
-import { getExchangeRates, convertFromUSDSync } from "./utils/currency";
+import {
+  getExchangeRates,
+  getRateMetadata,
+  convertFromUSDSync,
+} from "./utils/currency";
 
 const API_BASE_URL =
   import.meta.env.VITE_API_BASE_URL || "http://localhost:8080/api";
@@ -169,8 +173,11 @@ async function applyUserCurrencyConversion(
     : null;
 
   return {
+    ...data,
     all_prices: convertedPrices,
     best_deal: convertedBestDeal,
+    // Converted here, so the rates here are the ones to warn about
+    rate_metadata: await getRateMetadata(),
   };
 }
 
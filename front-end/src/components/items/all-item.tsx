@@ -1,6 +1,7 @@
 import LinkIcon from "../icons/link";
 import { ScrollArea } from "../ui/scroll-area";
 import type {
+  BuyRecommendation,
   Item,
   PriceComparisonResult,
   ProductMatchRequest,
@@ -10,6 +11,21 @@ import { useTrackedProducts } from "@/hooks/useTrackedProducts";
 import { isProductTracked } from "@/utils/storage";
 import { formatPrice, getCurrencySymbol } from "@/utils/currency";
 
+// Badge text and colors of each price insight recommendation
+const INSIGHT_LABELS: Record<BuyRecommendation, string> = {
+  buy: "Good time to buy",
+  fair: "Typical price",
+  wait: "Consider waiting",
+  not_enough_history: "",
+};
+
+const INSIGHT_STYLES: Record<BuyRecommendation, string> = {
+  buy: "bg-green-100 text-green-700",
+  fair: "bg-gray-100 text-gray-700",
+  wait: "bg-red-100 text-red-700",
+  not_enough_history: "",
+};
+
 interface AllItemProps {
   priceData: {
     data: PriceComparisonResult | null;
@@ -105,7 +121,9 @@ const AllItem = ({ priceData, searchQuery }: AllItemProps) => {
               data?.best_deal?.site === item.site &&
               data?.best_deal?.price_usd === item.price_usd
             }
-            onTrack={addTrackedProduct}
+            onTrack={(product, url) =>
+              addTrackedProduct(product, url, data?.product_id)
+            }
             onTrackComplete={refreshTracked}
           />
         ))}
@@ -195,6 +213,12 @@ const PriceItem = ({
                 {formatPrice(Number(item.price))}
               </>
             )}
+            {item.list_price && (
+              <span className="ml-1.5 text-xs font-normal text-[#7C7C7C] line-through">
+                {getCurrencySymbol(item.list_price.currency)}
+                {formatPrice(Number(item.list_price.amount))}
+              </span>
+            )}
           </p>
           {item.match_confidence !== null &&
             item.match_confidence !== undefined && (
@@ -202,6 +226,33 @@ const PriceItem = ({
                 {item.match_confidence}% match confidence
               </p>
             )}
+          {item.price_insight &&
+            item.price_insight.recommendation !== "not_enough_history" && (
+              <span
+                className={`text-[10px] font-medium px-1.5 py-0.5 rounded self-start ${
+                  INSIGHT_STYLES[item.price_insight.recommendation]
+                }`}
+                title={item.price_insight.reasons.join("\n")}
+              >
+                {INSIGHT_LABELS[item.price_insight.recommendation]}
+              </span>
+            )}
+          {(item.discount_check?.verdict === "inflated" ||
+            item.discount_check?.verdict === "shifting") && (
+            <span
+              className="text-[10px] font-medium px-1.5 py-0.5 rounded self-start bg-orange-100 text-orange-700"
+              title={
+                item.discount_check.verdict === "shifting"
+                  ? `The "was" price changed ${item.discount_check.reference_prices} times in the last ${item.discount_check.window_days} days`
+                  : `The "was" price wasn't seen in the last ${item.discount_check.window_days} days (${item.discount_check.observations} prices recorded)`
+              }
+            >
+              Claims {Number(item.discount_check.claimed_percent).toFixed(0)}%
+              off
+              {item.discount_check.true_percent !== undefined &&
+                ` · really ${Number(item.discount_check.true_percent).toFixed(0)}%`}
+            </span>
+          )}
         </div>
       </div>
       <div className="flex items-center gap-x-2 flex-shrink-0">
@@ -2,7 +2,12 @@ import { ScrollArea } from "../ui/scroll-area";
 import type { TrackedProduct } from "@/type/item";
 import { useState, useEffect } from "react";
 import LinkIcon from "../icons/link";
-import { getPriceChange, getCurrencyPreference } from "@/utils/storage";
+import { getCurrencyPreference } from "@/utils/storage";
+import {
+  fetchPriceHistory,
+  getPriceChange,
+  type PriceChange,
+} from "@/utils/history";
 import {
   convertCurrency,
   formatPrice,
@@ -91,37 +96,37 @@ const ProductCard = ({ product, onRemove }: ProductCardProps) => {
   const [removing, setRemoving] = useState(false);
   const [displayCurrency, setDisplayCurrency] = useState("USD");
   const [displayPrice, setDisplayPrice] = useState(product.current_price);
-  const [displayChangeAmount, setDisplayChangeAmount] = useState(0);
+  const [priceChange, setPriceChange] = useState<PriceChange>(
+    getPriceChange(null, product.site),
+  );
 
-  const priceChange = getPriceChange(product);
   const daysSinceTracked = Math.floor(
     (Date.now() - product.tracked_at) / (1000 * 60 * 60 * 24),
   );
 
-  // Get user's preferred currency and convert prices
+  // Load the price history in the user's preferred currency
   useEffect(() => {
-    async function loadCurrencyAndConvert() {
+    async function loadHistory() {
       const currency = await getCurrencyPreference();
       setDisplayCurrency(currency);
 
-      // Convert prices
-      const converted = await convertCurrency(
-        product.current_price,
-        product.currency,
-        currency,
-      );
-      setDisplayPrice(converted);
-
-      const convertedChange = await convertCurrency(
-        priceChange.change_amount,
-        product.currency,
-        currency,
+      const history = await fetchPriceHistory(product, currency);
+      const change = getPriceChange(history, product.site);
+      setPriceChange(change);
+
+      // Latest recorded price, or the tracked price when there is no history yet
+      setDisplayPrice(
+        change.current_price ??
+          (await convertCurrency(
+            product.current_price,
+            product.currency,
+            currency,
+          )),
       );
-      setDisplayChangeAmount(convertedChange);
     }
 
-    loadCurrencyAndConvert();
-  }, [product.current_price, product.currency, priceChange.change_amount]);
+    loadHistory();
+  }, [product]);
 
   const handleRemove = async () => {
     setRemoving(true);
@@ -178,7 +183,7 @@ const ProductCard = ({ product, onRemove }: ProductCardProps) => {
                 <span>
                   {priceChange.change_percentage.toFixed(1)}% (
                   {getCurrencySymbol(displayCurrency)}
-                  {formatPrice(displayChangeAmount)})
+                  {formatPrice(priceChange.change_amount)})
                 </span>
               </div>
             )}
@@ -5,8 +5,9 @@ import {
   trackProduct,
   untrackProduct,
   isProductTracked,
-  getPriceChange,
+  getCurrencyPreference,
 } from "@/utils/storage";
+import { fetchPriceHistory, getPriceChange } from "@/utils/history";
 
 export function useTrackedProducts() {
   const [products, setProducts] = useState<TrackedProduct[]>([]);
@@ -31,9 +32,9 @@ export function useTrackedProducts() {
 
   // Track a product
   const addTrackedProduct = useCallback(
-    async (product: SitePrice, url: string) => {
+    async (product: SitePrice, url: string, productId?: string) => {
       try {
-        await trackProduct(product, url);
+        await trackProduct(product, url, productId);
         await loadProducts();
       } catch (error) {
         console.error("Error tracking product:", error);
@@ -62,17 +63,24 @@ export function useTrackedProducts() {
     return await isProductTracked(site, url);
   }, []);
 
-  // Get products with price changes
-  const getProductsWithChanges = useCallback(() => {
-    return products.map((product) => ({
-      product,
-      ...getPriceChange(product),
-    }));
+  // Get products with price changes (from the backend's price history)
+  const getProductsWithChanges = useCallback(async () => {
+    const currency = await getCurrencyPreference();
+    return Promise.all(
+      products.map(async (product) => ({
+        product,
+        ...getPriceChange(
+          await fetchPriceHistory(product, currency),
+          product.site,
+        ),
+      })),
+    );
   }, [products]);
 
   // Get products with price drops
-  const getProductsWithDrops = useCallback(() => {
-    return getProductsWithChanges().filter((item) => item.status === "decreased");
+  const getProductsWithDrops = useCallback(async () => {
+    const changes = await getProductsWithChanges();
+    return changes.filter((item) => item.status === "decreased");
   }, [getProductsWithChanges]);
 
   return {
@@ -9,6 +9,7 @@ export interface ProductIdentifiers {
   model_number?: string;
   brand?: string;
   specifications?: Record<string, string>;
+  image_hash?: string; // 64-bit dHash as 16 hex digits (computed from image if absent)
 }
 
 // Request body for POST /api/compare
@@ -18,9 +19,133 @@ export interface ProductMatchRequest {
   currency?: string;
   current_site?: string;
   url?: string;
-  image?: string;
+  image?: string; // Hashed to compare with listing images
   target_currency?: string;
   identifiers?: ProductIdentifiers;
+  explain?: boolean; // Include match_explanation on each result (skips cache)
+  detail_fetch_budget?: number; // Max detail pages fetched to verify borderline matches
+  rank_by?: RankBy; // Omit to rank by unit price only for mixed pack sizes
+}
+
+// Order of all_prices
+export type RankBy = "price" | "unit_price";
+
+// Price per item, per 100 ml or per kg
+export interface UnitPrice {
+  quantity: {
+    count: number; // Items in the pack
+    amount: number; // Total quantity in basis units
+    basis: "item" | "100ml" | "kg";
+  };
+  price: number;
+  price_usd: number;
+  usd_rate?: UsdRate; // Rate used for price_usd
+  price_converted?: number;
+}
+
+// Rule that set the base match confidence
+export type MatchTier =
+  | "gtin"
+  | "asin"
+  | "ebay_item_id"
+  | "model_brand_specs"
+  | "model_brand"
+  | "partial"
+  | "fuzzy_title"
+  | "preset";
+
+// Deduction applied on top of the tier confidence
+export interface MatchPenalty {
+  reason: "spec_mismatch" | "accessory" | "gtin_mismatch" | "image_mismatch";
+  detail: string;
+  points: number;
+}
+
+// How a match confidence was reached (returned when explain is set)
+export interface MatchExplanation {
+  tier: MatchTier;
+  base_confidence: number;
+  matched_identifiers: string[]; // e.g. "gtin:00012345678905"
+  matched_specs: string[];
+  mismatched_specs: string[];
+  similarity?: {
+    language: "en" | "fr" | "de" | "ar";
+    jaro_winkler: number;
+    keyword_overlap: number;
+    combined: number;
+  };
+  penalties: MatchPenalty[];
+  image?: {
+    distance: number; // Hamming distance between image hashes (0-64)
+    boost: number;
+  };
+  confidence: number;
+  verified?: boolean; // Scored with data from the listing's detail page
+}
+
+// Exchange rate provider and publication time
+export interface RateSource {
+  provider: string; // "exchangerate_api", "open_exchange_rates", "ecb" or "fallback"
+  published_at: string; // ISO 8601
+}
+
+// Source and age of the rates behind a conversion
+export interface RateMetadata {
+  providers: string[]; // e.g. ["ecb"]; "fallback" for the built-in rates
+  fetched_at: string; // ISO 8601
+  published_at?: string; // Oldest rate used (absent when no rate was needed)
+  fallback_used: boolean;
+  stale: boolean; // Oldest rate is past the server's staleness limit
+}
+
+// GET /api/rates response
+export interface RatesResponse {
+  base: string;
+  rates: Record<string, string>; // Units per unit of base (decimal strings)
+  rate_metadata: RateMetadata;
+}
+
+// Rate a listing was normalized to USD with
+export interface UsdRate extends RateSource {
+  rate: number; // USD per unit of the listing's currency
+}
+
+// How a listing's currency was resolved, strongest evidence first
+export interface ResolvedCurrency {
+  currency: string;
+  evidence: "declared" | "code" | "marketplace" | "symbol" | "shared_symbol" | "default";
+  confidence: number; // 0-100
+}
+
+export type BuyRecommendation = "buy" | "fair" | "wait" | "not_enough_history";
+
+// How a listing's price compares with its recorded prices (in its own currency)
+export interface PriceInsight {
+  all_time_low: Money;
+  all_time_high: Money;
+  low_30d?: Money;
+  low_90d?: Money;
+  percentile_30d?: number; // Share of recent prices below the current one (0 = cheapest)
+  percentile_90d?: number;
+  days_since_lower?: number; // Absent if the price was never lower
+  observations: number;
+  tracked_since: string; // ISO 8601
+  recommendation: BuyRecommendation;
+  reasons: string[];
+}
+
+export type DiscountVerdict = "genuine" | "inflated" | "shifting" | "unverified";
+
+// Advertised discount checked against the listing's recorded prices
+export interface DiscountCheck {
+  verdict: DiscountVerdict;
+  claimed_percent: string; // Decimal string, e.g. "31.3"
+  true_percent?: string; // Discount from the median recorded price (negative when above it)
+  observed_median?: Money;
+  observed_max?: Money;
+  observations: number;
+  reference_prices: number; // Distinct "was" prices advertised in the window, current included
+  window_days: number;
 }
 
 // Individual price from a specific site
@@ -29,24 +154,35 @@ export interface SitePrice {
   title: string;
   price: number;
   currency: string;
+  currency_resolution?: ResolvedCurrency; // Present for scraped listings
   price_usd: number;
   price_converted?: number;
   target_currency?: string;
   link: string;
   image: string | null;
   match_confidence: number | null;
+  match_explanation?: MatchExplanation;
+  unit_price?: UnitPrice; // Present when the listing states a quantity
+  image_hash?: string; // Present when the image was hashed for matching
+  rate_sources?: RateSource[]; // Rates used for price_converted
+  price_insight?: PriceInsight; // Present when the listing has recorded history
+  list_price?: Money; // Advertised "was" / strike-through price
+  discount_check?: DiscountCheck; // Present when list_price is set
 }
 
 // API response from both GET and POST /api/compare
 export interface PriceComparisonResult {
+  product_id?: string; // Canonical product ID (absent if the catalog is unavailable)
   best_deal: SitePrice | null;
   all_prices: SitePrice[];
+  ranked_by: RankBy;
+  rate_metadata?: RateMetadata; // Rates behind price_usd and price_converted
 }
 
 // Legacy type for backward compatibility
 export type Item = SitePrice;
 
-// Tracked product with price history
+// Tracked product (its price history comes from GET /api/history)
 export interface TrackedProduct {
   id: string; // Unique identifier (combination of site + product ID)
   title: string;
@@ -57,13 +193,43 @@ export interface TrackedProduct {
   image: string | null;
   tracked_at: number; // Timestamp when first tracked
   last_checked: number; // Timestamp of last price check
-  price_history: PricePoint[];
   identifiers?: ProductIdentifiers;
+  product_id?: string; // Canonical product ID from the comparison
 }
 
-export interface PricePoint {
-  price: number;
-  timestamp: number;
+// Amount in a currency (decimal string, e.g. { amount: "999.99", currency: "USD" })
+export interface Money {
+  amount: string;
+  currency: string;
+}
+
+export type HistoryInterval = "day" | "week" | "month";
+
+// Prices of one site in one day, week or month
+export interface HistoryBucket {
+  start: string; // First day of the bucket (YYYY-MM-DD)
+  count: number; // 0 for a day/week/month without prices
+  min: Money | null;
+  avg: Money | null;
+  max: Money | null;
+  last: Money | null; // Carried forward across empty buckets
+}
+
+export interface SiteHistory {
+  site: string;
+  buckets: HistoryBucket[];
+}
+
+// GET /api/history response
+export interface PriceHistoryResponse {
+  interval: HistoryInterval;
+  currency: string;
+  rates: "observation" | "current";
+  from: string;
+  to: string;
+  sites: SiteHistory[];
+  unconverted: number; // Rows without a rate for conversion
+  truncated: boolean;
 }
 
 // Price change status for tracked items
@@ -1,3 +1,5 @@
+import type { RateMetadata, RatesResponse } from "@/type/item";
+
 // Currency symbols
 export const CURRENCY_SYMBOLS: Record<string, string> = {
   USD: "$",
@@ -25,44 +27,64 @@ const FALLBACK_RATES: Record<string, number> = {
 // Cache for exchange rates
 interface ExchangeRateCache {
   rates: Record<string, number>;
+  metadata: RateMetadata;
   timestamp: number;
 }
 
 const CACHE_DURATION = 60 * 60 * 1000; // 1 hour
-const EXCHANGE_RATE_API = "https://api.exchangerate-api.com/v4/latest/USD";
+const API_BASE_URL =
+  import.meta.env.VITE_API_BASE_URL || "http://localhost:8080/api";
 
 let cachedRates: ExchangeRateCache | null = null;
 
-// Fetch live exchange rates from API
-async function fetchExchangeRates(): Promise<Record<string, number>> {
+// Metadata reported when the backend is unreachable
+function fallbackMetadata(): RateMetadata {
+  return {
+    providers: ["fallback"],
+    fetched_at: new Date().toISOString(),
+    fallback_used: true,
+    stale: true,
+  };
+}
+
+// Fetch exchange rates (per USD) from the backend's /api/rates
+async function fetchExchangeRates(): Promise<ExchangeRateCache> {
   try {
     // Check cache first
     if (cachedRates && Date.now() - cachedRates.timestamp < CACHE_DURATION) {
-      return cachedRates.rates;
+      return cachedRates;
     }
 
-    const response = await fetch(EXCHANGE_RATE_API);
+    const response = await fetch(`${API_BASE_URL}/rates?base=USD`);
     if (!response.ok) {
       throw new Error("Failed to fetch exchange rates");
     }
 
-    const data = await response.json();
-    const rates = data.rates as Record<string, number>;
+    const data = (await response.json()) as RatesResponse;
+    const rates: Record<string, number> = {};
+    for (const [code, rate] of Object.entries(data.rates)) {
+      rates[code] = Number(rate);
+    }
 
     // Cache the rates
     cachedRates = {
       rates,
+      metadata: data.rate_metadata,
       timestamp: Date.now(),
     };
 
-    console.log("[Currency] Fetched live exchange rates:", rates);
-    return rates;
+    console.log("[Currency] Fetched exchange rates:", data.rate_metadata);
+    return cachedRates;
   } catch (error) {
     console.error(
       "[Currency] Failed to fetch exchange rates, using fallback:",
       error,
     );
-    return FALLBACK_RATES;
+    return {
+      rates: FALLBACK_RATES,
+      metadata: fallbackMetadata(),
+      timestamp: Date.now(),
+    };
   }
 }
 
@@ -70,7 +92,14 @@ async function fetchExchangeRates(): Promise<Record<string, number>> {
  * Get current exchange rates (cached or fresh)
  */
 export async function getExchangeRates(): Promise<Record<string, number>> {
-  return await fetchExchangeRates();
+  return (await fetchExchangeRates()).rates;
+}
+
+/**
+ * Get the source and age of the current exchange rates
+ */
+export async function getRateMetadata(): Promise<RateMetadata> {
+  return (await fetchExchangeRates()).metadata;
 }
 
 /**
@@ -0,0 +1,91 @@
+import type {
+  HistoryInterval,
+  PriceHistoryResponse,
+  SiteHistory,
+  TrackedProduct,
+} from "@/type/item";
+
+const API_BASE_URL =
+  import.meta.env.VITE_API_BASE_URL || "http://localhost:8080/api";
+
+export interface PriceChange {
+  status: "increased" | "decreased" | "same";
+  change_amount: number;
+  change_percentage: number;
+  current_price: number | null; // Latest recorded price, in the history's currency
+}
+
+// Fetch a tracked product's price history from the backend's /api/history,
+// by canonical product when known and by listing URL otherwise
+export async function fetchPriceHistory(
+  product: TrackedProduct,
+  currency: string,
+  interval: HistoryInterval = "day",
+): Promise<PriceHistoryResponse | null> {
+  const params = new URLSearchParams({ interval, currency });
+  if (product.product_id) {
+    params.set("product_id", product.product_id);
+  } else {
+    params.set("url", product.url);
+  }
+
+  try {
+    const response = await fetch(`${API_BASE_URL}/history?${params}`);
+    if (!response.ok) {
+      throw new Error(`History request failed: ${response.status}`);
+    }
+    return (await response.json()) as PriceHistoryResponse;
+  } catch (error) {
+    console.error("[History] Failed to fetch price history:", error);
+    return null;
+  }
+}
+
+// Buckets of the product's own site
+export function siteHistory(
+  history: PriceHistoryResponse | null,
+  site: string,
+): SiteHistory | undefined {
+  return history?.sites.find(
+    (entry) => entry.site.toLowerCase() === site.toLowerCase(),
+  );
+}
+
+// Change between the first and the latest recorded price on the product's site
+export function getPriceChange(
+  history: PriceHistoryResponse | null,
+  site: string,
+): PriceChange {
+  const prices = (siteHistory(history, site)?.buckets ?? [])
+    .filter((bucket) => bucket.count > 0 && bucket.last !== null)
+    .map((bucket) => Number(bucket.last!.amount));
+
+  if (prices.length === 0) {
+    return {
+      status: "same",
+      change_amount: 0,
+      change_percentage: 0,
+      current_price: null,
+    };
+  }
+
+  const originalPrice = prices[0];
+  const currentPrice = prices[prices.length - 1];
+  const change_amount = currentPrice - originalPrice;
+  const change_percentage =
+    originalPrice === 0 ? 0 : (change_amount / originalPrice) * 100;
+
+  let status: PriceChange["status"] = "same";
+  if (change_amount > 0) {
+    status = "increased";
+  } else if (change_amount < 0) {
+    status = "decreased";
+  }
+
+  return {
+    status,
+    change_amount: Math.abs(change_amount),
+    change_percentage: Math.abs(change_percentage),
+    current_price: currentPrice,
+  };
+}
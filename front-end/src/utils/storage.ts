@@ -32,6 +32,7 @@ function generateProductId(site: string, url: string): string {
 export async function trackProduct(
   product: SitePrice,
   url: string,
+  productId?: string,
 ): Promise<TrackedProduct> {
   const products = await getTrackedProducts();
   const id = generateProductId(product.site, url);
@@ -39,6 +40,11 @@ export async function trackProduct(
   // Check if already tracked
   const existing = products.find((p) => p.id === id);
   if (existing) {
+    // Link items tracked before product IDs existed
+    if (productId && !existing.product_id) {
+      existing.product_id = productId;
+      await saveTrackedProducts(products);
+    }
     return existing;
   }
 
@@ -53,12 +59,7 @@ export async function trackProduct(
     image: product.image,
     tracked_at: now,
     last_checked: now,
-    price_history: [
-      {
-        price: Number(product.price),
-        timestamp: now,
-      },
-    ],
+    product_id: productId,
   };
 
   products.push(trackedProduct);
@@ -95,61 +96,14 @@ export async function updateProductPrice(
     return null;
   }
 
-  const now = Date.now();
-
-  // Only add to history if price changed
-  if (product.current_price !== newPrice) {
-    product.price_history.push({
-      price: newPrice,
-      timestamp: now,
-    });
-
-    // Keep only last 30 price points to avoid storage bloat
-    if (product.price_history.length > 30) {
-      product.price_history = product.price_history.slice(-30);
-    }
-  }
-
+  // Price history is recorded by the backend (GET /api/history)
   product.current_price = newPrice;
-  product.last_checked = now;
+  product.last_checked = Date.now();
 
   await saveTrackedProducts(products);
   return product;
 }
 
-// Get price change for a tracked product
-export function getPriceChange(product: TrackedProduct): {
-  status: "increased" | "decreased" | "same";
-  change_amount: number;
-  change_percentage: number;
-} {
-  if (product.price_history.length < 2) {
-    return {
-      status: "same",
-      change_amount: 0,
-      change_percentage: 0,
-    };
-  }
-
-  const originalPrice = product.price_history[0].price;
-  const currentPrice = product.current_price;
-  const change_amount = currentPrice - originalPrice;
-  const change_percentage = (change_amount / originalPrice) * 100;
-
-  let status: "increased" | "decreased" | "same" = "same";
-  if (change_amount > 0) {
-    status = "increased";
-  } else if (change_amount < 0) {
-    status = "decreased";
-  }
-
-  return {
-    status,
-    change_amount: Math.abs(change_amount),
-    change_percentage: Math.abs(change_percentage),
-  };
-}
-
 // Get user's currency preference
 export async function getCurrencyPreference(): Promise<string> {
   try {
@@ -0,0 +1,27 @@
+-- Create match_feedback table to record user verdicts on product matches
+CREATE TABLE IF NOT EXISTS match_feedback (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_key VARCHAR(255) NOT NULL,
+    source_title VARCHAR(500) NOT NULL,
+    source_identifiers JSONB NOT NULL DEFAULT '{}'::jsonb,
+    candidate_site VARCHAR(50) NOT NULL,
+    candidate_title VARCHAR(500) NOT NULL,
+    candidate_link TEXT NOT NULL,
+    candidate_key TEXT NOT NULL,
+    verdict VARCHAR(20) NOT NULL,
+    match_confidence SMALLINT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT verdict_valid CHECK (verdict IN ('match', 'mismatch')),
+    CONSTRAINT match_confidence_range CHECK (match_confidence BETWEEN 0 AND 100)
+);
+
+CREATE INDEX idx_match_feedback_source ON match_feedback(source_key, candidate_key, created_at DESC);
+CREATE INDEX idx_match_feedback_created_at ON match_feedback(created_at DESC);
+
+-- Add comments for documentation
+COMMENT ON TABLE match_feedback IS 'User verdicts on whether a listing is the same product as the source';
+COMMENT ON COLUMN match_feedback.source_key IS 'Stable key of the source product (gtin:, asin:, ebay:, or title:)';
+COMMENT ON COLUMN match_feedback.candidate_key IS 'Candidate link without query string or fragment';
+COMMENT ON COLUMN match_feedback.verdict IS 'match or mismatch; the latest verdict per source and candidate wins';
+COMMENT ON COLUMN match_feedback.match_confidence IS 'Confidence shown to the user when the verdict was given';
@@ -0,0 +1,58 @@
+-- Create canonical product catalog so comparisons and history share stable product IDs
+CREATE TABLE IF NOT EXISTS products (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    canonical_key VARCHAR(255) NOT NULL UNIQUE,
+    title VARCHAR(500) NOT NULL,
+    normalized_title VARCHAR(500) NOT NULL,
+    brand VARCHAR(100),
+    model_number VARCHAR(100),
+    gtin VARCHAR(14),
+    asin VARCHAR(10),
+    specs JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_products_gtin ON products(gtin) WHERE gtin IS NOT NULL;
+CREATE INDEX idx_products_asin ON products(asin) WHERE asin IS NOT NULL;
+
+-- Keys (identifiers and normalized titles) that resolve to a product
+CREATE TABLE IF NOT EXISTS product_aliases (
+    alias_key VARCHAR(255) PRIMARY KEY,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_aliases_product ON product_aliases(product_id);
+
+-- Site listings clustered onto a product
+CREATE TABLE IF NOT EXISTS product_listings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    site VARCHAR(50) NOT NULL,
+    link_key TEXT NOT NULL UNIQUE,
+    link TEXT NOT NULL,
+    title VARCHAR(500) NOT NULL,
+    match_confidence SMALLINT,
+    last_price DECIMAL(12, 2) NOT NULL,
+    last_currency VARCHAR(3) NOT NULL,
+    last_price_usd DECIMAL(12, 2) NOT NULL,
+    first_seen_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_listings_product ON product_listings(product_id, last_seen_at DESC);
+
+-- Link price history to products
+ALTER TABLE price_history
+ADD COLUMN product_id UUID REFERENCES products(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_price_history_product ON price_history(product_id, scraped_at DESC);
+
+-- Add comments for documentation
+COMMENT ON TABLE products IS 'Canonical products that site listings and price history are clustered onto';
+COMMENT ON COLUMN products.canonical_key IS 'Strongest key known when the product was created (gtin:, asin:, ebay:, or title:)';
+COMMENT ON TABLE product_aliases IS 'Identifier and title keys that resolve to a canonical product';
+COMMENT ON TABLE product_listings IS 'Site listings matched to a canonical product with their latest price';
+COMMENT ON COLUMN product_listings.link_key IS 'Listing link without query string or fragment';
+COMMENT ON COLUMN price_history.product_id IS 'Canonical product the price belongs to';
@@ -0,0 +1,24 @@
+-- Daily exchange rate snapshots, so history can be converted at the rate of its date
+CREATE TABLE IF NOT EXISTS exchange_rate_snapshots (
+    rate_date DATE NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    rate_per_usd DECIMAL(24, 10) NOT NULL,
+    provider VARCHAR(50) NOT NULL,
+    published_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    recorded_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    PRIMARY KEY (rate_date, currency),
+    CONSTRAINT rate_per_usd_positive CHECK (rate_per_usd > 0)
+);
+
+CREATE INDEX idx_exchange_rate_snapshots_currency ON exchange_rate_snapshots(currency, rate_date DESC);
+
+-- USD per unit of weak currencies needs more than 6 decimal places (1 NGN is about 0.00065 USD)
+ALTER TABLE price_history
+ALTER COLUMN exchange_rate TYPE DECIMAL(24, 12);
+
+COMMENT ON TABLE exchange_rate_snapshots IS 'Exchange rates per USD, one row per currency and day';
+COMMENT ON COLUMN exchange_rate_snapshots.rate_date IS 'Day the rate was published for';
+COMMENT ON COLUMN exchange_rate_snapshots.rate_per_usd IS 'Units of the currency per USD';
+COMMENT ON COLUMN exchange_rate_snapshots.provider IS 'Rate provider (e.g., ecb, exchangerate_api)';
+COMMENT ON COLUMN price_history.exchange_rate IS 'USD per unit of the original currency, at the time the price was recorded';
@@ -0,0 +1,13 @@
+-- Record match confidence, source identifiers and request context with each price
+ALTER TABLE price_history
+ADD COLUMN IF NOT EXISTS match_confidence SMALLINT,
+ADD COLUMN IF NOT EXISTS source_identifiers JSONB NOT NULL DEFAULT '{}'::jsonb,
+ADD COLUMN IF NOT EXISTS request_context JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+ALTER TABLE price_history
+ADD CONSTRAINT price_history_match_confidence_range CHECK (match_confidence BETWEEN 0 AND 100);
+
+-- Add comments for documentation
+COMMENT ON COLUMN price_history.match_confidence IS 'Confidence that the listing is the searched product (0-100)';
+COMMENT ON COLUMN price_history.source_identifiers IS 'Identifiers of the product the comparison was made for';
+COMMENT ON COLUMN price_history.request_context IS 'Browsing site, target currency and ranking of the request';
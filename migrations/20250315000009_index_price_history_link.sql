@@ -0,0 +1,4 @@
+-- Index price history by normalized listing link (lowercased, without query
+-- string, fragment or trailing slash) for listing history and price insights
+CREATE INDEX IF NOT EXISTS idx_price_history_link_key
+ON price_history ((rtrim(lower(regexp_replace(product_link, '[?#].*$', '')), '/')), currency, scraped_at DESC);
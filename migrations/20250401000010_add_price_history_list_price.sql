@@ -0,0 +1,9 @@
+-- Record the reference ("was" or strike-through) price each listing advertised
+ALTER TABLE price_history
+ADD COLUMN IF NOT EXISTS list_price DECIMAL(12, 2);
+
+ALTER TABLE price_history
+ADD CONSTRAINT price_history_list_price_positive CHECK (list_price IS NULL OR list_price > 0);
+
+-- Add comments for documentation
+COMMENT ON COLUMN price_history.list_price IS 'Reference price the listing advertised, in the listing currency';
@@ -0,0 +1,18 @@
+-- Store the normalized listing link (see feedback::candidate_key) with each
+-- price instead of deriving it from product_link in SQL. The old expression
+-- dropped the whole query string and lowercased the path, so listings
+-- identified by a query parameter or a case-sensitive ID shared one key.
+ALTER TABLE price_history
+ADD COLUMN IF NOT EXISTS link_key TEXT;
+
+-- Existing rows are keyed by the backfill_link_keys binary, which computes
+-- keys with candidate_key itself (redirects, identifying query parameters)
+-- and also rekeys match_feedback and product_listings. Until it has run,
+-- rows recorded before this migration have no key and match no listing.
+DROP INDEX IF EXISTS idx_price_history_link_key;
+CREATE INDEX idx_price_history_link_key ON price_history(link_key, currency, scraped_at DESC);
+
+-- Add comments for documentation
+COMMENT ON COLUMN price_history.link_key IS 'Normalized listing link: ASIN or eBay item ID, else host, path and identifying query parameters';
+COMMENT ON COLUMN match_feedback.candidate_key IS 'Normalized candidate link (see price_history.link_key)';
+COMMENT ON COLUMN product_listings.link_key IS 'Normalized listing link (see price_history.link_key)';
@@ -0,0 +1,10 @@
+-- Prices are rounded to their currency's minor units (up to 4, e.g. CLF), so
+-- columns holding amounts in the original currency need that many decimal
+-- places. DECIMAL(12, 2) silently re-rounded BHD, KWD and TND prices to cents.
+-- USD columns keep two places.
+ALTER TABLE price_history
+ALTER COLUMN price_original TYPE DECIMAL(18, 4),
+ALTER COLUMN list_price TYPE DECIMAL(18, 4);
+
+ALTER TABLE product_listings
+ALTER COLUMN last_price TYPE DECIMAL(18, 4);
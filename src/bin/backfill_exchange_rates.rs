@@ -0,0 +1,65 @@
+//! Backfill of historical exchange rates.
+//!
+//! Loads the ECB reference rate history into `exchange_rate_snapshots`
+//! (days already snapshotted keep their live rates), then stores a USD rate
+//! and recomputed USD price on every `price_history` row recorded without one.
+//!
+//! Usage: `cargo run --bin backfill_exchange_rates -- [--since 2024-01-01] [--url eurofxref-hist.xml]`
+//!
+//! The feed defaults to `ECB_HISTORY_URL`. Rows in currencies the ECB does
+//! not publish (e.g., NGN) are filled only from live snapshots.
+
+use chrono::NaiveDate;
+use price_checker_extension::services::rate_history;
+use price_checker_extension::{db, utils, Config};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+    rustls::crypto::ring::default_provider()
+        .install_default()
+        .map_err(|_| "Failed to install rustls crypto provider")?;
+
+    let config = Config::from_env().map_err(|e| format!("Configuration error: {}", e))?;
+
+    let mut since: Option<NaiveDate> = None;
+    let mut url = config.currency.history_rates_url.clone();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--since" => {
+                let date = args.next().ok_or("--since needs a date (YYYY-MM-DD)")?;
+                since = Some(NaiveDate::parse_from_str(&date, "%Y-%m-%d")?);
+            }
+            "--url" => url = args.next().ok_or("--url needs a feed URL")?,
+            other => return Err(format!("Unknown argument: {}", other).into()),
+        }
+    }
+
+    let pool = db::create_pool(&config.database.url).await?;
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .map_err(|e| format!("Migration failed: {}", e))?;
+    let client = utils::create_http_client(
+        &config.scraper.user_agent,
+        config.scraper.request_timeout_seconds,
+    )?;
+
+    let report = rate_history::backfill(
+        &pool,
+        &client,
+        &url,
+        since,
+        config.currency.snapshot_max_age_days,
+    )
+    .await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    eprintln!(
+        "{} days loaded, {} snapshots added, {} history rows rated, {} still without a rate",
+        report.days, report.snapshots_saved, report.rows_updated, report.rows_without_rate,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Backfill of normalized listing links.
+//!
+//! Keys `price_history` rows recorded before `link_key` was stored, and
+//! rekeys `match_feedback` and `product_listings` rows written by earlier
+//! normalizations, all with `feedback::candidate_key`.
+//!
+//! Usage: `cargo run --bin backfill_link_keys -- [--batch-size 1000]`
+
+use price_checker_extension::services::feedback;
+use price_checker_extension::{db, Config};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+    rustls::crypto::ring::default_provider()
+        .install_default()
+        .map_err(|_| "Failed to install rustls crypto provider")?;
+
+    let config = Config::from_env().map_err(|e| format!("Configuration error: {}", e))?;
+
+    let mut batch_size: i64 = 1000;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--batch-size" => {
+                let size = args.next().ok_or("--batch-size needs a number of links")?;
+                batch_size = size.parse()?;
+            }
+            other => return Err(format!("Unknown argument: {}", other).into()),
+        }
+    }
+
+    let pool = db::create_pool(&config.database.url).await?;
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .map_err(|e| format!("Migration failed: {}", e))?;
+
+    let report = feedback::backfill_link_keys(&pool, batch_size).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    eprintln!(
+        "{} history rows keyed, {} feedback rows and {} listings rekeyed, {} listings kept on their old key",
+        report.history_rows, report.feedback_rows, report.listings, report.listings_kept,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+//! Offline evaluation of product matching against a labeled dataset.
+//!
+//! Runs one or two matcher profiles over labeled pairs and prints precision,
+//! recall, F1 and a per-tier confusion breakdown. With two profiles the
+//! reports are followed by a side-by-side comparison.
+//!
+//! Usage: `cargo run --bin evaluate_matching -- <pairs.jsonl> [--config a.json] [--config b.json] [--json]`
+//!
+//! Without `--config` the built-in defaults are evaluated. Profiles live in
+//! `data/matching/profiles/`.
+
+use price_checker_extension::services::evaluation::{self, MatcherProfile};
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut dataset: Option<PathBuf> = None;
+    let mut profiles = Vec::new();
+    let mut json = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                let path = PathBuf::from(args.next().ok_or("--config needs a path")?);
+                profiles.push(MatcherProfile::load(&path)?);
+            }
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                return Err(format!("Unknown argument: {}", other).into())
+            }
+            other => dataset = Some(PathBuf::from(other)),
+        }
+    }
+
+    let dataset =
+        dataset.ok_or("Usage: evaluate_matching <pairs.jsonl> [--config profile.json]...")?;
+    if profiles.len() > 2 {
+        return Err("At most two --config profiles can be compared".into());
+    }
+    if profiles.is_empty() {
+        profiles.push(MatcherProfile::default());
+    }
+
+    let pairs = evaluation::load_dataset(&dataset)?;
+    if pairs.is_empty() {
+        return Err(format!("{} contains no labeled pairs", dataset.display()).into());
+    }
+
+    let reports: Vec<_> = profiles
+        .iter()
+        .map(|profile| evaluation::evaluate(&pairs, profile))
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
+    }
+
+    for report in &reports {
+        println!("{}", evaluation::format_report(report));
+    }
+    if let [base, other] = reports.as_slice() {
+        print!("{}", evaluation::format_comparison(base, other));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Offline tuner for product matching.
+//!
+//! Loads user verdicts from `match_feedback`, derives tier weights and a
+//! confidence threshold, and prints precision/recall before and after.
+//!
+//! Usage: `cargo run --bin tune_matching -- [--output weights.json] [--limit N]`
+//!
+//! The weights file can be loaded by the server through `MATCHING_WEIGHTS_PATH`;
+//! the recommended threshold goes in `PRODUCT_MATCH_MIN_CONFIDENCE`.
+
+use price_checker_extension::services::feedback::{self, FeedbackSample};
+use price_checker_extension::{db, Config};
+
+/// Default number of feedback records to load.
+const DEFAULT_LIMIT: i64 = 100_000;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+
+    let mut output: Option<String> = None;
+    let mut limit = DEFAULT_LIMIT;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = Some(args.next().ok_or("--output needs a path")?),
+            "--limit" => limit = args.next().ok_or("--limit needs a number")?.parse()?,
+            other => return Err(format!("Unknown argument: {}", other).into()),
+        }
+    }
+
+    let config = Config::from_env().map_err(|e| format!("Configuration error: {}", e))?;
+    let pool = db::create_pool(&config.database.url).await?;
+
+    let samples = db::get_match_feedback(&pool, limit)
+        .await?
+        .into_iter()
+        .map(FeedbackSample::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if samples.is_empty() {
+        return Err("No match feedback recorded yet".into());
+    }
+
+    let report = feedback::tune(
+        &samples,
+        &config.matching,
+        config.scraper.product_match_min_confidence,
+    );
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    eprintln!(
+        "precision {:.3} -> {:.3}, recall {:.3} -> {:.3} (threshold {} -> {})",
+        report.baseline.precision,
+        report.tuned.precision,
+        report.baseline.recall,
+        report.tuned.recall,
+        report.baseline.threshold,
+        report.tuned.threshold,
+    );
+
+    if let Some(path) = output {
+        std::fs::write(&path, serde_json::to_string_pretty(&report.tier_weights)?)?;
+        eprintln!("Tier weights written to {}", path);
+    }
+
+    Ok(())
+}
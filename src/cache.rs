@@ -1,6 +1,8 @@
 //! Redis caching layer for price comparison results.
 //!
 //! Implements cache-aside pattern with TTL for performance optimization.
+//! Each cached result is also added to an index set (one per product or
+//! query), so invalidation deletes only the entries that were written.
 
 use deadpool_redis::{Config as RedisConfig, Pool, Runtime};
 use redis::AsyncCommands;
@@ -87,11 +89,20 @@ pub async fn get_cached_result(
     }
 }
 
+/// Generates the key of the set indexing the cache keys of one product or query.
+fn generate_index_key(index: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(index.as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+    format!("price_check_index:{}", hash)
+}
+
 /// Stores price comparison results in cache with TTL.
 ///
 /// # Arguments
 /// * `pool` - Redis connection pool
 /// * `search_query` - Search query used as key
+/// * `index` - Product or query the entry is indexed under (see `invalidate_cache`)
 /// * `result` - Price comparison result to cache
 /// * `ttl_seconds` - Time-to-live in seconds
 ///
@@ -101,6 +112,7 @@ pub async fn get_cached_result(
 pub async fn set_cached_result(
     pool: &Pool,
     search_query: &str,
+    index: &str,
     result: &PriceComparisonResult,
     ttl_seconds: u64,
 ) -> Result<(), AppError> {
@@ -113,8 +125,16 @@ pub async fn set_cached_result(
     let json = serde_json::to_string(result)
         .map_err(|e| AppError::Internal(format!("Failed to serialize result: {}", e)))?;
 
-    let _: () = conn
+    // The index lives as long as its newest entry
+    let index_key = generate_index_key(index);
+    let _: () = redis::pipe()
         .set_ex(&cache_key, json, ttl_seconds)
+        .ignore()
+        .sadd(&index_key, &cache_key)
+        .ignore()
+        .expire(&index_key, ttl_seconds as i64)
+        .ignore()
+        .query_async(&mut conn)
         .await
         .map_err(|e| AppError::Internal(format!("Redis SET failed: {}", e)))?;
 
@@ -128,33 +148,45 @@ pub async fn set_cached_result(
     Ok(())
 }
 
-/// Invalidates cached result for a search query.
+/// Invalidates every cached result indexed under the given products or queries.
 ///
 /// # Arguments
 /// * `pool` - Redis connection pool
-/// * `search_query` - Search query to invalidate
+/// * `indexes` - Products or queries passed to `set_cached_result`
 ///
 /// # Returns
 /// * `Ok(())` - Successfully invalidated
 /// * `Err(AppError)` - Redis error
-pub async fn invalidate_cache(pool: &Pool, search_query: &str) -> Result<(), AppError> {
+pub async fn invalidate_cache(pool: &Pool, indexes: &[String]) -> Result<(), AppError> {
+    if indexes.is_empty() {
+        return Ok(());
+    }
+
     let mut conn = pool
         .get()
         .await
         .map_err(|e| AppError::Internal(format!("Failed to get Redis connection: {}", e)))?;
 
-    let cache_key = generate_cache_key(search_query);
+    let index_keys: Vec<String> = indexes
+        .iter()
+        .map(|index| generate_index_key(index))
+        .collect();
+    let mut members = redis::pipe();
+    for index_key in &index_keys {
+        members.smembers(index_key);
+    }
+    let cache_keys: Vec<Vec<String>> = members
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| AppError::Internal(format!("Redis SMEMBERS failed: {}", e)))?;
 
+    let keys: Vec<String> = cache_keys.into_iter().flatten().chain(index_keys).collect();
     let _: () = conn
-        .del(&cache_key)
+        .del(&keys)
         .await
         .map_err(|e| AppError::Internal(format!("Redis DEL failed: {}", e)))?;
 
-    tracing::debug!(
-        search_query = %search_query,
-        cache_key = %cache_key,
-        "Cache invalidated"
-    );
+    tracing::debug!(keys = keys.len(), "Cache invalidated");
 
     Ok(())
 }
@@ -181,5 +213,6 @@ mod tests {
     fn test_cache_key_format() {
         let key = generate_cache_key("test");
         assert!(key.starts_with("price_check:"));
+        assert!(generate_index_key("test").starts_with("price_check_index:"));
     }
 }
@@ -2,9 +2,15 @@
 //!
 //! Loads configuration from environment variables using dotenvy.
 
+use rust_decimal::Decimal;
 use serde::Deserialize;
 use std::env;
 
+use crate::services::accessory::AccessoryLexicon;
+use crate::services::matching::{SimilarityWeights, TierWeights};
+use crate::services::normalize::TitleNormalizer;
+use crate::services::rate_providers::{ProviderKind, RateProvider};
+
 /// Application configuration loaded from environment variables.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
@@ -12,8 +18,12 @@ pub struct Config {
     pub database: DatabaseConfig,
     pub redis: RedisConfig,
     pub cache: CacheConfig,
+    pub history: HistoryConfig,
+    pub insights: InsightConfig,
+    pub discounts: DiscountConfig,
     pub currency: CurrencyConfig,
     pub scraper: ScraperConfig,
+    pub matching: MatchingConfig,
 }
 
 /// HTTP server configuration.
@@ -41,12 +51,140 @@ pub struct CacheConfig {
     pub ttl_seconds: u64,
 }
 
+/// Price history writer configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryConfig {
+    /// Rows waiting to be written before new rows are dropped
+    pub queue_capacity: usize,
+    /// Most rows in one insert
+    pub batch_size: usize,
+    /// Longest wait for a batch to fill before it is written
+    pub flush_interval_ms: u64,
+}
+
+/// Price insight recommendation thresholds.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InsightConfig {
+    /// Fewest recorded prices before a buy / wait recommendation is made
+    pub min_observations: i64,
+    /// Highest 90-day percentile recommended as a buy
+    pub buy_percentile: u8,
+    /// Lowest 90-day percentile recommended to wait on
+    pub wait_percentile: u8,
+}
+
+impl Default for InsightConfig {
+    fn default() -> Self {
+        Self {
+            min_observations: 5,
+            buy_percentile: 20,
+            wait_percentile: 70,
+        }
+    }
+}
+
+/// Advertised discount check configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscountConfig {
+    /// Days of recorded prices a reference price must appear in
+    pub window_days: i64,
+    /// Fewest recorded prices before a discount is flagged as inflated
+    pub min_observations: i64,
+    /// How far below the reference price a recorded price may be and still count
+    pub tolerance_percent: Decimal,
+    /// Distinct reference prices in the window before the reference is flagged as shifting
+    pub max_reference_prices: i64,
+}
+
+impl Default for DiscountConfig {
+    fn default() -> Self {
+        Self {
+            window_days: 90,
+            min_observations: 5,
+            tolerance_percent: Decimal::from(2),
+            max_reference_prices: 3,
+        }
+    }
+}
+
 /// Currency and exchange rate configuration.
 #[derive(Debug, Clone, Deserialize)]
 pub struct CurrencyConfig {
     pub base_currency: String,
-    pub api_url: String,
+    /// Exchange rate providers, highest priority first
+    pub providers: Vec<RateProvider>,
+    /// Largest deviation from the other providers' median before a rate is rejected
+    pub outlier_tolerance_percent: Decimal,
     pub cache_ttl_hours: u64,
+    /// ECB reference rate history loaded by the backfill job
+    pub history_rates_url: String,
+    /// Oldest daily snapshot used for a history date (covers weekends and holidays)
+    pub snapshot_max_age_days: i32,
+    /// Age past which a rate is reported as stale (covers ECB weekends)
+    pub stale_after_hours: i64,
+    /// Minutes between background refreshes (0 disables them); keep below the cache TTL
+    pub refresh_interval_minutes: u64,
+    /// First retry delay after a failed refresh, doubled up to the refresh interval
+    pub refresh_retry_seconds: u64,
+    /// Smallest move between two fetches that is reported as a rate change
+    pub change_alert_percent: Decimal,
+}
+
+/// Product matching configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatchingConfig {
+    /// Highest confidence an accessory listing can get for a non-accessory source
+    pub accessory_confidence_cap: u8,
+    /// Confidence removed per conflicting spec (e.g., 128GB vs 256GB)
+    pub spec_mismatch_penalty: u8,
+    /// Per-locale accessory word lists
+    pub accessory_lexicon: AccessoryLexicon,
+    /// Base confidence of each matching tier
+    pub tier_weights: TierWeights,
+    /// Weights of the fuzzy title similarity components
+    pub similarity_weights: SimilarityWeights,
+    /// Brand, unit and promo dictionaries applied to titles before matching
+    pub normalizer: TitleNormalizer,
+    /// Confidence adjustment from perceptual image similarity
+    pub image: ImageMatchConfig,
+}
+
+/// Confidence adjustment from the Hamming distance between image hashes.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ImageMatchConfig {
+    /// Largest distance treated as the same picture
+    pub similar_distance: u32,
+    /// Smallest distance treated as a different picture
+    pub different_distance: u32,
+    /// Confidence added for the same picture
+    pub boost: u8,
+    /// Confidence removed for a different picture
+    pub penalty: u8,
+}
+
+impl Default for ImageMatchConfig {
+    fn default() -> Self {
+        Self {
+            similar_distance: 10,
+            different_distance: 24,
+            boost: 10,
+            penalty: 15,
+        }
+    }
+}
+
+impl Default for MatchingConfig {
+    fn default() -> Self {
+        Self {
+            accessory_confidence_cap: 50,
+            spec_mismatch_penalty: 25,
+            accessory_lexicon: AccessoryLexicon::default(),
+            tier_weights: TierWeights::default(),
+            similarity_weights: SimilarityWeights::default(),
+            normalizer: TitleNormalizer::default(),
+            image: ImageMatchConfig::default(),
+        }
+    }
 }
 
 /// Scraper behavior configuration.
@@ -63,6 +201,47 @@ pub struct ScraperConfig {
     pub jumia: JumiaConfig,
     pub konga: KongaConfig,
     pub use_mock_data: bool,
+    pub detail_verification: DetailVerificationConfig,
+    pub image_hashing: ImageHashConfig,
+}
+
+/// Perceptual hashing of listing images.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageHashConfig {
+    /// Most uncached images downloaded per comparison (0 disables image matching)
+    pub max_fetches: usize,
+    /// How long an image URL's hash is cached
+    pub cache_ttl_seconds: u64,
+}
+
+impl Default for ImageHashConfig {
+    fn default() -> Self {
+        Self {
+            max_fetches: 8,
+            cache_ttl_seconds: 7 * 24 * 3600,
+        }
+    }
+}
+
+/// Detail-page verification of borderline search matches.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DetailVerificationConfig {
+    /// Most detail pages fetched per comparison (0 disables verification)
+    pub max_fetches: usize,
+    /// Lowest confidence worth verifying
+    pub min_confidence: u8,
+    /// Highest confidence still worth verifying
+    pub max_confidence: u8,
+}
+
+impl Default for DetailVerificationConfig {
+    fn default() -> Self {
+        Self {
+            max_fetches: 2,
+            min_confidence: 50,
+            max_confidence: 90,
+        }
+    }
 }
 
 /// eBay API configuration.
@@ -129,15 +308,86 @@ impl Config {
                     .parse()
                     .map_err(|e| format!("Invalid CACHE_TTL_SECONDS: {}", e))?,
             },
+            history: HistoryConfig {
+                queue_capacity: env::var("PRICE_HISTORY_QUEUE_CAPACITY")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid PRICE_HISTORY_QUEUE_CAPACITY: {}", e))?,
+                batch_size: env::var("PRICE_HISTORY_BATCH_SIZE")
+                    .unwrap_or_else(|_| "200".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid PRICE_HISTORY_BATCH_SIZE: {}", e))?,
+                flush_interval_ms: env::var("PRICE_HISTORY_FLUSH_INTERVAL_MS")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid PRICE_HISTORY_FLUSH_INTERVAL_MS: {}", e))?,
+            },
+            insights: InsightConfig {
+                min_observations: env::var("PRICE_INSIGHT_MIN_OBSERVATIONS")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid PRICE_INSIGHT_MIN_OBSERVATIONS: {}", e))?,
+                buy_percentile: env::var("PRICE_INSIGHT_BUY_PERCENTILE")
+                    .unwrap_or_else(|_| "20".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid PRICE_INSIGHT_BUY_PERCENTILE: {}", e))?,
+                wait_percentile: env::var("PRICE_INSIGHT_WAIT_PERCENTILE")
+                    .unwrap_or_else(|_| "70".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid PRICE_INSIGHT_WAIT_PERCENTILE: {}", e))?,
+            },
+            discounts: DiscountConfig {
+                window_days: env::var("DISCOUNT_WINDOW_DAYS")
+                    .unwrap_or_else(|_| "90".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid DISCOUNT_WINDOW_DAYS: {}", e))?,
+                min_observations: env::var("DISCOUNT_MIN_OBSERVATIONS")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid DISCOUNT_MIN_OBSERVATIONS: {}", e))?,
+                tolerance_percent: env::var("DISCOUNT_TOLERANCE_PERCENT")
+                    .unwrap_or_else(|_| "2".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid DISCOUNT_TOLERANCE_PERCENT: {}", e))?,
+                max_reference_prices: env::var("DISCOUNT_MAX_REFERENCE_PRICES")
+                    .unwrap_or_else(|_| "3".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid DISCOUNT_MAX_REFERENCE_PRICES: {}", e))?,
+            },
             currency: CurrencyConfig {
                 base_currency: env::var("BASE_CURRENCY").unwrap_or_else(|_| "USD".to_string()),
-                api_url: env::var("EXCHANGE_RATE_API_URL").unwrap_or_else(|_| {
-                    "https://api.exchangerate-api.com/v4/latest/USD".to_string()
-                }),
+                providers: load_rate_providers()?,
+                outlier_tolerance_percent: env::var("EXCHANGE_RATE_OUTLIER_PERCENT")
+                    .unwrap_or_else(|_| "2".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid EXCHANGE_RATE_OUTLIER_PERCENT: {}", e))?,
                 cache_ttl_hours: env::var("EXCHANGE_RATE_CACHE_TTL_HOURS")
                     .unwrap_or_else(|_| "24".to_string())
                     .parse()
                     .map_err(|e| format!("Invalid EXCHANGE_RATE_CACHE_TTL_HOURS: {}", e))?,
+                history_rates_url: env::var("ECB_HISTORY_URL").unwrap_or_else(|_| {
+                    "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-hist.xml".to_string()
+                }),
+                snapshot_max_age_days: env::var("RATE_SNAPSHOT_MAX_AGE_DAYS")
+                    .unwrap_or_else(|_| "7".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid RATE_SNAPSHOT_MAX_AGE_DAYS: {}", e))?,
+                stale_after_hours: env::var("RATE_STALE_AFTER_HOURS")
+                    .unwrap_or_else(|_| "72".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid RATE_STALE_AFTER_HOURS: {}", e))?,
+                refresh_interval_minutes: env::var("RATE_REFRESH_INTERVAL_MINUTES")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid RATE_REFRESH_INTERVAL_MINUTES: {}", e))?,
+                refresh_retry_seconds: env::var("RATE_REFRESH_RETRY_SECONDS")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid RATE_REFRESH_RETRY_SECONDS: {}", e))?,
+                change_alert_percent: env::var("RATE_CHANGE_ALERT_PERCENT")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid RATE_CHANGE_ALERT_PERCENT: {}", e))?,
             },
             scraper: ScraperConfig {
                 user_agent: env::var("USER_AGENT")
@@ -198,7 +448,140 @@ impl Config {
                     .unwrap_or_else(|_| "true".to_string())
                     .parse()
                     .unwrap_or(true),
+                detail_verification: DetailVerificationConfig {
+                    max_fetches: env::var("DETAIL_FETCH_BUDGET")
+                        .unwrap_or_else(|_| "2".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid DETAIL_FETCH_BUDGET: {}", e))?,
+                    min_confidence: env::var("DETAIL_VERIFY_MIN_CONFIDENCE")
+                        .unwrap_or_else(|_| "50".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid DETAIL_VERIFY_MIN_CONFIDENCE: {}", e))?,
+                    max_confidence: env::var("DETAIL_VERIFY_MAX_CONFIDENCE")
+                        .unwrap_or_else(|_| "90".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid DETAIL_VERIFY_MAX_CONFIDENCE: {}", e))?,
+                },
+                image_hashing: ImageHashConfig {
+                    max_fetches: env::var("IMAGE_HASH_MAX_FETCHES")
+                        .unwrap_or_else(|_| "8".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid IMAGE_HASH_MAX_FETCHES: {}", e))?,
+                    cache_ttl_seconds: env::var("IMAGE_HASH_CACHE_TTL_SECONDS")
+                        .unwrap_or_else(|_| "604800".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid IMAGE_HASH_CACHE_TTL_SECONDS: {}", e))?,
+                },
+            },
+            matching: MatchingConfig {
+                accessory_confidence_cap: env::var("ACCESSORY_CONFIDENCE_CAP")
+                    .unwrap_or_else(|_| "50".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid ACCESSORY_CONFIDENCE_CAP: {}", e))?,
+                spec_mismatch_penalty: env::var("SPEC_MISMATCH_PENALTY")
+                    .unwrap_or_else(|_| "25".to_string())
+                    .parse()
+                    .map_err(|e| format!("Invalid SPEC_MISMATCH_PENALTY: {}", e))?,
+                accessory_lexicon: load_accessory_lexicon(),
+                tier_weights: load_tier_weights()?,
+                similarity_weights: SimilarityWeights::default(),
+                normalizer: load_title_normalizer()?,
+                image: ImageMatchConfig {
+                    similar_distance: env::var("IMAGE_SIMILAR_DISTANCE")
+                        .unwrap_or_else(|_| "10".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid IMAGE_SIMILAR_DISTANCE: {}", e))?,
+                    different_distance: env::var("IMAGE_DIFFERENT_DISTANCE")
+                        .unwrap_or_else(|_| "24".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid IMAGE_DIFFERENT_DISTANCE: {}", e))?,
+                    boost: env::var("IMAGE_MATCH_BOOST")
+                        .unwrap_or_else(|_| "10".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid IMAGE_MATCH_BOOST: {}", e))?,
+                    penalty: env::var("IMAGE_MISMATCH_PENALTY")
+                        .unwrap_or_else(|_| "15".to_string())
+                        .parse()
+                        .map_err(|e| format!("Invalid IMAGE_MISMATCH_PENALTY: {}", e))?,
+                },
             },
         })
     }
 }
+
+/// Builds the accessory lexicon from defaults plus `ACCESSORY_TERMS_<LOCALE>` variables.
+///
+/// Each variable holds comma-separated terms, e.g. `ACCESSORY_TERMS_FR=film protecteur,coque`.
+fn load_accessory_lexicon() -> AccessoryLexicon {
+    let mut lexicon = AccessoryLexicon::default();
+
+    for (key, value) in env::vars() {
+        if let Some(locale) = key.strip_prefix("ACCESSORY_TERMS_") {
+            lexicon.extend_terms(locale, value.split(',').map(str::to_string));
+        }
+    }
+
+    lexicon
+}
+
+/// Builds the exchange rate provider chain from `EXCHANGE_RATE_PROVIDERS`.
+///
+/// The variable lists provider names in priority order (default
+/// `exchangerate_api,open_exchange_rates,ecb`). Each provider reads its URL
+/// from `EXCHANGE_RATE_API_URL`, `OPEN_EXCHANGE_RATES_URL` (which carries the
+/// `app_id`) or `ECB_RATES_URL`; Open Exchange Rates has no default URL and is
+/// skipped until one is set.
+fn load_rate_providers() -> Result<Vec<RateProvider>, String> {
+    let names = env::var("EXCHANGE_RATE_PROVIDERS")
+        .unwrap_or_else(|_| "exchangerate_api,open_exchange_rates,ecb".to_string());
+
+    let mut providers = Vec::new();
+    for name in names.split(',').filter(|name| !name.trim().is_empty()) {
+        let kind: ProviderKind = name.parse()?;
+        let url = match kind {
+            ProviderKind::ExchangeRateApi => {
+                Some(env::var("EXCHANGE_RATE_API_URL").unwrap_or_else(|_| {
+                    "https://api.exchangerate-api.com/v4/latest/USD".to_string()
+                }))
+            }
+            ProviderKind::OpenExchangeRates => env::var("OPEN_EXCHANGE_RATES_URL").ok(),
+            ProviderKind::Ecb => Some(env::var("ECB_RATES_URL").unwrap_or_else(|_| {
+                "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml".to_string()
+            })),
+        };
+        if let Some(url) = url {
+            providers.push(RateProvider { kind, url });
+        }
+    }
+
+    Ok(providers)
+}
+
+/// Loads tuned tier weights from the JSON file named by `MATCHING_WEIGHTS_PATH`.
+///
+/// The file is the `tier_weights` output of the `tune_matching` binary.
+/// Missing fields keep their defaults; without the variable all defaults apply.
+fn load_tier_weights() -> Result<TierWeights, String> {
+    let Ok(path) = env::var("MATCHING_WEIGHTS_PATH") else {
+        return Ok(TierWeights::default());
+    };
+
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Invalid MATCHING_WEIGHTS_PATH {}: {}", path, e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid matching weights in {}: {}", path, e))
+}
+
+/// Loads the title normalization dictionary named by `TITLE_NORMALIZATION_PATH`.
+///
+/// The file replaces the built-in `data/matching/normalization.json`, so it
+/// should start from a copy of it. Without the variable the built-in applies.
+fn load_title_normalizer() -> Result<TitleNormalizer, String> {
+    let Ok(path) = env::var("TITLE_NORMALIZATION_PATH") else {
+        return Ok(TitleNormalizer::default());
+    };
+
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Invalid TITLE_NORMALIZATION_PATH {}: {}", path, e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Invalid normalization dictionary in {}: {}", path, e))
+}
@@ -3,12 +3,22 @@
 //! Manages PostgreSQL connection pool and provides data access methods
 //! for price history, metrics, and scraper status tracking.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
-use sqlx::{PgPool, postgres::PgPoolOptions};
+use serde::Serialize;
+use sqlx::{
+    FromRow, PgPool, Postgres, QueryBuilder, Row,
+    postgres::{PgPoolOptions, PgRow},
+    types::Json,
+};
+use std::collections::HashSet;
 use uuid::Uuid;
 
-use crate::{AppError, SitePrice};
+use crate::services::history_writer::HistoryEntry;
+use crate::services::money::Money;
+use crate::services::price_history::HistoryKey;
+use crate::services::rate_history::HistoryConversion;
+use crate::{AppError, MatchFeedbackRequest, ProductIdentifiers, SitePrice};
 
 /// Creates a PostgreSQL connection pool.
 ///
@@ -26,44 +36,57 @@ pub async fn create_pool(database_url: &str) -> Result<PgPool, AppError> {
         .map_err(|e| AppError::Internal(format!("Database connection failed: {}", e)))
 }
 
-/// Saves a price record to the price_history table.
+/// Saves a batch of listings to the price_history table in one insert.
 ///
 /// # Arguments
 /// * `pool` - Database connection pool
-/// * `price` - Price data to save
-/// * `search_query` - Original search query
+/// * `entries` - Listings with their product, exchange rate and request context
 ///
 /// # Returns
-/// * `Ok(Uuid)` - ID of inserted record
+/// * `Ok(u64)` - Number of inserted rows
 /// * `Err(AppError)` - Database error
-pub async fn save_price_history(
+pub async fn save_price_history_batch(
     pool: &PgPool,
-    price: &SitePrice,
-    search_query: &str,
-) -> Result<Uuid, AppError> {
-    let record: (Uuid,) = sqlx::query_as(
+    entries: &[HistoryEntry],
+) -> Result<u64, AppError> {
+    if entries.is_empty() {
+        return Ok(0);
+    }
+
+    let mut query = QueryBuilder::<Postgres>::new(
         r#"
         INSERT INTO price_history (
-            site, product_title, price_original, currency, price_usd,
-            product_link, image_url, search_query
+            site, product_title, price_original, currency, price_usd, exchange_rate,
+            product_link, image_url, search_query, product_id, match_confidence,
+            source_identifiers, request_context, list_price, link_key
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        RETURNING id
         "#,
-    )
-    .bind(&price.site)
-    .bind(&price.title)
-    .bind(&price.price)
-    .bind(&price.currency)
-    .bind(&price.price_usd)
-    .bind(&price.link)
-    .bind(&price.image)
-    .bind(search_query)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| AppError::Internal(format!("Failed to save price history: {}", e)))?;
+    );
+    query.push_values(entries, |mut row, entry| {
+        row.push_bind(&entry.price.site)
+            .push_bind(&entry.price.title)
+            .push_bind(entry.price.price.amount())
+            .push_bind(entry.price.price.currency().code())
+            .push_bind(entry.price.price_usd)
+            .push_bind(entry.exchange_rate)
+            .push_bind(&entry.price.link)
+            .push_bind(&entry.price.image)
+            .push_bind(&entry.search_query)
+            .push_bind(entry.product_id)
+            .push_bind(entry.price.match_confidence.map(i16::from))
+            .push_bind(Json(&entry.identifiers))
+            .push_bind(Json(&entry.context))
+            .push_bind(entry.price.list_price.map(|list_price| list_price.amount()))
+            .push_bind(&entry.link_key);
+    });
 
-    Ok(record.0)
+    let result = query
+        .build()
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to save price history: {}", e)))?;
+
+    Ok(result.rows_affected())
 }
 
 /// Records API metrics for performance monitoring.
@@ -142,32 +165,83 @@ pub async fn record_scraper_status(
     Ok(())
 }
 
-/// Retrieves recent price history for a search query.
+/// Retrieves price history for a product, search query or listing in a time range.
 ///
 /// # Arguments
 /// * `pool` - Database connection pool
-/// * `search_query` - Search query to filter by
+/// * `key` - Product ID, exact search query or normalized listing link
+/// * `from` - Start of the range (inclusive)
+/// * `to` - End of the range (exclusive)
 /// * `limit` - Maximum number of results
 ///
 /// # Returns
-/// * `Ok(Vec<PriceHistoryRecord>)` - List of historical prices
+/// * `Ok(Vec<PriceHistoryRecord>)` - Historical prices, newest first
 /// * `Err(AppError)` - Database error
 pub async fn get_price_history(
     pool: &PgPool,
-    search_query: &str,
+    key: &HistoryKey,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<PriceHistoryRecord>, AppError> {
+    let mut query = QueryBuilder::<Postgres>::new(
+        r#"
+        SELECT id, site, product_title, price_original, currency, price_usd,
+               exchange_rate, product_link, image_url, search_query, product_id,
+               scraped_at
+        FROM price_history
+        WHERE scraped_at >= "#,
+    );
+    query.push_bind(from).push(" AND scraped_at < ").push_bind(to);
+    match key {
+        HistoryKey::Product(product_id) => {
+            query.push(" AND product_id = ").push_bind(*product_id);
+        }
+        HistoryKey::Query(search_query) => {
+            query.push(" AND search_query = ").push_bind(search_query);
+        }
+        HistoryKey::Listing(link_key) => {
+            query.push(" AND link_key = ").push_bind(link_key);
+        }
+    }
+    query.push(" ORDER BY scraped_at DESC LIMIT ").push_bind(limit);
+
+    let records = query
+        .build_query_as::<PriceHistoryRecord>()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to fetch price history: {}", e)))?;
+
+    Ok(records)
+}
+
+/// Retrieves recent price history for a canonical product.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `product_id` - Canonical product ID
+/// * `limit` - Maximum number of results
+///
+/// # Returns
+/// * `Ok(Vec<PriceHistoryRecord>)` - List of historical prices, newest first
+/// * `Err(AppError)` - Database error
+pub async fn get_product_price_history(
+    pool: &PgPool,
+    product_id: Uuid,
     limit: i64,
 ) -> Result<Vec<PriceHistoryRecord>, AppError> {
     let records = sqlx::query_as::<_, PriceHistoryRecord>(
         r#"
         SELECT id, site, product_title, price_original, currency, price_usd,
-               product_link, image_url, search_query, scraped_at
+               exchange_rate, product_link, image_url, search_query, product_id,
+               scraped_at
         FROM price_history
-        WHERE search_query = $1
+        WHERE product_id = $1
         ORDER BY scraped_at DESC
         LIMIT $2
         "#,
     )
-    .bind(search_query)
+    .bind(product_id)
     .bind(limit)
     .fetch_all(pool)
     .await
@@ -176,17 +250,1064 @@ pub async fn get_price_history(
     Ok(records)
 }
 
+/// Summarizes the recorded prices of listings, for price insights.
+///
+/// Rows are matched on the normalized link (see `feedback::candidate_key`)
+/// and the listing's currency.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `listings` - Normalized link, currency code and current price of each listing
+/// * `now` - End of the 30- and 90-day windows
+///
+/// # Returns
+/// * `Ok(Vec<ListingPriceStats>)` - Stats of the listings with recorded prices
+/// * `Err(AppError)` - Database error
+pub async fn get_listing_price_stats(
+    pool: &PgPool,
+    listings: &[(String, String, Decimal)],
+    now: DateTime<Utc>,
+) -> Result<Vec<ListingPriceStats>, AppError> {
+    if listings.is_empty() {
+        return Ok(Vec::new());
+    }
+    let link_keys: Vec<&str> = listings.iter().map(|(key, _, _)| key.as_str()).collect();
+    let currencies: Vec<&str> = listings.iter().map(|(_, code, _)| code.as_str()).collect();
+    let prices: Vec<Decimal> = listings.iter().map(|(_, _, price)| *price).collect();
+
+    let stats = sqlx::query_as::<_, ListingPriceStats>(
+        r#"
+        SELECT k.link_key,
+               MIN(ph.price_original) AS all_time_low,
+               MAX(ph.price_original) AS all_time_high,
+               COUNT(*) AS observations,
+               MIN(ph.scraped_at) AS first_seen,
+               MAX(ph.scraped_at) FILTER (WHERE ph.price_original < k.price) AS last_lower_at,
+               MIN(ph.price_original) FILTER (WHERE ph.scraped_at >= $4) AS low_30d,
+               COUNT(*) FILTER (WHERE ph.scraped_at >= $4 AND ph.price_original < k.price) AS lower_30d,
+               COUNT(*) FILTER (WHERE ph.scraped_at >= $4) AS observations_30d,
+               MIN(ph.price_original) FILTER (WHERE ph.scraped_at >= $5) AS low_90d,
+               COUNT(*) FILTER (WHERE ph.scraped_at >= $5 AND ph.price_original < k.price) AS lower_90d,
+               COUNT(*) FILTER (WHERE ph.scraped_at >= $5) AS observations_90d
+        FROM UNNEST($1::text[], $2::text[], $3::numeric[]) AS k(link_key, currency, price)
+        JOIN price_history ph
+          ON ph.link_key = k.link_key
+         AND ph.currency = k.currency
+        GROUP BY k.link_key
+        "#,
+    )
+    .bind(&link_keys)
+    .bind(&currencies)
+    .bind(&prices)
+    .bind(now - chrono::Duration::days(30))
+    .bind(now - chrono::Duration::days(90))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to fetch listing price stats: {}", e)))?;
+
+    Ok(stats)
+}
+
+/// Recorded prices of one listing, in its currency.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ListingPriceStats {
+    /// Normalized listing link
+    pub link_key: String,
+    pub all_time_low: Decimal,
+    pub all_time_high: Decimal,
+    pub observations: i64,
+    pub first_seen: DateTime<Utc>,
+    /// Latest time the listing was cheaper than its current price
+    pub last_lower_at: Option<DateTime<Utc>>,
+    pub low_30d: Option<Decimal>,
+    /// Prices in the last 30 days below the current price
+    pub lower_30d: i64,
+    pub observations_30d: i64,
+    pub low_90d: Option<Decimal>,
+    /// Prices in the last 90 days below the current price
+    pub lower_90d: i64,
+    pub observations_90d: i64,
+}
+
+/// Summarizes the prices recorded for listings since a time, to check advertised discounts.
+///
+/// Rows are matched on the normalized link (see `feedback::candidate_key`)
+/// and the listing's currency.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `listings` - Normalized link, currency code and advertised reference price of each listing
+/// * `since` - Start of the window
+///
+/// # Returns
+/// * `Ok(Vec<ReferencePriceStats>)` - Stats of the listings with recorded prices
+/// * `Err(AppError)` - Database error
+pub async fn get_reference_price_stats(
+    pool: &PgPool,
+    listings: &[(String, String, Decimal)],
+    since: DateTime<Utc>,
+) -> Result<Vec<ReferencePriceStats>, AppError> {
+    let link_keys: Vec<&str> = listings.iter().map(|(key, _, _)| key.as_str()).collect();
+    let currencies: Vec<&str> = listings.iter().map(|(_, code, _)| code.as_str()).collect();
+    let list_prices: Vec<Decimal> = listings.iter().map(|(_, _, price)| *price).collect();
+
+    let stats = sqlx::query_as::<_, ReferencePriceStats>(
+        r#"
+        SELECT k.link_key,
+               COUNT(*) AS observations,
+               MAX(ph.price_original) AS observed_max,
+               PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY ph.price_original)::numeric AS observed_median,
+               COUNT(DISTINCT ph.list_price) FILTER (WHERE ph.list_price <> k.list_price) + 1
+                   AS reference_prices
+        FROM UNNEST($1::text[], $2::text[], $3::numeric[]) AS k(link_key, currency, list_price)
+        JOIN price_history ph
+          ON ph.link_key = k.link_key
+         AND ph.currency = k.currency
+        WHERE ph.scraped_at >= $4
+        GROUP BY k.link_key
+        "#,
+    )
+    .bind(&link_keys)
+    .bind(&currencies)
+    .bind(&list_prices)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to fetch reference price stats: {}", e)))?;
+
+    Ok(stats)
+}
+
+/// Prices recorded for one listing in a window, in its currency.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReferencePriceStats {
+    /// Normalized listing link
+    pub link_key: String,
+    pub observations: i64,
+    pub observed_max: Option<Decimal>,
+    pub observed_median: Option<Decimal>,
+    /// Distinct reference prices advertised, the current one included
+    pub reference_prices: i64,
+}
+
 /// Price history record from database.
-#[derive(Debug, sqlx::FromRow)]
+#[derive(Debug, Serialize)]
 pub struct PriceHistoryRecord {
     pub id: Uuid,
     pub site: String,
     pub product_title: String,
-    pub price_original: Decimal,
-    pub currency: String,
+    /// Recorded price (sent as `price_original` and `currency`)
+    #[serde(flatten, serialize_with = "recorded_price::serialize")]
+    pub price: Money,
     pub price_usd: Decimal,
+    /// USD per unit of `currency` when the price was recorded
+    pub exchange_rate: Option<Decimal>,
     pub product_link: String,
     pub image_url: Option<String>,
     pub search_query: String,
+    pub product_id: Option<Uuid>,
     pub scraped_at: DateTime<Utc>,
+    /// Price in the requested currency, set by the history endpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted: Option<HistoryConversion>,
+}
+
+impl<'r> FromRow<'r, PgRow> for PriceHistoryRecord {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            site: row.try_get("site")?,
+            product_title: row.try_get("product_title")?,
+            price: Money::new(row.try_get("price_original")?, row.try_get("currency")?),
+            price_usd: row.try_get("price_usd")?,
+            exchange_rate: row.try_get("exchange_rate")?,
+            product_link: row.try_get("product_link")?,
+            image_url: row.try_get("image_url")?,
+            search_query: row.try_get("search_query")?,
+            product_id: row.try_get("product_id")?,
+            scraped_at: row.try_get("scraped_at")?,
+            converted: None,
+        })
+    }
+}
+
+/// Wire format of `PriceHistoryRecord::price`: `price_original` and `currency` fields.
+mod recorded_price {
+    use super::*;
+    use crate::services::currency::Currency;
+    use serde::Serializer;
+
+    #[derive(Serialize)]
+    struct Fields {
+        price_original: Decimal,
+        currency: Currency,
+    }
+
+    pub fn serialize<S: Serializer>(money: &Money, serializer: S) -> Result<S::Ok, S::Error> {
+        Fields {
+            price_original: money.amount(),
+            currency: money.currency(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Exchange rate of one currency on one day.
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct RateSnapshot {
+    pub rate_date: NaiveDate,
+    pub currency: String,
+    /// Units of `currency` per USD
+    pub rate_per_usd: Decimal,
+    pub provider: String,
+    pub published_at: DateTime<Utc>,
+}
+
+/// Saves daily exchange rate snapshots.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `snapshots` - Rates to save
+/// * `overwrite` - Replace snapshots already saved for the same day and currency
+///   (live rates do; backfilled rates never replace them)
+///
+/// # Returns
+/// * `Ok(u64)` - Number of snapshots written
+/// * `Err(AppError)` - Database error
+pub async fn save_rate_snapshots(
+    pool: &PgPool,
+    snapshots: &[RateSnapshot],
+    overwrite: bool,
+) -> Result<u64, AppError> {
+    let dates: Vec<NaiveDate> = snapshots.iter().map(|s| s.rate_date).collect();
+    let currencies: Vec<&str> = snapshots.iter().map(|s| s.currency.as_str()).collect();
+    let rates: Vec<Decimal> = snapshots.iter().map(|s| s.rate_per_usd).collect();
+    let providers: Vec<&str> = snapshots.iter().map(|s| s.provider.as_str()).collect();
+    let published: Vec<DateTime<Utc>> = snapshots.iter().map(|s| s.published_at).collect();
+
+    let on_conflict = if overwrite {
+        r#"
+        ON CONFLICT (rate_date, currency) DO UPDATE SET
+            rate_per_usd = EXCLUDED.rate_per_usd,
+            provider = EXCLUDED.provider,
+            published_at = EXCLUDED.published_at,
+            recorded_at = NOW()
+        "#
+    } else {
+        "ON CONFLICT (rate_date, currency) DO NOTHING"
+    };
+    let query = format!(
+        r#"
+        INSERT INTO exchange_rate_snapshots (rate_date, currency, rate_per_usd, provider, published_at)
+        SELECT * FROM UNNEST($1::date[], $2::varchar[], $3::numeric[], $4::varchar[], $5::timestamptz[])
+        {}
+        "#,
+        on_conflict
+    );
+
+    let result = sqlx::query(&query)
+        .bind(dates)
+        .bind(currencies)
+        .bind(rates)
+        .bind(providers)
+        .bind(published)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to save rate snapshots: {}", e)))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Retrieves exchange rate snapshots of some currencies over a date range.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `currencies` - Currency codes
+/// * `from` - First day (inclusive)
+/// * `to` - Last day (inclusive)
+///
+/// # Returns
+/// * `Ok(Vec<RateSnapshot>)` - Snapshots, oldest first
+/// * `Err(AppError)` - Database error
+pub async fn get_rate_snapshots(
+    pool: &PgPool,
+    currencies: &[String],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<RateSnapshot>, AppError> {
+    sqlx::query_as::<_, RateSnapshot>(
+        r#"
+        SELECT rate_date, currency, rate_per_usd, provider, published_at
+        FROM exchange_rate_snapshots
+        WHERE currency = ANY($1) AND rate_date BETWEEN $2 AND $3
+        ORDER BY rate_date
+        "#,
+    )
+    .bind(currencies)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to fetch rate snapshots: {}", e)))
+}
+
+/// Fills in the exchange rate of history rows recorded without one.
+///
+/// Each row takes the latest snapshot of its currency at most
+/// `max_age_days` before its scrape date, and its USD price is recomputed
+/// with that rate. USD rows get a rate of 1; rows without a recent enough
+/// snapshot are left unchanged.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `max_age_days` - Oldest snapshot accepted for a row, in days
+///
+/// # Returns
+/// * `Ok(u64)` - Number of rows updated
+/// * `Err(AppError)` - Database error
+pub async fn backfill_history_exchange_rates(
+    pool: &PgPool,
+    max_age_days: i32,
+) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE price_history ph
+        SET exchange_rate = rated.usd_rate,
+            price_usd = ROUND(ph.price_original * rated.usd_rate, 2)
+        FROM (
+            SELECT p.id,
+                   CASE WHEN p.currency = 'USD' THEN 1 ELSE (
+                       SELECT 1 / s.rate_per_usd
+                       FROM exchange_rate_snapshots s
+                       WHERE s.currency = p.currency
+                         AND s.rate_date <= (p.scraped_at AT TIME ZONE 'UTC')::date
+                         AND s.rate_date >= (p.scraped_at AT TIME ZONE 'UTC')::date - $1
+                       ORDER BY s.rate_date DESC
+                       LIMIT 1
+                   ) END AS usd_rate
+            FROM price_history p
+            WHERE p.exchange_rate IS NULL
+        ) rated
+        WHERE ph.id = rated.id AND rated.usd_rate IS NOT NULL
+        "#,
+    )
+    .bind(max_age_days)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to backfill exchange rates: {}", e)))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Counts history rows that have no exchange rate.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+///
+/// # Returns
+/// * `Ok(i64)` - Number of rows without a rate
+/// * `Err(AppError)` - Database error
+pub async fn count_history_without_exchange_rate(pool: &PgPool) -> Result<i64, AppError> {
+    let count: (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM price_history WHERE exchange_rate IS NULL")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count price history: {}", e)))?;
+
+    Ok(count.0)
+}
+
+/// Retrieves listing links of history rows recorded without a link key.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `limit` - Maximum number of links
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - Distinct product links
+/// * `Err(AppError)` - Database error
+pub async fn get_history_links_without_key(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<String>, AppError> {
+    let links: Vec<(String,)> = sqlx::query_as(
+        r#"
+        SELECT DISTINCT product_link
+        FROM price_history
+        WHERE link_key IS NULL
+        ORDER BY product_link
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to fetch price history links: {}", e)))?;
+
+    Ok(links.into_iter().map(|(link,)| link).collect())
+}
+
+/// Sets the link key of history rows recorded without one.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `keys` - Product link and its normalized key
+///
+/// # Returns
+/// * `Ok(u64)` - Number of rows updated
+/// * `Err(AppError)` - Database error
+pub async fn set_history_link_keys(
+    pool: &PgPool,
+    keys: &[(String, String)],
+) -> Result<u64, AppError> {
+    let (links, link_keys): (Vec<&str>, Vec<&str>) = keys
+        .iter()
+        .map(|(link, key)| (link.as_str(), key.as_str()))
+        .unzip();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE price_history ph
+        SET link_key = k.link_key
+        FROM UNNEST($1::text[], $2::text[]) AS k(link, link_key)
+        WHERE ph.product_link = k.link AND ph.link_key IS NULL
+        "#,
+    )
+    .bind(&links)
+    .bind(&link_keys)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to update price history: {}", e)))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Retrieves the distinct candidate links of match feedback.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - Candidate links
+/// * `Err(AppError)` - Database error
+pub async fn get_feedback_candidate_links(pool: &PgPool) -> Result<Vec<String>, AppError> {
+    let links: Vec<(String,)> =
+        sqlx::query_as("SELECT DISTINCT candidate_link FROM match_feedback")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch feedback links: {}", e)))?;
+
+    Ok(links.into_iter().map(|(link,)| link).collect())
+}
+
+/// Replaces the candidate key of match feedback where it differs.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `keys` - Candidate link and its normalized key
+///
+/// # Returns
+/// * `Ok(u64)` - Number of rows updated
+/// * `Err(AppError)` - Database error
+pub async fn set_feedback_candidate_keys(
+    pool: &PgPool,
+    keys: &[(String, String)],
+) -> Result<u64, AppError> {
+    let (links, candidate_keys): (Vec<&str>, Vec<&str>) = keys
+        .iter()
+        .map(|(link, key)| (link.as_str(), key.as_str()))
+        .unzip();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE match_feedback mf
+        SET candidate_key = k.candidate_key
+        FROM UNNEST($1::text[], $2::text[]) AS k(link, candidate_key)
+        WHERE mf.candidate_link = k.link AND mf.candidate_key <> k.candidate_key
+        "#,
+    )
+    .bind(&links)
+    .bind(&candidate_keys)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to update match feedback: {}", e)))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Retrieves the links of all catalog listings.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - Listing links
+/// * `Err(AppError)` - Database error
+pub async fn get_product_listing_links(pool: &PgPool) -> Result<Vec<String>, AppError> {
+    let links: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT link FROM product_listings")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to fetch product listing links: {}", e)))?;
+
+    Ok(links.into_iter().map(|(link,)| link).collect())
+}
+
+/// Replaces the link key of catalog listings where it differs.
+///
+/// Link keys are unique, so a listing whose new key another listing already
+/// holds keeps its old key.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `keys` - Listing link and its normalized key
+///
+/// # Returns
+/// * `Ok(u64)` - Number of listings updated
+/// * `Err(AppError)` - Database error
+pub async fn set_product_listing_link_keys(
+    pool: &PgPool,
+    keys: &[(String, String)],
+) -> Result<u64, AppError> {
+    let (links, link_keys): (Vec<&str>, Vec<&str>) = keys
+        .iter()
+        .map(|(link, key)| (link.as_str(), key.as_str()))
+        .unzip();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE product_listings pl
+        SET link_key = renamed.link_key
+        FROM (
+            SELECT DISTINCT ON (k.link_key) pl.id, k.link_key
+            FROM UNNEST($1::text[], $2::text[]) AS k(link, link_key)
+            JOIN product_listings pl ON pl.link = k.link AND pl.link_key <> k.link_key
+            WHERE NOT EXISTS (
+                SELECT 1 FROM product_listings taken WHERE taken.link_key = k.link_key
+            )
+            ORDER BY k.link_key, pl.last_seen_at DESC
+        ) AS renamed
+        WHERE pl.id = renamed.id
+        "#,
+    )
+    .bind(&links)
+    .bind(&link_keys)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to update product listings: {}", e)))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Counts catalog listings whose link key differs from the given key.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `keys` - Listing link and its normalized key
+///
+/// # Returns
+/// * `Ok(i64)` - Number of listings on another key
+/// * `Err(AppError)` - Database error
+pub async fn count_product_listings_with_other_key(
+    pool: &PgPool,
+    keys: &[(String, String)],
+) -> Result<i64, AppError> {
+    let (links, link_keys): (Vec<&str>, Vec<&str>) = keys
+        .iter()
+        .map(|(link, key)| (link.as_str(), key.as_str()))
+        .unzip();
+
+    let count: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*)
+        FROM UNNEST($1::text[], $2::text[]) AS k(link, link_key)
+        JOIN product_listings pl ON pl.link = k.link AND pl.link_key <> k.link_key
+        "#,
+    )
+    .bind(&links)
+    .bind(&link_keys)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to count product listings: {}", e)))?;
+
+    Ok(count.0)
+}
+
+/// Saves a user verdict on a product match.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `feedback` - Verdict with source and candidate details
+/// * `source_key` - Stable key of the source product
+/// * `candidate_key` - Normalized candidate link
+///
+/// # Returns
+/// * `Ok(Uuid)` - ID of inserted record
+/// * `Err(AppError)` - Database error
+pub async fn save_match_feedback(
+    pool: &PgPool,
+    feedback: &MatchFeedbackRequest,
+    source_key: &str,
+    candidate_key: &str,
+) -> Result<Uuid, AppError> {
+    let record: (Uuid,) = sqlx::query_as(
+        r#"
+        INSERT INTO match_feedback (
+            source_key, source_title, source_identifiers, candidate_site,
+            candidate_title, candidate_link, candidate_key, verdict, match_confidence
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id
+        "#,
+    )
+    .bind(source_key)
+    .bind(&feedback.source_title)
+    .bind(Json(&feedback.identifiers))
+    .bind(&feedback.candidate_site)
+    .bind(&feedback.candidate_title)
+    .bind(&feedback.candidate_link)
+    .bind(candidate_key)
+    .bind(feedback.verdict.as_str())
+    .bind(feedback.match_confidence.map(i16::from))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to save match feedback: {}", e)))?;
+
+    Ok(record.0)
+}
+
+/// Retrieves candidates users have rejected for a source product.
+///
+/// Only the latest verdict per candidate counts, so a later "match"
+/// lifts an earlier "mismatch".
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `source_key` - Stable key of the source product
+///
+/// # Returns
+/// * `Ok(HashSet<String>)` - Normalized links of rejected candidates
+/// * `Err(AppError)` - Database error
+pub async fn get_rejected_candidates(
+    pool: &PgPool,
+    source_key: &str,
+) -> Result<HashSet<String>, AppError> {
+    let rows: Vec<(String,)> = sqlx::query_as(
+        r#"
+        SELECT candidate_key
+        FROM (
+            SELECT DISTINCT ON (candidate_key) candidate_key, verdict
+            FROM match_feedback
+            WHERE source_key = $1
+            ORDER BY candidate_key, created_at DESC
+        ) latest
+        WHERE verdict = 'mismatch'
+        "#,
+    )
+    .bind(source_key)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to fetch match feedback: {}", e)))?;
+
+    Ok(rows.into_iter().map(|(key,)| key).collect())
+}
+
+/// Retrieves the latest verdict for every source and candidate pair.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `limit` - Maximum number of results
+///
+/// # Returns
+/// * `Ok(Vec<MatchFeedbackRecord>)` - Feedback records, newest first
+/// * `Err(AppError)` - Database error
+pub async fn get_match_feedback(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<MatchFeedbackRecord>, AppError> {
+    let records = sqlx::query_as::<_, MatchFeedbackRecord>(
+        r#"
+        SELECT * FROM (
+            SELECT DISTINCT ON (source_key, candidate_key)
+                   id, source_title, source_identifiers, candidate_site,
+                   candidate_title, candidate_link, verdict, match_confidence, created_at
+            FROM match_feedback
+            ORDER BY source_key, candidate_key, created_at DESC
+        ) latest
+        ORDER BY created_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to fetch match feedback: {}", e)))?;
+
+    Ok(records)
+}
+
+/// Match feedback record from database.
+#[derive(Debug, sqlx::FromRow)]
+pub struct MatchFeedbackRecord {
+    pub id: Uuid,
+    pub source_title: String,
+    pub source_identifiers: Json<ProductIdentifiers>,
+    pub candidate_site: String,
+    pub candidate_title: String,
+    pub candidate_link: String,
+    pub verdict: String,
+    pub match_confidence: Option<i16>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Fields of a canonical product, used to create it or fill in gaps.
+#[derive(Debug, Clone)]
+pub struct NewProduct {
+    pub canonical_key: String,
+    pub title: String,
+    pub normalized_title: String,
+    pub brand: Option<String>,
+    pub model_number: Option<String>,
+    pub gtin: Option<String>,
+    pub asin: Option<String>,
+    pub specs: serde_json::Value,
+}
+
+/// Finds the product that one of the given alias keys resolves to.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `alias_keys` - Keys in priority order (identifiers before titles)
+///
+/// # Returns
+/// * `Ok(Some(Uuid))` - Product of the highest-priority known key
+/// * `Ok(None)` - No key is known
+/// * `Err(AppError)` - Database error
+pub async fn find_product_by_aliases(
+    pool: &PgPool,
+    alias_keys: &[String],
+) -> Result<Option<Uuid>, AppError> {
+    let rows: Vec<(String, Uuid)> = sqlx::query_as(
+        r#"
+        SELECT alias_key, product_id
+        FROM product_aliases
+        WHERE alias_key = ANY($1)
+        "#,
+    )
+    .bind(alias_keys)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to look up product aliases: {}", e)))?;
+
+    Ok(alias_keys.iter().find_map(|key| {
+        rows.iter()
+            .find(|(alias_key, _)| alias_key == key)
+            .map(|(_, product_id)| *product_id)
+    }))
+}
+
+/// Finds the product most of the given listings are already clustered onto.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_keys` - Normalized links of matched listings
+///
+/// # Returns
+/// * `Ok(Some(Uuid))` - Product linked to the most listings
+/// * `Ok(None)` - None of the listings are linked
+/// * `Err(AppError)` - Database error
+pub async fn find_product_by_listings(
+    pool: &PgPool,
+    link_keys: &[String],
+) -> Result<Option<Uuid>, AppError> {
+    let row: Option<(Uuid,)> = sqlx::query_as(
+        r#"
+        SELECT product_id
+        FROM product_listings
+        WHERE link_key = ANY($1)
+        GROUP BY product_id
+        ORDER BY COUNT(*) DESC, MIN(first_seen_at)
+        LIMIT 1
+        "#,
+    )
+    .bind(link_keys)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to look up product listings: {}", e)))?;
+
+    Ok(row.map(|(product_id,)| product_id))
+}
+
+/// Creates a product, or returns the existing one with the same canonical key.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `product` - Product fields
+///
+/// # Returns
+/// * `Ok(Uuid)` - Product ID
+/// * `Err(AppError)` - Database error
+pub async fn upsert_product(pool: &PgPool, product: &NewProduct) -> Result<Uuid, AppError> {
+    let record: (Uuid,) = sqlx::query_as(
+        r#"
+        INSERT INTO products (
+            canonical_key, title, normalized_title, brand, model_number, gtin, asin, specs
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (canonical_key) DO UPDATE SET updated_at = NOW()
+        RETURNING id
+        "#,
+    )
+    .bind(&product.canonical_key)
+    .bind(&product.title)
+    .bind(&product.normalized_title)
+    .bind(&product.brand)
+    .bind(&product.model_number)
+    .bind(&product.gtin)
+    .bind(&product.asin)
+    .bind(&product.specs)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to save product: {}", e)))?;
+
+    Ok(record.0)
+}
+
+/// Fills in identifiers a product did not have yet.
+///
+/// Known values are never overwritten, so one odd request cannot
+/// relabel a product.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `product_id` - Product to update
+/// * `product` - Newly seen product fields
+pub async fn merge_product_identifiers(
+    pool: &PgPool,
+    product_id: Uuid,
+    product: &NewProduct,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE products
+        SET brand = COALESCE(brand, $2),
+            model_number = COALESCE(model_number, $3),
+            gtin = COALESCE(gtin, $4),
+            asin = COALESCE(asin, $5),
+            specs = $6 || specs,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(product_id)
+    .bind(&product.brand)
+    .bind(&product.model_number)
+    .bind(&product.gtin)
+    .bind(&product.asin)
+    .bind(&product.specs)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to update product: {}", e)))?;
+
+    Ok(())
+}
+
+/// Points alias keys at a product; keys already claimed keep their product.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `product_id` - Product the keys resolve to
+/// * `alias_keys` - Identifier and title keys
+pub async fn add_product_aliases(
+    pool: &PgPool,
+    product_id: Uuid,
+    alias_keys: &[String],
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO product_aliases (alias_key, product_id)
+        SELECT UNNEST($1::varchar[]), $2
+        ON CONFLICT (alias_key) DO NOTHING
+        "#,
+    )
+    .bind(alias_keys)
+    .bind(product_id)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to save product aliases: {}", e)))?;
+
+    Ok(())
+}
+
+/// Links a listing to a product and records its latest price.
+///
+/// A listing already linked to a product stays on it; only its title,
+/// confidence and price are refreshed.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `product_id` - Product the listing matched
+/// * `price` - Listing with its latest price
+/// * `link_key` - Normalized listing link
+pub async fn upsert_product_listing(
+    pool: &PgPool,
+    product_id: Uuid,
+    price: &SitePrice,
+    link_key: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO product_listings (
+            product_id, site, link_key, link, title, match_confidence,
+            last_price, last_currency, last_price_usd
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (link_key) DO UPDATE SET
+            title = EXCLUDED.title,
+            match_confidence = EXCLUDED.match_confidence,
+            last_price = EXCLUDED.last_price,
+            last_currency = EXCLUDED.last_currency,
+            last_price_usd = EXCLUDED.last_price_usd,
+            last_seen_at = NOW()
+        "#,
+    )
+    .bind(product_id)
+    .bind(&price.site)
+    .bind(link_key)
+    .bind(&price.link)
+    .bind(&price.title)
+    .bind(price.match_confidence.map(i16::from))
+    .bind(price.price.amount())
+    .bind(price.price.currency().code())
+    .bind(price.price_usd)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to save product listing: {}", e)))?;
+
+    Ok(())
+}
+
+/// Retrieves a canonical product.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `product_id` - Product ID
+///
+/// # Returns
+/// * `Ok(Some(ProductRecord))` - Product found
+/// * `Ok(None)` - No such product
+/// * `Err(AppError)` - Database error
+pub async fn get_product(
+    pool: &PgPool,
+    product_id: Uuid,
+) -> Result<Option<ProductRecord>, AppError> {
+    sqlx::query_as::<_, ProductRecord>(
+        r#"
+        SELECT id, canonical_key, title, normalized_title, brand, model_number,
+               gtin, asin, specs, created_at, updated_at
+        FROM products
+        WHERE id = $1
+        "#,
+    )
+    .bind(product_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))
+}
+
+/// Retrieves the site listings clustered onto a product.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `product_id` - Product ID
+///
+/// # Returns
+/// * `Ok(Vec<ProductListingRecord>)` - Listings, most recently seen first
+/// * `Err(AppError)` - Database error
+pub async fn get_product_listings(
+    pool: &PgPool,
+    product_id: Uuid,
+) -> Result<Vec<ProductListingRecord>, AppError> {
+    sqlx::query_as::<_, ProductListingRecord>(
+        r#"
+        SELECT id, site, link, title, match_confidence, last_price, last_currency,
+               last_price_usd, first_seen_at, last_seen_at
+        FROM product_listings
+        WHERE product_id = $1
+        ORDER BY last_seen_at DESC
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to fetch product listings: {}", e)))
+}
+
+/// Canonical product record from database.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ProductRecord {
+    pub id: Uuid,
+    pub canonical_key: String,
+    pub title: String,
+    pub normalized_title: String,
+    pub brand: Option<String>,
+    pub model_number: Option<String>,
+    pub gtin: Option<String>,
+    pub asin: Option<String>,
+    pub specs: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Site listing clustered onto a product, from database.
+#[derive(Debug, Serialize)]
+pub struct ProductListingRecord {
+    pub id: Uuid,
+    pub site: String,
+    pub link: String,
+    pub title: String,
+    pub match_confidence: Option<i16>,
+    /// Last seen price (sent as `last_price` and `last_currency`)
+    #[serde(flatten, serialize_with = "last_price::serialize")]
+    pub last_price: Money,
+    pub last_price_usd: Decimal,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+impl<'r> FromRow<'r, PgRow> for ProductListingRecord {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            site: row.try_get("site")?,
+            link: row.try_get("link")?,
+            title: row.try_get("title")?,
+            match_confidence: row.try_get("match_confidence")?,
+            last_price: Money::new(row.try_get("last_price")?, row.try_get("last_currency")?),
+            last_price_usd: row.try_get("last_price_usd")?,
+            first_seen_at: row.try_get("first_seen_at")?,
+            last_seen_at: row.try_get("last_seen_at")?,
+        })
+    }
+}
+
+/// Wire format of `ProductListingRecord::last_price`: `last_price` and
+/// `last_currency` fields.
+mod last_price {
+    use super::*;
+    use crate::services::currency::Currency;
+    use serde::Serializer;
+
+    #[derive(Serialize)]
+    struct Fields {
+        last_price: Decimal,
+        last_currency: Currency,
+    }
+
+    pub fn serialize<S: Serializer>(money: &Money, serializer: S) -> Result<S::Ok, S::Error> {
+        Fields {
+            last_price: money.amount(),
+            last_currency: money.currency(),
+        }
+        .serialize(serializer)
+    }
 }
@@ -26,6 +26,14 @@ pub enum AppError {
     #[error("Missing field: {0}")]
     MissingField(String),
 
+    /// Malformed or inconsistent client input
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// Requested resource does not exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     /// Cache-related errors (Redis connection, serialization, etc.)
     #[error("Cache error: {0}")]
     Cache(String),
@@ -42,6 +50,8 @@ impl AppError {
             AppError::Network(_) => StatusCode::BAD_GATEWAY,
             AppError::Parse(_) => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::MissingField(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
             AppError::Cache(_) => StatusCode::SERVICE_UNAVAILABLE,
             AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -11,5 +11,8 @@ pub mod utils;
 
 pub use config::Config;
 pub use errors::AppError;
-pub use models::{PriceComparisonResult, ProductIdentifiers, ProductMatchRequest, SitePrice};
+pub use models::{
+    MatchFeedbackRequest, MatchVerdict, PriceComparisonResult, ProductIdentifiers,
+    ProductMatchRequest, SitePrice,
+};
 pub use state::AppState;
@@ -4,11 +4,16 @@
 //! and all API routes.
 
 use axum::middleware;
-use price_checker_extension::{cache, db, observability, routes, utils, AppState, Config};
-use std::{net::SocketAddr, sync::Arc};
+use price_checker_extension::{
+    cache, db, observability, routes, services, utils, AppState, Config,
+};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 use tokio::signal;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
+/// Longest wait for queued price history on shutdown.
+const HISTORY_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables from .env file
@@ -57,16 +62,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         config.scraper.request_timeout_seconds,
     )?;
 
+    // Start the batched price history writer
+    let (history_writer, history_task) =
+        services::history_writer::HistoryWriter::spawn(db_pool.clone(), &config.history);
+
     // Create shared application state
     let state = Arc::new(AppState::new(
         db_pool,
         redis_pool,
         http_client,
         config.clone(),
+        history_writer,
     ));
 
     tracing::info!("Application state initialized");
 
+    // Refresh exchange rates ahead of the cache expiry
+    services::rate_refresher::spawn(state.currency_service.clone(), &config.currency);
+
     // Create application router with middleware
     let app = routes::create_router(state, metrics_handle)
         .layer(middleware::from_fn(observability::track_metrics))
@@ -88,6 +101,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_graceful_shutdown(shutdown_signal())
         .await?;
 
+    // The router is gone, so the writer drains its queue and stops
+    if tokio::time::timeout(HISTORY_DRAIN_TIMEOUT, history_task)
+        .await
+        .is_err()
+    {
+        tracing::warn!("Timed out writing queued price history");
+    }
+
     tracing::info!("Server shutdown complete");
 
     // Shutdown tracing to flush remaining spans
@@ -1,9 +1,22 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::services::currency::{RateMetadata, ResolvedCurrency, UsdRate};
+use crate::services::discounts::DiscountCheck;
+use crate::services::gtin::Gtin;
+use crate::services::image_hash::ImageHash;
+use crate::services::matching::MatchExplanation;
+use crate::services::money::Money;
+use crate::services::price_insights::PriceInsight;
+use crate::services::rate_providers::RateSource;
+use crate::services::units::{self, UnitPrice};
+use crate::AppError;
 
 /// Product identifiers used for matching across sites.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct ProductIdentifiers {
     /// Universal Product Code
     pub upc: Option<String>,
@@ -23,6 +36,50 @@ pub struct ProductIdentifiers {
     pub brand: Option<String>,
     /// Product specifications (e.g., RAM, storage, color)
     pub specifications: Option<HashMap<String, String>>,
+    /// Perceptual hash of the product image (computed from the request image if absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_hash: Option<ImageHash>,
+}
+
+impl ProductIdentifiers {
+    /// Returns the trade item number normalized to GTIN-14.
+    ///
+    /// UPC, EAN and GTIN are different encodings of one identity, so the
+    /// first field that parses is returned regardless of which was set.
+    pub fn gtin14(&self) -> Option<Gtin> {
+        [&self.gtin, &self.ean, &self.upc]
+            .into_iter()
+            .flatten()
+            .find_map(|code| Gtin::parse(code).ok())
+    }
+
+    /// Validates UPC/EAN/GTIN and stores the normalized GTIN-14 in `gtin`.
+    ///
+    /// # Returns
+    /// * `Ok(())` - All codes are valid and name the same item (or none are set)
+    /// * `Err(AppError)` - A code is malformed or the codes disagree
+    pub fn normalize_trade_item_numbers(&mut self) -> Result<(), AppError> {
+        let mut normalized: Option<Gtin> = None;
+
+        for code in [&self.gtin, &self.ean, &self.upc].into_iter().flatten() {
+            let gtin = Gtin::parse(code)?;
+            match &normalized {
+                Some(existing) if *existing != gtin => {
+                    return Err(AppError::InvalidInput(format!(
+                        "Conflicting trade item numbers: {} and {}",
+                        existing, gtin
+                    )));
+                }
+                _ => normalized = Some(gtin),
+            }
+        }
+
+        if let Some(gtin) = normalized {
+            self.gtin = Some(gtin.to_string());
+        }
+
+        Ok(())
+    }
 }
 
 /// Request body for product comparison with detailed identifiers.
@@ -38,11 +95,95 @@ pub struct ProductMatchRequest {
     pub current_site: Option<String>,
     /// Product URL from the current page
     pub url: Option<String>,
+    /// Product image URL from the current page, hashed for image matching
+    #[serde(default)]
+    pub image: Option<String>,
     /// Target currency for price conversion (optional)
     pub target_currency: Option<String>,
     /// Product identifiers
     #[serde(default)]
     pub identifiers: ProductIdentifiers,
+    /// Attach a match explanation to each listing (bypasses the cache)
+    #[serde(default)]
+    pub explain: bool,
+    /// Most detail pages to fetch when verifying borderline matches
+    /// (capped by `DETAIL_FETCH_BUDGET`)
+    #[serde(default)]
+    pub detail_fetch_budget: Option<usize>,
+    /// How to rank listings (unit price is chosen automatically for mixed pack sizes)
+    #[serde(default)]
+    pub rank_by: Option<RankBy>,
+}
+
+/// Order in which comparison results are ranked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankBy {
+    /// Lowest total price (in USD) first
+    #[default]
+    Price,
+    /// Lowest price per item, per 100 ml or per kg first
+    UnitPrice,
+}
+
+/// User verdict on whether a listing is the same product as the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchVerdict {
+    /// Listing is the same product
+    Match,
+    /// Listing is a different product
+    Mismatch,
+}
+
+impl MatchVerdict {
+    /// Returns the value stored in the `match_feedback.verdict` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchVerdict::Match => "match",
+            MatchVerdict::Mismatch => "mismatch",
+        }
+    }
+}
+
+impl FromStr for MatchVerdict {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "match" => Ok(MatchVerdict::Match),
+            "mismatch" => Ok(MatchVerdict::Mismatch),
+            other => Err(AppError::Parse(format!("Unknown match verdict: {}", other))),
+        }
+    }
+}
+
+/// Request body for reporting whether a compared listing is the same product.
+#[derive(Debug, Deserialize)]
+pub struct MatchFeedbackRequest {
+    /// Title of the product on the page being viewed
+    pub source_title: String,
+    /// Identifiers of the product on the page being viewed
+    #[serde(default)]
+    pub identifiers: ProductIdentifiers,
+    /// Site of the listing being judged (e.g., "Jumia")
+    pub candidate_site: String,
+    /// Title of the listing being judged
+    pub candidate_title: String,
+    /// Link of the listing being judged
+    pub candidate_link: String,
+    /// User verdict
+    pub verdict: MatchVerdict,
+    /// Match confidence shown to the user (optional)
+    pub match_confidence: Option<u8>,
+}
+
+/// Response for recorded match feedback.
+#[derive(Debug, Serialize)]
+pub struct MatchFeedbackResponse {
+    pub id: Uuid,
+    /// Key under which the verdict applies to future comparisons
+    pub source_key: String,
 }
 
 /// Price information from a specific site with match confidence.
@@ -50,22 +191,46 @@ pub struct ProductMatchRequest {
 pub struct SitePrice {
     pub site: String,
     pub title: String,
-    /// Price in original currency (use Decimal for precision)
-    pub price: Decimal,
-    /// ISO 4217 currency code (e.g., "USD", "GBP", "NGN")
-    pub currency: String,
+    /// Listed price in the storefront's currency (sent as `price` and `currency`)
+    #[serde(flatten, with = "listed_price")]
+    pub price: Money,
+    /// How the price's currency was resolved and how sure it is (scraped listings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency_resolution: Option<ResolvedCurrency>,
     /// Price converted to USD for comparison
     pub price_usd: Decimal,
-    /// Price converted to target currency (if requested)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub price_converted: Option<Decimal>,
-    /// Target currency code (if conversion was requested)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub target_currency: Option<String>,
+    /// Rate `price_usd` was normalized with, and when it was published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usd_rate: Option<UsdRate>,
+    /// Price converted to the target currency, if requested (sent as
+    /// `price_converted` and `target_currency`)
+    #[serde(flatten, with = "converted_price")]
+    pub price_converted: Option<Money>,
     pub link: String,
     pub image: Option<String>,
     /// Match confidence score (0-100), where 100 is exact match
     pub match_confidence: Option<u8>,
+    /// How the match confidence was reached (only when explanations are requested)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_explanation: Option<MatchExplanation>,
+    /// Price per item, per 100 ml or per kg (when the listing states a quantity)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<UnitPrice>,
+    /// Perceptual hash of `image` (when image matching fetched it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_hash: Option<ImageHash>,
+    /// Provider and publication time of the rates used for `price_converted`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rate_sources: Vec<RateSource>,
+    /// How the price compares with the listing's history (when it has one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_insight: Option<PriceInsight>,
+    /// Reference ("was" or strike-through) price the listing advertises
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list_price: Option<Money>,
+    /// Advertised discount checked against recorded prices (when `list_price` is set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discount_check: Option<DiscountCheck>,
 }
 
 impl SitePrice {
@@ -73,8 +238,7 @@ impl SitePrice {
     pub fn new(
         site: String,
         title: String,
-        price: Decimal,
-        currency: String,
+        price: Money,
         price_usd: Decimal,
         link: String,
         image: Option<String>,
@@ -84,42 +248,199 @@ impl SitePrice {
             site,
             title,
             price,
-            currency,
+            currency_resolution: None,
             price_usd,
+            usd_rate: None,
             price_converted: None,
-            target_currency: None,
             link,
             image,
             match_confidence,
+            match_explanation: None,
+            unit_price: None,
+            image_hash: None,
+            rate_sources: Vec::new(),
+            price_insight: None,
+            list_price: None,
+            discount_check: None,
         }
     }
 
-    /// Sets the converted price and target currency.
-    pub fn with_conversion(mut self, price_converted: Decimal, target_currency: String) -> Self {
+    /// Sets the price converted to the target currency.
+    pub fn with_conversion(mut self, price_converted: Money) -> Self {
         self.price_converted = Some(price_converted);
-        self.target_currency = Some(target_currency);
         self
     }
 }
 
+/// Wire format of `SitePrice::price`: `price` and `currency` fields.
+mod listed_price {
+    use super::*;
+    use crate::services::currency::Currency;
+    use serde::{Deserializer, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Fields {
+        price: Decimal,
+        currency: Currency,
+    }
+
+    pub fn serialize<S: Serializer>(money: &Money, serializer: S) -> Result<S::Ok, S::Error> {
+        Fields {
+            price: money.amount(),
+            currency: money.currency(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        let fields = Fields::deserialize(deserializer)?;
+        Ok(Money::new(fields.price, fields.currency))
+    }
+}
+
+/// Wire format of `SitePrice::price_converted`: `price_converted` and
+/// `target_currency` fields, both absent without a conversion.
+mod converted_price {
+    use super::*;
+    use crate::services::currency::Currency;
+    use serde::{Deserializer, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Fields {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        price_converted: Option<Decimal>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target_currency: Option<Currency>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        money: &Option<Money>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Fields {
+            price_converted: money.map(|money| money.amount()),
+            target_currency: money.map(|money| money.currency()),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Money>, D::Error> {
+        let fields = Fields::deserialize(deserializer)?;
+        Ok(fields
+            .price_converted
+            .zip(fields.target_currency)
+            .map(|(amount, currency)| Money::new(amount, currency)))
+    }
+}
+
 /// Result of price comparison across multiple sites.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PriceComparisonResult {
+    /// Canonical product the comparison resolved to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_id: Option<Uuid>,
     pub best_deal: Option<SitePrice>,
     pub all_prices: Vec<SitePrice>,
+    /// How `all_prices` is ordered
+    #[serde(default)]
+    pub ranked_by: RankBy,
+    /// Source and age of the rates behind `price_usd` and `price_converted`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_metadata: Option<RateMetadata>,
 }
 
 impl PriceComparisonResult {
     /// Creates a new comparison result and finds the best deal.
     pub fn new(mut prices: Vec<SitePrice>) -> Self {
         // Sort by USD price to find best deal
-        prices.sort_by(|a, b| a.price_usd.cmp(&b.price_usd));
+        prices.sort_by_key(|p| p.price_usd);
 
         let best_deal = prices.first().cloned();
 
         Self {
+            product_id: None,
             best_deal,
             all_prices: prices,
+            ranked_by: RankBy::Price,
+            rate_metadata: None,
+        }
+    }
+
+    /// Rate sources used by any listing, each once.
+    pub fn rate_sources(&self) -> Vec<RateSource> {
+        let mut sources: Vec<RateSource> = Vec::new();
+        for price in &self.all_prices {
+            let usd_source = price.usd_rate.iter().map(|usd_rate| &usd_rate.source);
+            for source in usd_source.chain(&price.rate_sources) {
+                if !sources.contains(source) {
+                    sources.push(source.clone());
+                }
+            }
+        }
+        sources
+    }
+
+    /// Orders the listings and picks the best deal.
+    ///
+    /// # Arguments
+    /// * `rank_by` - Requested order; `None` ranks by unit price only when
+    ///   every listing has one and their pack sizes differ
+    pub fn rank(&mut self, rank_by: Option<RankBy>) {
+        let rank_by = rank_by.unwrap_or_else(|| {
+            if units::prefer_unit_price(&self.all_prices) {
+                RankBy::UnitPrice
+            } else {
+                RankBy::Price
+            }
+        });
+
+        match rank_by {
+            // Listings without a unit price go last, cheapest first
+            RankBy::UnitPrice => self.all_prices.sort_by_key(|p| {
+                (
+                    p.unit_price.is_none(),
+                    p.unit_price.as_ref().map(|unit| unit.price_usd),
+                    p.price_usd,
+                )
+            }),
+            RankBy::Price => self.all_prices.sort_by_key(|p| p.price_usd),
         }
+
+        self.best_deal = self.all_prices.first().cloned();
+        self.ranked_by = rank_by;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::currency::Currency;
+
+    #[test]
+    fn test_site_price_wire_format() {
+        let listing = SitePrice::new(
+            "Jumia".to_string(),
+            "Phone".to_string(),
+            Money::new(Decimal::from(500_000), Currency::NGN),
+            Decimal::new(31250, 2),
+            "https://www.jumia.com.ng/phone".to_string(),
+            None,
+            None,
+        );
+
+        let json = serde_json::to_value(&listing).unwrap();
+        assert_eq!(json["price"], "500000");
+        assert_eq!(json["currency"], "NGN");
+        assert!(json.get("price_converted").is_none());
+        assert!(json.get("target_currency").is_none());
+
+        let converted = listing.with_conversion(Money::new(Decimal::new(24688, 2), Currency::GBP));
+        let json = serde_json::to_value(&converted).unwrap();
+        assert_eq!(json["target_currency"], "GBP");
+        let parsed: SitePrice = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.price, converted.price);
+        assert_eq!(parsed.price_converted, converted.price_converted);
     }
 }
@@ -86,6 +86,22 @@ pub fn init_metrics() {
         "Total calls to exchange rate API"
     );
     describe_counter!("currency_api_cache_hits", "Exchange rate cache hits");
+    describe_counter!(
+        "price_history_rows_total",
+        "Price history rows by outcome (written, failed, dropped)"
+    );
+    describe_counter!(
+        "exchange_rate_refreshes_total",
+        "Background exchange rate refreshes by outcome"
+    );
+    describe_counter!(
+        "exchange_rate_changes_total",
+        "Exchange rate moves past the alert threshold by currency"
+    );
+    describe_gauge!(
+        "exchange_rate_change_percent",
+        "Last alerted exchange rate move in percent by currency"
+    );
 
     // Business Metrics
     describe_gauge!("active_searches", "Number of active price searches");
@@ -174,6 +190,23 @@ pub fn record_currency_api_call(cached: bool) {
     }
 }
 
+/// Records a background exchange rate refresh.
+pub fn record_exchange_rate_refresh(success: bool) {
+    let outcome = if success { "success" } else { "failure" };
+    counter!("exchange_rate_refreshes_total", "outcome" => outcome).increment(1);
+}
+
+/// Records an exchange rate move past the alert threshold.
+pub fn record_exchange_rate_change(currency: &str, change_percent: f64) {
+    counter!("exchange_rate_changes_total", "currency" => currency.to_string()).increment(1);
+    gauge!("exchange_rate_change_percent", "currency" => currency.to_string()).set(change_percent);
+}
+
+/// Records price history rows written, failed or dropped.
+pub fn record_price_history_rows(outcome: &'static str, rows: usize) {
+    counter!("price_history_rows_total", "outcome" => outcome).increment(rows as u64);
+}
+
 /// Records price savings found.
 pub fn record_price_savings(savings_usd: f64) {
     histogram!("price_savings_usd").record(savings_usd);
@@ -193,6 +226,12 @@ impl SearchTracker {
     }
 }
 
+impl Default for SearchTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Drop for SearchTracker {
     fn drop(&mut self) {
         gauge!("active_searches").decrement(1.0);
@@ -1,23 +1,99 @@
 //! API route handlers.
 
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json, Response},
-    routing::get,
+    routing::{get, post},
     Router,
 };
+use chrono::{NaiveDate, NaiveTime, Utc};
 use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use uuid::Uuid;
 
-use crate::{cache, services, AppError, AppState, PriceComparisonResult, ProductMatchRequest};
+use crate::db::{PriceHistoryRecord, ProductListingRecord, ProductRecord};
+use crate::models::MatchFeedbackResponse;
+use crate::services::currency::{Currency, CurrencyMetadata, RateMetadata};
+use crate::services::money::Money;
+use crate::services::price_history::{self, HistoryInterval, HistoryKey, SiteHistory};
+use crate::services::rate_history::{self, HistoryRates};
+use crate::services::{catalog, feedback};
+use crate::{
+    cache, db, services, AppError, AppState, MatchFeedbackRequest, MatchVerdict,
+    PriceComparisonResult, ProductIdentifiers, ProductMatchRequest,
+};
 
 /// Query parameters for price comparison endpoint.
 #[derive(Debug, Deserialize)]
 pub struct CompareQuery {
     /// Product search query
     pub item: String,
+    /// Attach a match explanation to each listing (bypasses the cache)
+    #[serde(default)]
+    pub explain: bool,
+}
+
+/// Query parameters for product price history.
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Maximum number of records (default 100, max 1000)
+    pub limit: Option<i64>,
+    /// Currency to convert prices to (e.g., "USD")
+    pub currency: Option<String>,
+    /// Rates to convert with; required with `currency`
+    pub rates: Option<HistoryRates>,
+}
+
+/// Query parameters for the bucketed price history endpoint.
+///
+/// Exactly one of `product_id`, `query` and `url` is required.
+#[derive(Debug, Deserialize)]
+pub struct PriceHistoryQuery {
+    /// Canonical product ID
+    pub product_id: Option<Uuid>,
+    /// Exact search query the prices were recorded for
+    pub query: Option<String>,
+    /// Listing URL (query string and fragment are ignored)
+    pub url: Option<String>,
+    /// Bucket width (default day)
+    pub interval: Option<HistoryInterval>,
+    /// Currency to report prices in (default USD)
+    pub currency: Option<String>,
+    /// Rates to convert with (default observation)
+    pub rates: Option<HistoryRates>,
+    /// First day (default 30 days, 26 weeks or 12 months before `to`)
+    pub from: Option<NaiveDate>,
+    /// Last day (default today)
+    pub to: Option<NaiveDate>,
+}
+
+/// Query parameters for the exchange rates endpoint.
+#[derive(Debug, Deserialize)]
+pub struct RatesQuery {
+    /// Currency the rates are expressed against (default USD)
+    pub base: Option<String>,
+}
+
+/// Query parameters for the conversion endpoint.
+#[derive(Debug, Deserialize)]
+pub struct ConvertQuery {
+    pub amount: Decimal,
+    /// Source currency code
+    pub from: String,
+    /// Target currency code
+    pub to: String,
+}
+
+/// Canonical product with its clustered site listings.
+#[derive(Serialize)]
+pub struct ProductResponse {
+    #[serde(flatten)]
+    pub product: ProductRecord,
+    pub listings: Vec<ProductListingRecord>,
 }
 
 /// Health check response.
@@ -64,6 +140,12 @@ pub fn create_router(state: Arc<AppState>, metrics_handle: PrometheusHandle) ->
             get(compare_handler).post(compare_post_handler),
         )
         .route("/api/currencies", get(currencies_handler))
+        .route("/api/rates", get(rates_handler))
+        .route("/api/convert", get(convert_handler))
+        .route("/api/matches/feedback", post(match_feedback_handler))
+        .route("/api/products/{id}", get(product_handler))
+        .route("/api/products/{id}/history", get(product_history_handler))
+        .route("/api/history", get(price_history_handler))
         .with_state(state)
 }
 
@@ -114,6 +196,7 @@ async fn ready_handler(State(state): State<Arc<AppState>>) -> (StatusCode, Json<
 ///
 /// # Query Parameters
 /// * `item` - Product search query (required)
+/// * `explain` - Include match explanations (optional, skips the cache)
 ///
 /// # Returns
 /// * `200 OK` - Comparison results
@@ -123,32 +206,61 @@ async fn compare_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<CompareQuery>,
 ) -> Result<Json<PriceComparisonResult>, AppError> {
-    tracing::info!(item = %params.item, "Received price comparison request");
+    tracing::info!(
+        item = %params.item,
+        explain = params.explain,
+        "Received price comparison request"
+    );
 
-    // Check cache first
-    if let Ok(Some(cached_result)) = cache::get_cached_result(&state.redis_pool, &params.item).await
-    {
-        tracing::info!(item = %params.item, "Returning cached result");
-        return Ok(Json(cached_result));
+    // Known products are cached under their ID, shared by every alias
+    let identifiers = ProductIdentifiers::default();
+    let product_id = catalog::find_product(
+        &state.db_pool,
+        &state.redis_pool,
+        &identifiers,
+        &params.item,
+        state.config.cache.ttl_seconds,
+    )
+    .await;
+    let options = services::CompareOptions {
+        explain: params.explain,
+        product_id,
+        ..Default::default()
+    };
+    let cache_key = catalog::cache_key(&identifiers, &params.item, &options);
+
+    // Cached results carry no explanations, so explain requests skip the cache
+    if !params.explain {
+        if let Ok(Some(cached_result)) =
+            cache::get_cached_result(&state.redis_pool, &cache_key).await
+        {
+            tracing::info!(item = %params.item, "Returning cached result");
+            let mut cached_result = cached_result;
+            refresh_rate_metadata(&state, &mut cached_result);
+            return Ok(Json(cached_result));
+        }
     }
 
     // Cache miss - fetch fresh data
-    let result = services::compare_all(&params.item, &state).await?;
+    let result = services::compare_all(&params.item, &state, &options).await?;
 
     // Cache the result
-    if let Err(e) = cache::set_cached_result(
-        &state.redis_pool,
-        &params.item,
-        &result,
-        state.config.cache.ttl_seconds,
-    )
-    .await
-    {
-        tracing::warn!(
-            item = %params.item,
-            error = %e,
-            "Failed to cache result"
-        );
+    if !params.explain {
+        if let Err(e) = cache::set_cached_result(
+            &state.redis_pool,
+            &cache_key,
+            &catalog::cache_index(product_id, &params.item),
+            &result,
+            state.config.cache.ttl_seconds,
+        )
+        .await
+        {
+            tracing::warn!(
+                item = %params.item,
+                error = %e,
+                "Failed to cache result"
+            );
+        }
     }
 
     tracing::info!(
@@ -160,70 +272,137 @@ async fn compare_handler(
     Ok(Json(result))
 }
 
-/// Currency information response.
+/// Exchange rates response.
+#[derive(Serialize)]
+pub struct RatesResponse {
+    pub base: Currency,
+    /// Units of each currency per unit of `base`
+    pub rates: BTreeMap<String, Decimal>,
+    pub rate_metadata: RateMetadata,
+}
+
+/// Conversion response.
 #[derive(Serialize)]
-pub struct CurrencyInfo {
-    pub code: String,
-    pub symbol: String,
-    pub name: String,
+pub struct ConvertResponse {
+    pub amount: Money,
+    /// Converted amount, rounded to the target currency's minor units
+    pub converted: Money,
+    /// Units of the target currency per unit of the source currency
+    pub rate: Decimal,
+    pub rate_metadata: RateMetadata,
+}
+
+/// Bucketed price history response.
+#[derive(Serialize)]
+pub struct PriceHistoryResponse {
+    pub interval: HistoryInterval,
+    pub currency: Currency,
+    pub rates: HistoryRates,
+    /// First day of the first bucket
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Buckets per site, each covering the whole range
+    pub sites: Vec<SiteHistory>,
+    /// Rows left out for lack of a rate to convert them
+    pub unconverted: usize,
+    /// Whether the row limit cut off the oldest rows
+    pub truncated: bool,
 }
 
 /// Currencies list response.
 #[derive(Serialize)]
 pub struct CurrenciesResponse {
-    pub currencies: Vec<CurrencyInfo>,
+    pub currencies: Vec<&'static CurrencyMetadata>,
 }
 
-/// Currencies endpoint - returns list of supported currencies.
+/// Currencies endpoint - returns the ISO 4217 currencies from the embedded table.
+///
+/// Each entry carries the code, numeric code, name, minor units, symbols and
+/// locale formats. Units that are not money (gold, XDR) are omitted.
 ///
 /// GET /api/currencies
 async fn currencies_handler() -> Json<CurrenciesResponse> {
-    use crate::services::currency::Currency;
-
-    let currencies = vec![
-        CurrencyInfo {
-            code: Currency::USD.code().to_string(),
-            symbol: Currency::USD.symbol().to_string(),
-            name: "US Dollar".to_string(),
-        },
-        CurrencyInfo {
-            code: Currency::EUR.code().to_string(),
-            symbol: Currency::EUR.symbol().to_string(),
-            name: "Euro".to_string(),
-        },
-        CurrencyInfo {
-            code: Currency::GBP.code().to_string(),
-            symbol: Currency::GBP.symbol().to_string(),
-            name: "British Pound".to_string(),
-        },
-        CurrencyInfo {
-            code: Currency::NGN.code().to_string(),
-            symbol: Currency::NGN.symbol().to_string(),
-            name: "Nigerian Naira".to_string(),
-        },
-        CurrencyInfo {
-            code: Currency::INR.code().to_string(),
-            symbol: Currency::INR.symbol().to_string(),
-            name: "Indian Rupee".to_string(),
-        },
-        CurrencyInfo {
-            code: Currency::CAD.code().to_string(),
-            symbol: Currency::CAD.symbol().to_string(),
-            name: "Canadian Dollar".to_string(),
-        },
-        CurrencyInfo {
-            code: Currency::AUD.code().to_string(),
-            symbol: Currency::AUD.symbol().to_string(),
-            name: "Australian Dollar".to_string(),
-        },
-        CurrencyInfo {
-            code: Currency::JPY.code().to_string(),
-            symbol: Currency::JPY.symbol().to_string(),
-            name: "Japanese Yen".to_string(),
-        },
-    ];
-
-    Json(CurrenciesResponse { currencies })
+    Json(CurrenciesResponse {
+        currencies: Currency::all().collect(),
+    })
+}
+
+/// Exchange rates endpoint.
+///
+/// GET /api/rates?base=EUR
+///
+/// # Returns
+/// * `200 OK` - Current rates against `base`, with their providers and age
+/// * `400 Bad Request` - Unknown currency
+/// * `404 Not Found` - No rate for `base`
+async fn rates_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RatesQuery>,
+) -> Result<Json<RatesResponse>, AppError> {
+    let base = parse_currency(params.base.as_deref().unwrap_or("USD"))?;
+
+    let exchange_rates = state.currency_service.get_exchange_rates().await?;
+    let (rates, sources) = exchange_rates
+        .rebased(&base)
+        .ok_or_else(|| AppError::NotFound(format!("No exchange rate for {}", base)))?;
+    let rate_metadata = state
+        .currency_service
+        .rate_metadata(&sources, exchange_rates.updated_at);
+
+    Ok(Json(RatesResponse {
+        base,
+        rates,
+        rate_metadata,
+    }))
+}
+
+/// Currency conversion endpoint.
+///
+/// GET /api/convert?amount=100&from=GBP&to=NGN
+///
+/// # Returns
+/// * `200 OK` - Converted amount, effective rate and rate metadata
+/// * `400 Bad Request` - Unknown currency or negative amount
+/// * `404 Not Found` - No rate for either currency
+async fn convert_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ConvertQuery>,
+) -> Result<Json<ConvertResponse>, AppError> {
+    if params.amount.is_sign_negative() {
+        return Err(AppError::InvalidInput(
+            "amount must not be negative".to_string(),
+        ));
+    }
+    let amount = Money::new(params.amount, parse_currency(&params.from)?);
+    let to = parse_currency(&params.to)?;
+
+    let conversion = state
+        .currency_service
+        .convert_with_sources(amount, &to)
+        .await?;
+    let rate_metadata = state
+        .currency_service
+        .rate_metadata(&conversion.sources, conversion.fetched_at);
+
+    Ok(Json(ConvertResponse {
+        amount,
+        converted: conversion.amount,
+        rate: conversion.rate,
+        rate_metadata,
+    }))
+}
+
+/// Parses a currency code from a request, rejecting codes outside the ISO table.
+fn parse_currency(code: &str) -> Result<Currency, AppError> {
+    code.parse::<Currency>()
+        .map_err(|_| AppError::InvalidInput(format!("Unsupported currency: {}", code)))
+}
+
+/// Marks a cached comparison's rates stale if they have aged since it was cached.
+fn refresh_rate_metadata(state: &AppState, result: &mut PriceComparisonResult) {
+    if let Some(rate_metadata) = result.rate_metadata.as_mut() {
+        state.currency_service.refresh_rate_metadata(rate_metadata);
+    }
 }
 
 /// Product comparison endpoint with detailed identifiers (POST).
@@ -237,54 +416,79 @@ async fn currencies_handler() -> Json<CurrenciesResponse> {
 ///
 /// # Returns
 /// * `200 OK` - Comparison results with match confidence scores
-/// * `400 Bad Request` - Invalid request body
+/// * `400 Bad Request` - Invalid request body or malformed UPC/EAN/GTIN
 /// * `500 Internal Server Error` - All scrapers failed or no matches found
 async fn compare_post_handler(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<ProductMatchRequest>,
+    Json(mut request): Json<ProductMatchRequest>,
 ) -> Result<Json<PriceComparisonResult>, AppError> {
+    // Reject malformed UPC/EAN/GTIN before they reach matching
+    request.identifiers.normalize_trade_item_numbers()?;
+
     tracing::info!(
         title = %request.title,
         current_site = ?request.current_site,
-        has_upc = request.identifiers.upc.is_some(),
+        gtin = ?request.identifiers.gtin,
         has_asin = request.identifiers.asin.is_some(),
+        explain = request.explain,
         "Received product match request"
     );
 
     // Use title as search query
     let search_query = &request.title;
 
-    // Check cache first (using title as key)
-    if let Ok(Some(cached_result)) = cache::get_cached_result(&state.redis_pool, search_query).await
-    {
-        tracing::info!(title = %request.title, "Returning cached result");
-        return Ok(Json(cached_result));
-    }
-
-    // Cache miss - fetch fresh data with identifiers
-    let target_currency = request.target_currency.as_deref();
-    let result = services::compare_with_identifiers(
-        &request.identifiers,
-        search_query,
-        &state,
-        target_currency,
-    )
-    .await?;
-
-    // Cache the result
-    if let Err(e) = cache::set_cached_result(
+    // Check cache first (by product ID, or title for unknown products); explain requests skip it
+    let product_id = catalog::find_product(
+        &state.db_pool,
         &state.redis_pool,
+        &request.identifiers,
         search_query,
-        &result,
         state.config.cache.ttl_seconds,
     )
-    .await
-    {
-        tracing::warn!(
-            title = %request.title,
-            error = %e,
-            "Failed to cache result"
-        );
+    .await;
+    let options = services::CompareOptions {
+        target_currency: request.target_currency.clone(),
+        explain: request.explain,
+        detail_fetch_budget: request.detail_fetch_budget,
+        rank_by: request.rank_by,
+        source_image: request.image.clone(),
+        current_site: request.current_site.clone(),
+        product_id,
+    };
+    let cache_key = catalog::cache_key(&request.identifiers, search_query, &options);
+    if !request.explain {
+        if let Ok(Some(cached_result)) =
+            cache::get_cached_result(&state.redis_pool, &cache_key).await
+        {
+            tracing::info!(title = %request.title, "Returning cached result");
+            let mut cached_result = cached_result;
+            refresh_rate_metadata(&state, &mut cached_result);
+            return Ok(Json(cached_result));
+        }
+    }
+
+    // Cache miss - fetch fresh data with identifiers
+    let result =
+        services::compare_with_identifiers(&request.identifiers, search_query, &state, &options)
+            .await?;
+
+    // Cache the result (explained results are never cached)
+    if !request.explain {
+        if let Err(e) = cache::set_cached_result(
+            &state.redis_pool,
+            &cache_key,
+            &catalog::cache_index(product_id, search_query),
+            &result,
+            state.config.cache.ttl_seconds,
+        )
+        .await
+        {
+            tracing::warn!(
+                title = %request.title,
+                error = %e,
+                "Failed to cache result"
+            );
+        }
     }
 
     tracing::info!(
@@ -296,3 +500,233 @@ async fn compare_post_handler(
 
     Ok(Json(result))
 }
+
+/// Match feedback endpoint (POST).
+///
+/// Records whether a compared listing is the same product as the one the
+/// user is viewing. A "mismatch" hides that listing from later comparisons
+/// of the same source product and clears its cached result.
+///
+/// # Request Body
+/// * `MatchFeedbackRequest` - Source product, judged listing and verdict (JSON)
+///
+/// # Returns
+/// * `201 Created` - Feedback recorded
+/// * `400 Bad Request` - Missing title/link or malformed UPC/EAN/GTIN
+/// * `500 Internal Server Error` - Database failure
+async fn match_feedback_handler(
+    State(state): State<Arc<AppState>>,
+    Json(mut request): Json<MatchFeedbackRequest>,
+) -> Result<(StatusCode, Json<MatchFeedbackResponse>), AppError> {
+    request.identifiers.normalize_trade_item_numbers()?;
+
+    if request.source_title.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "source_title is required".to_string(),
+        ));
+    }
+    if request.candidate_link.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "candidate_link is required".to_string(),
+        ));
+    }
+
+    let source_key = feedback::source_key(&request.identifiers, &request.source_title);
+    let candidate_key = feedback::candidate_key(&request.candidate_link);
+    let id = db::save_match_feedback(&state.db_pool, &request, &source_key, &candidate_key).await?;
+
+    tracing::info!(
+        source_key = %source_key,
+        candidate_site = %request.candidate_site,
+        verdict = request.verdict.as_str(),
+        "Recorded match feedback"
+    );
+
+    // The cached comparison may still contain the rejected listing
+    if request.verdict == MatchVerdict::Mismatch {
+        let product_id = catalog::find_product(
+            &state.db_pool,
+            &state.redis_pool,
+            &request.identifiers,
+            &request.source_title,
+            state.config.cache.ttl_seconds,
+        )
+        .await;
+        // Entries cached under the title (any identifiers) and under the product
+        let mut indexes = vec![catalog::cache_index(None, &request.source_title)];
+        if product_id.is_some() {
+            indexes.push(catalog::cache_index(product_id, &request.source_title));
+        }
+        if let Err(e) = cache::invalidate_cache(&state.redis_pool, &indexes).await {
+            tracing::warn!(
+                title = %request.source_title,
+                error = %e,
+                "Failed to invalidate cached result"
+            );
+        }
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(MatchFeedbackResponse { id, source_key }),
+    ))
+}
+
+/// Canonical product endpoint.
+///
+/// GET /api/products/{id}
+///
+/// # Returns
+/// * `200 OK` - Product with its identifiers, specs and site listings
+/// * `404 Not Found` - Unknown product ID
+async fn product_handler(
+    State(state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+) -> Result<Json<ProductResponse>, AppError> {
+    let product = db::get_product(&state.db_pool, product_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Product {}", product_id)))?;
+    let listings = db::get_product_listings(&state.db_pool, product_id).await?;
+
+    Ok(Json(ProductResponse { product, listings }))
+}
+
+/// Product price history endpoint.
+///
+/// GET /api/products/{id}/history?limit=100&currency=USD&rates=observation
+///
+/// With `currency`, each record gets a `converted` price at the rates of
+/// the day it was recorded (`rates=observation`) or at today's rates
+/// (`rates=current`); records without a rate for their day are left
+/// unconverted.
+///
+/// # Returns
+/// * `200 OK` - Recorded prices for the product, newest first
+/// * `400 Bad Request` - Unknown currency, or `currency` without `rates`
+/// * `404 Not Found` - Unknown product ID
+async fn product_history_handler(
+    State(state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+    Query(params): Query<HistoryQuery>,
+) -> Result<Json<Vec<PriceHistoryRecord>>, AppError> {
+    if db::get_product(&state.db_pool, product_id).await?.is_none() {
+        return Err(AppError::NotFound(format!("Product {}", product_id)));
+    }
+
+    let target = params.currency.as_deref().map(parse_currency).transpose()?;
+
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    let mut history = db::get_product_price_history(&state.db_pool, product_id, limit).await?;
+
+    if let Some(target) = target {
+        let rates = params.rates.ok_or_else(|| {
+            AppError::InvalidInput(
+                "rates=observation or rates=current is required with currency".to_string(),
+            )
+        })?;
+        rate_history::convert_history(
+            &mut history,
+            &target,
+            rates,
+            &state.db_pool,
+            &state.currency_service,
+            state.config.currency.snapshot_max_age_days,
+        )
+        .await?;
+    }
+
+    Ok(Json(history))
+}
+
+/// Most history rows aggregated per request.
+const MAX_HISTORY_ROWS: i64 = 20_000;
+
+/// Bucketed price history endpoint.
+///
+/// GET /api/history?product_id=...&interval=week&currency=GBP&from=2025-01-01
+///
+/// Keyed by `product_id`, `query` or `url`. Prices are converted to
+/// `currency` at the rates of the day they were recorded (or today's with
+/// `rates=current`) and summarized per site and bucket; empty buckets are
+/// filled, carrying the last price forward.
+///
+/// # Returns
+/// * `200 OK` - Min/avg/max/last prices per site and bucket
+/// * `400 Bad Request` - Missing or ambiguous key, unknown currency or too long a range
+/// * `404 Not Found` - Unknown product ID
+async fn price_history_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<PriceHistoryQuery>,
+) -> Result<Json<PriceHistoryResponse>, AppError> {
+    let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+    let key = match (
+        params.product_id,
+        non_empty(params.query),
+        non_empty(params.url),
+    ) {
+        (Some(product_id), None, None) => HistoryKey::Product(product_id),
+        (None, Some(query), None) => HistoryKey::Query(query),
+        (None, None, Some(url)) => HistoryKey::Listing(feedback::candidate_key(&url)),
+        _ => {
+            return Err(AppError::InvalidInput(
+                "Exactly one of product_id, query or url is required".to_string(),
+            ));
+        }
+    };
+    if let HistoryKey::Product(product_id) = key {
+        if db::get_product(&state.db_pool, product_id).await?.is_none() {
+            return Err(AppError::NotFound(format!("Product {}", product_id)));
+        }
+    }
+
+    let interval = params.interval.unwrap_or_default();
+    let currency = parse_currency(params.currency.as_deref().unwrap_or("USD"))?;
+    let rates = params.rates.unwrap_or(HistoryRates::Observation);
+    let to = params.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = params.from.unwrap_or_else(|| interval.default_from(to));
+    if from > to {
+        return Err(AppError::InvalidInput(
+            "from must not be after to".to_string(),
+        ));
+    }
+    let starts = interval.buckets(from, to).ok_or_else(|| {
+        AppError::InvalidInput(format!(
+            "Range has more than {} buckets; use a shorter range or a wider interval",
+            price_history::MAX_BUCKETS
+        ))
+    })?;
+
+    // Whole first bucket, through the end of `to`
+    let from = starts[0];
+    let mut records = db::get_price_history(
+        &state.db_pool,
+        &key,
+        from.and_time(NaiveTime::MIN).and_utc(),
+        to.succ_opt()
+            .unwrap_or(to)
+            .and_time(NaiveTime::MIN)
+            .and_utc(),
+        MAX_HISTORY_ROWS,
+    )
+    .await?;
+    rate_history::convert_history(
+        &mut records,
+        &currency,
+        rates,
+        &state.db_pool,
+        &state.currency_service,
+        state.config.currency.snapshot_max_age_days,
+    )
+    .await?;
+
+    Ok(Json(PriceHistoryResponse {
+        interval,
+        currency,
+        rates,
+        from,
+        to,
+        sites: price_history::aggregate(&records, interval, &starts),
+        unconverted: records.iter().filter(|r| r.converted.is_none()).count(),
+        truncated: records.len() as i64 == MAX_HISTORY_ROWS,
+    }))
+}
@@ -0,0 +1,453 @@
+//! Accessory and "compatible with" detection for match suppression.
+//!
+//! Listings such as "Case for iPhone 15 Pro" contain the brand and model of
+//! the product being compared, so title-based matching rates them highly.
+//! This module recognizes accessories from per-locale word lists so their
+//! confidence can be capped unless the source product is an accessory too.
+
+use super::language::Language;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Accessory word lists for a single locale.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LocaleLexicon {
+    /// Phrases that always mark a listing as made for another product
+    #[serde(default)]
+    pub compatibility_phrases: Vec<String>,
+    /// Prepositions that mark an accessory when followed by the source product ("for", "pour")
+    #[serde(default)]
+    pub target_prepositions: Vec<String>,
+    /// Words that introduce bundled items rather than the listing itself ("with", "avec")
+    #[serde(default)]
+    pub bundle_markers: Vec<String>,
+    /// Accessory nouns that apply to every product category
+    #[serde(default)]
+    pub terms: Vec<String>,
+    /// Accessory nouns that only apply when the source is in a category
+    #[serde(default)]
+    pub category_terms: HashMap<String, Vec<String>>,
+}
+
+/// Per-locale accessory lexicons used by product matching.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccessoryLexicon {
+    /// Keywords identifying a product category in a source title
+    pub categories: HashMap<String, Vec<String>>,
+    /// Word lists keyed by locale (e.g., "en", "fr")
+    pub locales: HashMap<String, LocaleLexicon>,
+}
+
+impl Default for AccessoryLexicon {
+    fn default() -> Self {
+        let mut categories = HashMap::new();
+        categories.insert(
+            "phone".to_string(),
+            words(&[
+                "phone",
+                "smartphone",
+                "iphone",
+                "galaxy",
+                "pixel",
+                "redmi",
+                "tecno",
+                "infinix",
+                "téléphone",
+            ]),
+        );
+        categories.insert(
+            "laptop".to_string(),
+            words(&["laptop", "notebook", "macbook", "chromebook", "ordinateur"]),
+        );
+        categories.insert("tablet".to_string(), words(&["tablet", "ipad", "tablette"]));
+        categories.insert(
+            "tv".to_string(),
+            words(&["tv", "television", "télévision", "téléviseur"]),
+        );
+        categories.insert(
+            "camera".to_string(),
+            words(&["camera", "dslr", "mirrorless", "caméra", "appareil photo"]),
+        );
+        categories.insert(
+            "console".to_string(),
+            words(&["playstation", "ps5", "xbox", "switch", "console"]),
+        );
+
+        let mut en_categories = HashMap::new();
+        en_categories.insert(
+            "phone".to_string(),
+            words(&["holder", "pop socket", "lens protector", "back glass"]),
+        );
+        en_categories.insert(
+            "laptop".to_string(),
+            words(&["keyboard cover", "docking station", "laptop stand", "bag"]),
+        );
+        en_categories.insert(
+            "tablet".to_string(),
+            words(&["stylus", "keyboard folio", "tablet stand"]),
+        );
+        en_categories.insert(
+            "tv".to_string(),
+            words(&["wall mount", "bracket", "remote", "remote control"]),
+        );
+        en_categories.insert(
+            "camera".to_string(),
+            words(&["lens cap", "lens hood", "strap", "tripod", "battery grip"]),
+        );
+        en_categories.insert(
+            "console".to_string(),
+            words(&["controller skin", "charging dock", "charging station"]),
+        );
+
+        let en = LocaleLexicon {
+            compatibility_phrases: words(&[
+                "compatible with",
+                "compatible for",
+                "designed for",
+                "made for",
+                "replacement for",
+            ]),
+            target_prepositions: words(&["for"]),
+            bundle_markers: words(&["with", "includes", "including", "plus"]),
+            terms: words(&[
+                "case",
+                "cover",
+                "screen protector",
+                "tempered glass",
+                "charger",
+                "charging cable",
+                "cable",
+                "adapter",
+                "skin",
+                "sleeve",
+                "pouch",
+                "replacement",
+                "spare part",
+                "decal",
+                "sticker",
+            ]),
+            category_terms: en_categories,
+        };
+
+        let mut fr_categories = HashMap::new();
+        fr_categories.insert(
+            "phone".to_string(),
+            words(&["support téléphone", "protection objectif"]),
+        );
+        fr_categories.insert(
+            "laptop".to_string(),
+            words(&["sacoche", "station d'accueil", "support ordinateur"]),
+        );
+        fr_categories.insert("tablet".to_string(), words(&["stylet"]));
+        fr_categories.insert("tv".to_string(), words(&["support mural", "télécommande"]));
+        fr_categories.insert(
+            "camera".to_string(),
+            words(&["bouchon d'objectif", "trépied", "sangle"]),
+        );
+        fr_categories.insert("console".to_string(), words(&["manette skin"]));
+
+        let fr = LocaleLexicon {
+            compatibility_phrases: words(&[
+                "compatible avec",
+                "compatible pour",
+                "conçu pour",
+                "adapté à",
+                "remplacement pour",
+            ]),
+            target_prepositions: words(&["pour"]),
+            bundle_markers: words(&["avec", "inclus", "incluant"]),
+            terms: words(&[
+                "coque",
+                "étui",
+                "housse",
+                "protection écran",
+                "protège écran",
+                "verre trempé",
+                "chargeur",
+                "câble",
+                "adaptateur",
+                "pièce de rechange",
+                "autocollant",
+            ]),
+            category_terms: fr_categories,
+        };
+
+        let mut locales = HashMap::new();
+        locales.insert("en".to_string(), en);
+        locales.insert("fr".to_string(), fr);
+
+        Self {
+            categories,
+            locales,
+        }
+    }
+}
+
+impl AccessoryLexicon {
+    /// Adds extra generic accessory terms for a locale.
+    ///
+    /// Creates the locale if it does not exist yet.
+    pub fn extend_terms(&mut self, locale: &str, terms: impl IntoIterator<Item = String>) {
+        let lexicon = self.locales.entry(locale.to_lowercase()).or_default();
+        lexicon.terms.extend(
+            terms
+                .into_iter()
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty()),
+        );
+    }
+
+    /// Checks whether a source title describes an accessory itself.
+    ///
+    /// The source locale is unknown, so every locale's word list is consulted.
+    pub fn is_accessory_source(&self, source_title: &str) -> bool {
+        let tokens = tokenize(source_title);
+        let category = self.detect_category(&tokens);
+        self.locales
+            .values()
+            .any(|lexicon| match_lexicon(lexicon, &tokens, category, None).is_some())
+    }
+
+    /// Detects whether a candidate listing is an accessory for the source product.
+    ///
+    /// English word lists always apply (many storefronts mix languages),
+    /// plus the list for the candidate's locale.
+    ///
+    /// # Arguments
+    /// * `candidate_title` - Title of the candidate listing
+    /// * `source_title` - Title of the product being compared
+    /// * `locale` - Locale of the candidate's storefront (e.g., "fr")
+    ///
+    /// # Returns
+    /// * `Some(reason)` - The phrase or term that marked the listing as an accessory
+    /// * `None` - No accessory signal found
+    pub fn detect(
+        &self,
+        candidate_title: &str,
+        source_title: &str,
+        locale: &str,
+    ) -> Option<String> {
+        let tokens = tokenize(candidate_title);
+        let source_tokens = tokenize(source_title);
+        let category = self.detect_category(&source_tokens);
+
+        let mut locales = vec!["en"];
+        if locale != "en" {
+            locales.push(locale);
+        }
+
+        locales
+            .into_iter()
+            .filter_map(|code| self.locales.get(code))
+            .find_map(|lexicon| match_lexicon(lexicon, &tokens, category, Some(&source_tokens)))
+    }
+
+    /// Returns the first category whose keywords appear in the tokens.
+    fn detect_category(&self, tokens: &[String]) -> Option<&str> {
+        let mut categories: Vec<(&String, &Vec<String>)> = self.categories.iter().collect();
+        // Deterministic order regardless of HashMap iteration
+        categories.sort_by_key(|(name, _)| name.as_str());
+
+        categories
+            .into_iter()
+            .find(|(_, keywords)| {
+                keywords
+                    .iter()
+                    .any(|k| !phrase_positions(tokens, k).is_empty())
+            })
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Maps a listing URL to the locale of its storefront.
+///
+/// See `Language::from_link`; locales without a word list fall back to English.
+pub fn locale_for_link(link: &str) -> &'static str {
+    Language::from_link(link).code()
+}
+
+/// Matches a tokenized title against one locale's word lists.
+///
+/// When `source_tokens` is given, a target preposition ("for") counts only
+/// if it is followed by a large part of the source title.
+fn match_lexicon(
+    lexicon: &LocaleLexicon,
+    tokens: &[String],
+    category: Option<&str>,
+    source_tokens: Option<&[String]>,
+) -> Option<String> {
+    if let Some(phrase) = lexicon
+        .compatibility_phrases
+        .iter()
+        .find(|p| !phrase_positions(tokens, p).is_empty())
+    {
+        return Some(format!("compatibility phrase \"{}\"", phrase));
+    }
+
+    let category_terms = category
+        .and_then(|c| lexicon.category_terms.get(c))
+        .into_iter()
+        .flatten();
+
+    for term in lexicon.terms.iter().chain(category_terms) {
+        let standalone = phrase_positions(tokens, term)
+            .into_iter()
+            .any(|pos| !is_bundled(tokens, pos, &lexicon.bundle_markers));
+        if standalone {
+            return Some(format!("accessory term \"{}\"", term));
+        }
+    }
+
+    if let Some(source_tokens) = source_tokens {
+        for preposition in &lexicon.target_prepositions {
+            for pos in phrase_positions(tokens, preposition) {
+                if targets_source(&tokens[pos + 1..], source_tokens) {
+                    return Some(format!(
+                        "\"{}\" followed by the source product",
+                        preposition
+                    ));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Checks whether a term is preceded by a bundle marker ("with charger").
+fn is_bundled(tokens: &[String], pos: usize, bundle_markers: &[String]) -> bool {
+    tokens[pos.saturating_sub(3)..pos]
+        .iter()
+        .any(|t| bundle_markers.iter().any(|m| m == t))
+}
+
+/// Checks whether the tokens after a preposition name the source product.
+fn targets_source(tail: &[String], source_tokens: &[String]) -> bool {
+    let significant: Vec<&String> = source_tokens.iter().filter(|t| t.len() > 1).collect();
+    if significant.is_empty() {
+        return false;
+    }
+
+    let overlap = significant.iter().filter(|t| tail.contains(t)).count();
+    overlap > 0 && overlap * 2 >= significant.len()
+}
+
+/// Returns every token index where a (possibly multi-word) phrase starts.
+///
+/// A trailing "s"/"es" on the title token is accepted so plurals match.
+fn phrase_positions(tokens: &[String], phrase: &str) -> Vec<usize> {
+    let words: Vec<String> = tokenize(phrase);
+    if words.is_empty() || words.len() > tokens.len() {
+        return Vec::new();
+    }
+
+    tokens
+        .windows(words.len())
+        .enumerate()
+        .filter(|(_, window)| {
+            window.iter().zip(&words).all(|(token, word)| {
+                token == word
+                    || token.strip_suffix('s') == Some(word.as_str())
+                    || token.strip_suffix("es") == Some(word.as_str())
+            })
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Splits a title into lowercase alphanumeric tokens.
+///
+/// Apostrophes are kept inside words so "station d'accueil" stays intact.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|t| t.trim_matches('\''))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn words(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_case_for_phone() {
+        let lexicon = AccessoryLexicon::default();
+        let reason = lexicon.detect("Case for iPhone 15 Pro", "iPhone 15 Pro 256GB", "en");
+        assert!(reason.is_some());
+    }
+
+    #[test]
+    fn test_bundled_accessory_is_not_flagged() {
+        let lexicon = AccessoryLexicon::default();
+        let reason = lexicon.detect(
+            "Apple iPhone 15 Pro 256GB with Charger",
+            "iPhone 15 Pro 256GB",
+            "en",
+        );
+        assert!(reason.is_none());
+    }
+
+    #[test]
+    fn test_for_without_source_product_is_not_flagged() {
+        let lexicon = AccessoryLexicon::default();
+        assert!(lexicon
+            .detect("Dell XPS 13 Laptop for Students", "Dell XPS 13", "en")
+            .is_none());
+        assert!(lexicon
+            .detect("Screen guard for Dell XPS 13 display", "Dell XPS 13", "en")
+            .is_some());
+    }
+
+    #[test]
+    fn test_french_lexicon() {
+        let lexicon = AccessoryLexicon::default();
+        let reason = lexicon.detect("Coque Silicone iPhone 15 Pro", "iPhone 15 Pro", "fr");
+        assert!(reason.is_some());
+
+        // French terms are not applied to English storefronts
+        assert!(lexicon
+            .detect("Coque iPhone 15 Pro", "iPhone 15 Pro", "en")
+            .is_none());
+    }
+
+    #[test]
+    fn test_category_terms_follow_source_category() {
+        let lexicon = AccessoryLexicon::default();
+        assert!(lexicon
+            .detect("Samsung 55\" TV Wall Mount", "Samsung 55 inch TV", "en")
+            .is_some());
+        assert!(lexicon
+            .detect("Samsung Wall Mount 55", "Samsung Galaxy S24", "en")
+            .is_none());
+    }
+
+    #[test]
+    fn test_accessory_source() {
+        let lexicon = AccessoryLexicon::default();
+        assert!(lexicon.is_accessory_source("Spigen Case for iPhone 15 Pro"));
+        assert!(!lexicon.is_accessory_source("Apple iPhone 15 Pro 256GB"));
+    }
+
+    #[test]
+    fn test_extend_terms() {
+        let mut lexicon = AccessoryLexicon::default();
+        lexicon.extend_terms("fr", vec!["film protecteur".to_string()]);
+        assert!(lexicon
+            .detect("Film protecteur Galaxy S24", "Galaxy S24", "fr")
+            .is_some());
+    }
+
+    #[test]
+    fn test_locale_for_link() {
+        assert_eq!(locale_for_link("https://www.jumia.ci/catalog/?q=x"), "fr");
+        assert_eq!(locale_for_link("https://www.jumia.ma/p/1"), "fr");
+        assert_eq!(locale_for_link("https://www.jumia.com.ng/p/1"), "en");
+        assert_eq!(locale_for_link("https://www.amazon.com/dp/B0"), "en");
+    }
+}
@@ -63,6 +63,7 @@ pub async fn fetch_price(
         price: "span.a-price span.a-offscreen".to_string(),
         link: "h2 a".to_string(),
         image: "img.s-image".to_string(),
+        list_price: Some("span.a-price.a-text-price span.a-offscreen".to_string()),
     };
 
     let mut result = search_product(client, zenrows, &search_url, &selectors).await?;
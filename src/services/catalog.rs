@@ -0,0 +1,451 @@
+//! Canonical product catalog.
+//!
+//! Comparisons resolve to a stable product ID so that "iphone 15" and
+//! "Apple iPhone 15 128GB" share one cache entry and one price history.
+//! A request resolves through its alias keys (GTIN, ASIN, eBay item ID,
+//! normalized title), then through the listings it matched, and creates
+//! a product only when neither is known.
+
+use super::feedback;
+use super::specs::ProductSpecs;
+use super::CompareOptions;
+use crate::db::{self, NewProduct};
+use crate::models::RankBy;
+use crate::{AppError, ProductIdentifiers, SitePrice};
+use deadpool_redis::Pool;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Minimum confidence for a listing to be clustered onto a product.
+///
+/// Lower-confidence listings are still returned and recorded in history,
+/// but must not pull unrelated searches onto the product.
+pub const CLUSTER_MIN_CONFIDENCE: u8 = 90;
+
+/// Longest normalized title kept in a title key.
+const MAX_TITLE_KEY_CHARS: usize = 200;
+
+/// Normalizes a title for keys: lowercase alphanumeric words, single spaces.
+///
+/// # Arguments
+/// * `title` - Title or search query
+///
+/// # Returns
+/// * Normalized title, at most 200 characters (e.g., "dell xps 13 2024")
+pub fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_TITLE_KEY_CHARS)
+        .collect()
+}
+
+/// Builds every key a source product can be found under, strongest first.
+///
+/// # Arguments
+/// * `identifiers` - Identifiers of the source product
+/// * `title` - Title or search query of the source product
+///
+/// # Returns
+/// * Keys such as "gtin:00012345678905", "asin:B07FZ8S74R", "title:dell xps 13";
+///   the title key is always last
+pub fn alias_keys(identifiers: &ProductIdentifiers, title: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+
+    if let Some(gtin) = identifiers.gtin14() {
+        keys.push(format!("gtin:{}", gtin));
+    }
+    if let Some(asin) = &identifiers.asin {
+        keys.push(format!("asin:{}", asin.to_uppercase()));
+    }
+    if let Some(item_id) = &identifiers.ebay_item_id {
+        keys.push(format!("ebay:{}", item_id));
+    }
+    keys.push(format!("title:{}", normalize_title(title)));
+
+    keys
+}
+
+/// Returns the cache key for a comparison.
+///
+/// Known products are cached under their ID so every alias shares the entry.
+/// Unknown products are cached under the query and the request identifiers,
+/// which change how listings are matched. The target currency and ranking
+/// are part of both keys, and so are the source image and detail fetch
+/// budget, since each changes which listings survive.
+///
+/// # Arguments
+/// * `identifiers` - Identifiers of the source product
+/// * `search_query` - Search query used when the product is unknown
+/// * `options` - Resolved product, currency, ranking, image and fetch budget
+///
+/// # Returns
+/// * Key such as "product:{id}:GBP:price:{fingerprint}" or
+///   "query:original:auto:{fingerprint}:iphone 15"
+pub fn cache_key(
+    identifiers: &ProductIdentifiers,
+    search_query: &str,
+    options: &CompareOptions,
+) -> String {
+    let currency = options
+        .target_currency
+        .as_deref()
+        .map(|code| code.trim().to_uppercase())
+        .unwrap_or_else(|| "original".to_string());
+    let rank_by = match options.rank_by {
+        None => "auto",
+        Some(RankBy::Price) => "price",
+        Some(RankBy::UnitPrice) => "unit_price",
+    };
+    match options.product_id {
+        Some(id) => format!(
+            "product:{}:{}:{}:{}",
+            id,
+            currency,
+            rank_by,
+            request_fingerprint(None, options)
+        ),
+        // The query goes last, so colons in it cannot be mistaken for a separator
+        None => format!(
+            "query:{}:{}:{}:{}",
+            currency,
+            rank_by,
+            request_fingerprint(Some(identifiers), options),
+            search_query
+        ),
+    }
+}
+
+/// Returns the key under which every cached variant of a comparison is indexed.
+///
+/// # Arguments
+/// * `product_id` - Resolved product, if any
+/// * `search_query` - Search query used when the product is unknown
+///
+/// # Returns
+/// * Key such as "product:{id}" or "query:iphone 15"
+pub fn cache_index(product_id: Option<Uuid>, search_query: &str) -> String {
+    match product_id {
+        Some(id) => format!("product:{}", id),
+        None => format!("query:{}", search_query),
+    }
+}
+
+/// Short digest of the request inputs that change which listings survive.
+///
+/// Serialized through `serde_json::Value`, whose maps are ordered, so the
+/// specifications hash the same whatever their order.
+fn request_fingerprint(
+    identifiers: Option<&ProductIdentifiers>,
+    options: &CompareOptions,
+) -> String {
+    let json = serde_json::json!({
+        "identifiers": identifiers,
+        "source_image": options.source_image,
+        "detail_fetch_budget": options.detail_fetch_budget,
+    })
+    .to_string();
+    let digest = format!("{:x}", Sha256::digest(json.as_bytes()));
+    digest[..16].to_string()
+}
+
+/// Looks up the product a request refers to, without creating one.
+///
+/// The resolution is cached in Redis (unknown products included), so a
+/// cached comparison costs no database query. Failures are logged and
+/// treated as unknown, so the catalog never blocks a comparison.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `redis_pool` - Redis connection pool
+/// * `identifiers` - Identifiers of the source product
+/// * `title` - Title or search query of the source product
+/// * `ttl_seconds` - How long a resolution is cached
+///
+/// # Returns
+/// * Product ID when one of the alias keys is known
+pub async fn find_product(
+    pool: &PgPool,
+    redis_pool: &Pool,
+    identifiers: &ProductIdentifiers,
+    title: &str,
+    ttl_seconds: u64,
+) -> Option<Uuid> {
+    let keys = alias_keys(identifiers, title);
+    match get_cached_resolution(redis_pool, &keys).await {
+        Ok(Some(product_id)) => return product_id,
+        Ok(None) => {}
+        Err(e) => tracing::debug!(title = %title, error = %e, "Failed to read product resolution"),
+    }
+
+    match db::find_product_by_aliases(pool, &keys).await {
+        Ok(product_id) => {
+            cache_resolution(redis_pool, &keys, product_id, ttl_seconds).await;
+            product_id
+        }
+        Err(e) => {
+            tracing::warn!(title = %title, error = %e, "Failed to look up product");
+            None
+        }
+    }
+}
+
+/// Redis key of the product a set of alias keys resolves to.
+fn resolution_key(keys: &[String]) -> String {
+    format!(
+        "catalog_product:{:x}",
+        Sha256::digest(keys.join("\n").as_bytes())
+    )
+}
+
+/// Reads a cached resolution.
+///
+/// # Returns
+/// * `Ok(Some(Some(Uuid)))` - Known product
+/// * `Ok(Some(None))` - Cached as unknown
+/// * `Ok(None)` - Not cached
+async fn get_cached_resolution(
+    redis_pool: &Pool,
+    keys: &[String],
+) -> Result<Option<Option<Uuid>>, AppError> {
+    let mut conn = redis_pool
+        .get()
+        .await
+        .map_err(|e| AppError::Cache(format!("Redis connection failed: {}", e)))?;
+
+    let cached: Option<String> = redis::cmd("GET")
+        .arg(resolution_key(keys))
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| AppError::Cache(format!("Cache read failed: {}", e)))?;
+
+    Ok(cached.map(|value| Uuid::parse_str(&value).ok()))
+}
+
+/// Caches a resolution; an unknown product is stored as "none".
+///
+/// Failures are logged, since the database stays authoritative.
+async fn cache_resolution(
+    redis_pool: &Pool,
+    keys: &[String],
+    product_id: Option<Uuid>,
+    ttl_seconds: u64,
+) {
+    let value = product_id.map_or_else(|| "none".to_string(), |id| id.to_string());
+    let result = async {
+        let mut conn = redis_pool
+            .get()
+            .await
+            .map_err(|e| AppError::Cache(format!("Redis connection failed: {}", e)))?;
+        let _: () = redis::cmd("SETEX")
+            .arg(resolution_key(keys))
+            .arg(ttl_seconds)
+            .arg(value)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Cache(format!("Cache write failed: {}", e)))?;
+        Ok::<(), AppError>(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::debug!(error = %e, "Failed to cache product resolution");
+    }
+}
+
+/// Resolves a comparison to a canonical product and records its listings.
+///
+/// Resolution order: alias keys, then the product most high-confidence
+/// listings are already linked to, then a new product. The request's keys
+/// become aliases and high-confidence listings are clustered onto the
+/// product, and the resolution cache is updated. Price history is written
+/// separately by `history_writer`.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `redis_pool` - Redis connection pool
+/// * `identifiers` - Identifiers of the source product
+/// * `title` - Title or search query of the source product
+/// * `prices` - Listings returned by the comparison
+/// * `ttl_seconds` - How long the resolution is cached
+///
+/// # Returns
+/// * `Ok(Uuid)` - Canonical product ID
+/// * `Err(AppError)` - Database error
+pub async fn record_comparison(
+    pool: &PgPool,
+    redis_pool: &Pool,
+    identifiers: &ProductIdentifiers,
+    title: &str,
+    prices: &[SitePrice],
+    ttl_seconds: u64,
+) -> Result<Uuid, AppError> {
+    let keys = alias_keys(identifiers, title);
+    let clustered: Vec<(&SitePrice, String)> = prices
+        .iter()
+        .filter(|p| p.match_confidence.unwrap_or(0) >= CLUSTER_MIN_CONFIDENCE)
+        .map(|p| (p, feedback::candidate_key(&p.link)))
+        .collect();
+    let product = new_product(identifiers, title, &keys);
+
+    let product_id = match db::find_product_by_aliases(pool, &keys).await? {
+        Some(id) => id,
+        None => {
+            let link_keys: Vec<String> = clustered.iter().map(|(_, key)| key.clone()).collect();
+            match db::find_product_by_listings(pool, &link_keys).await? {
+                Some(id) => id,
+                None => db::upsert_product(pool, &product).await?,
+            }
+        }
+    };
+
+    db::merge_product_identifiers(pool, product_id, &product).await?;
+    db::add_product_aliases(pool, product_id, &keys).await?;
+
+    for (price, link_key) in &clustered {
+        db::upsert_product_listing(pool, product_id, price, link_key).await?;
+    }
+
+    tracing::debug!(
+        product_id = %product_id,
+        aliases = keys.len(),
+        clustered = clustered.len(),
+        "Comparison recorded in product catalog"
+    );
+
+    cache_resolution(redis_pool, &keys, Some(product_id), ttl_seconds).await;
+    Ok(product_id)
+}
+
+/// Builds product fields from a request.
+fn new_product(identifiers: &ProductIdentifiers, title: &str, keys: &[String]) -> NewProduct {
+    let declared = identifiers
+        .specifications
+        .as_ref()
+        .map(ProductSpecs::from_map)
+        .unwrap_or_default();
+    let specs = declared.or(ProductSpecs::from_title(title));
+
+    NewProduct {
+        // Keys are strongest first, and never empty (the title key is always present)
+        canonical_key: keys[0].clone(),
+        title: title.to_string(),
+        normalized_title: normalize_title(title),
+        brand: identifiers.brand.clone(),
+        model_number: identifiers.model_number.clone(),
+        gtin: identifiers.gtin14().map(|gtin| gtin.to_string()),
+        asin: identifiers.asin.as_ref().map(|asin| asin.to_uppercase()),
+        specs: serde_json::to_value(specs).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_keys_strongest_first() {
+        let identifiers = ProductIdentifiers {
+            ean: Some("0012345678905".to_string()),
+            asin: Some("b07fz8s74r".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            alias_keys(&identifiers, "Apple iPhone 15 (128GB) - Black"),
+            vec![
+                "gtin:00012345678905",
+                "asin:B07FZ8S74R",
+                "title:apple iphone 15 128gb black",
+            ]
+        );
+        assert_eq!(
+            alias_keys(&ProductIdentifiers::default(), "iPhone 15"),
+            vec!["title:iphone 15"]
+        );
+    }
+
+    #[test]
+    fn test_cache_key_prefers_product() {
+        let id = Uuid::nil();
+        let none = ProductIdentifiers::default();
+        let with_gtin = ProductIdentifiers {
+            gtin: Some("00012345678905".to_string()),
+            ..Default::default()
+        };
+        let known = CompareOptions {
+            product_id: Some(id),
+            target_currency: Some("gbp".to_string()),
+            rank_by: Some(RankBy::Price),
+            ..Default::default()
+        };
+
+        // Every alias of a product shares its entry, per currency and ranking
+        let product_key = cache_key(&none, "iphone 15", &known);
+        assert!(product_key.starts_with("product:00000000-0000-0000-0000-000000000000:GBP:price:"));
+        assert_eq!(cache_key(&with_gtin, "iphone", &known), product_key);
+        let original = CompareOptions {
+            target_currency: None,
+            ..known.clone()
+        };
+        assert_ne!(cache_key(&none, "iphone 15", &original), product_key);
+
+        // Unknown products are keyed by query and identifiers
+        let unknown = CompareOptions::default();
+        let query_key = cache_key(&none, "iphone 15", &unknown);
+        assert!(query_key.starts_with("query:original:auto:"));
+        assert!(query_key.ends_with(":iphone 15"));
+        assert_ne!(cache_key(&with_gtin, "iphone 15", &unknown), query_key);
+
+        // Both variants are indexed under the product or query
+        assert_eq!(
+            cache_index(Some(id), "iphone 15"),
+            "product:00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(cache_index(None, "iphone 15"), "query:iphone 15");
+    }
+
+    #[test]
+    fn test_cache_key_covers_image_and_detail_budget() {
+        let none = ProductIdentifiers::default();
+        for product_id in [None, Some(Uuid::nil())] {
+            let plain = CompareOptions {
+                product_id,
+                ..Default::default()
+            };
+            let with_image = CompareOptions {
+                source_image: Some("https://example.com/iphone.jpg".to_string()),
+                ..plain.clone()
+            };
+            let with_budget = CompareOptions {
+                detail_fetch_budget: Some(0),
+                ..plain.clone()
+            };
+
+            let key = cache_key(&none, "iphone 15", &plain);
+            assert_ne!(cache_key(&none, "iphone 15", &with_image), key);
+            assert_ne!(cache_key(&none, "iphone 15", &with_budget), key);
+            assert_eq!(cache_key(&none, "iphone 15", &plain.clone()), key);
+        }
+    }
+
+    #[test]
+    fn test_new_product_uses_strongest_key() {
+        let identifiers = ProductIdentifiers {
+            upc: Some("012345678905".to_string()),
+            brand: Some("Samsung".to_string()),
+            ..Default::default()
+        };
+        let keys = alias_keys(&identifiers, "Samsung Galaxy A54 256GB");
+        let product = new_product(&identifiers, "Samsung Galaxy A54 256GB", &keys);
+
+        assert_eq!(product.canonical_key, "gtin:00012345678905");
+        assert_eq!(product.gtin.as_deref(), Some("00012345678905"));
+        assert_eq!(product.specs["storage_gb"], 256);
+    }
+}
@@ -3,112 +3,675 @@
 //! Provides accurate currency handling using Decimal for precision,
 //! with real-time exchange rates cached in Redis.
 
-use crate::AppError;
+use super::marketplace::Marketplace;
+use super::money::Money;
+use super::price_parser;
+use super::rate_history;
+use super::rate_providers::{self, ProviderRates, RateOutlier, RateProvider, RateSource};
+use super::rate_refresher::{self, RateRefresh};
+use crate::config::CurrencyConfig;
+use crate::{AppError, SitePrice};
 use deadpool_redis::Pool;
 use reqwest::Client;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::str::FromStr;
+use std::sync::LazyLock;
+use tokio::task::JoinSet;
 
-/// Supported currencies with ISO 4217 codes.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum Currency {
-    USD, // US Dollar
-    EUR, // Euro
-    GBP, // British Pound
-    NGN, // Nigerian Naira
-    INR, // Indian Rupee
-    CAD, // Canadian Dollar
-    AUD, // Australian Dollar
-    JPY, // Japanese Yen
+/// ISO 4217 table: codes, names, minor units, symbols and locale formats.
+///
+/// Generated from the iso-codes currency list and the glibc locale
+/// `LC_MONETARY` sections; symbols of widely traded currencies are set by
+/// hand so that dollar and yen variants stay distinguishable ("C$", "A$").
+const CURRENCY_TABLE: &str = include_str!("../../data/currencies.json");
+
+/// Rates per USD used only when no exchange rate source is available.
+///
+/// They date from `FALLBACK_RATES_DATE` and are reported with that date.
+const FALLBACK_RATES_PER_USD: [(&str, &str); 8] = [
+    ("USD", "1"),
+    ("EUR", "0.926"),
+    ("GBP", "0.787"),
+    ("NGN", "769.23"),
+    ("INR", "83.33"),
+    ("CAD", "1.351"),
+    ("AUD", "1.515"),
+    ("JPY", "149.25"),
+];
+
+/// Redis key of the current rates, expiring after the cache TTL.
+const RATES_KEY: &str = "exchange_rates:usd";
+
+/// Redis key of the last rates fetched from a provider, kept without expiry.
+const LAST_GOOD_RATES_KEY: &str = "exchange_rates:usd:last_good";
+
+/// Date the fallback rates were taken.
+const FALLBACK_RATES_DATE: &str = "2023-06-01T00:00:00Z";
+
+/// Source recorded for conversions that used a fallback rate.
+fn fallback_source() -> RateSource {
+    RateSource {
+        provider: "fallback".to_string(),
+        published_at: chrono::DateTime::parse_from_rfc3339(FALLBACK_RATES_DATE)
+            .map(|date| date.with_timezone(&chrono::Utc))
+            .unwrap_or_default(),
+    }
+}
+
+/// How amounts in a currency are written in a group of locales.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocaleFormat {
+    /// BCP 47 tags of the locales using this format (e.g., "fr-FR")
+    pub locales: Vec<String>,
+    /// Symbol as written in these locales
+    pub symbol: String,
+    /// Decimal separator
+    pub decimal: String,
+    /// Thousands separator (empty when digits are not grouped)
+    pub group: String,
+    /// Symbol written before the amount
+    pub symbol_first: bool,
+    /// Space between the symbol and the amount
+    pub space: bool,
+    /// Digit group sizes from the right, the last one repeating
+    /// (`[3]` for 1,234,567, `[3, 2]` for the Indian 12,34,567)
+    #[serde(default = "default_grouping")]
+    pub grouping: Vec<usize>,
+}
+
+fn default_grouping() -> Vec<usize> {
+    vec![3]
+}
+
+/// ISO 4217 metadata of a currency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrencyMetadata {
+    /// Alphabetic code (e.g., "NGN")
+    pub code: String,
+    /// Numeric code (e.g., "566")
+    pub numeric: String,
+    pub name: String,
+    /// Digits after the decimal point; `None` for units that are not money (gold, XDR)
+    pub minor_units: Option<u32>,
+    /// Symbol used when no locale is known
+    pub symbol: String,
+    /// Other symbols found in local formats (e.g., "$" for CAD)
+    pub alt_symbols: Vec<String>,
+    pub formats: Vec<LocaleFormat>,
+}
+
+impl LocaleFormat {
+    /// Returns how amounts are written in a locale, whatever the currency.
+    ///
+    /// # Arguments
+    /// * `locale` - BCP 47 tag (e.g., "de-DE"); "de_DE" is accepted too
+    ///
+    /// # Returns
+    /// * `Some(&LocaleFormat)` - First format listing the locale
+    /// * `None` - Unknown locale
+    pub fn for_locale(locale: &str) -> Option<&'static LocaleFormat> {
+        let locale = locale.replace('_', "-");
+        CURRENCIES
+            .currencies
+            .iter()
+            .flat_map(|currency| &currency.formats)
+            .find(|format| {
+                format
+                    .locales
+                    .iter()
+                    .any(|l| l.eq_ignore_ascii_case(&locale))
+            })
+    }
 }
 
+/// Parsed currency table, in code order.
+struct CurrencyTable {
+    currencies: Vec<CurrencyMetadata>,
+    by_code: HashMap<String, usize>,
+}
+
+static CURRENCIES: LazyLock<CurrencyTable> = LazyLock::new(|| {
+    let mut currencies: Vec<CurrencyMetadata> =
+        serde_json::from_str(CURRENCY_TABLE).expect("embedded currency table is valid JSON");
+    currencies.sort_by(|a, b| a.code.cmp(&b.code));
+    let by_code = currencies
+        .iter()
+        .enumerate()
+        .map(|(index, currency)| (currency.code.clone(), index))
+        .collect();
+
+    CurrencyTable {
+        currencies,
+        by_code,
+    }
+});
+
+/// ISO 4217 currency.
+///
+/// Any code in the embedded table is supported; the constants cover the
+/// currencies the scrapers detect from symbols and storefronts.
+/// Serialized as its code (e.g., "USD").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency(&'static str);
+
 impl Currency {
+    pub const USD: Currency = Currency("USD"); // US Dollar
+    pub const EUR: Currency = Currency("EUR"); // Euro
+    pub const GBP: Currency = Currency("GBP"); // British Pound
+    pub const NGN: Currency = Currency("NGN"); // Nigerian Naira
+    pub const INR: Currency = Currency("INR"); // Indian Rupee
+    pub const CAD: Currency = Currency("CAD"); // Canadian Dollar
+    pub const AUD: Currency = Currency("AUD"); // Australian Dollar
+    pub const JPY: Currency = Currency("JPY"); // Japanese Yen
+
+    /// Currencies preferred when several share a symbol ("$" is USD, "¥" is JPY).
+    const PREFERRED: [Currency; 8] = [
+        Self::USD,
+        Self::EUR,
+        Self::GBP,
+        Self::NGN,
+        Self::INR,
+        Self::CAD,
+        Self::AUD,
+        Self::JPY,
+    ];
+
+    /// Finds the currency written with a symbol.
+    ///
+    /// # Arguments
+    /// * `symbol` - Symbol as written (e.g., "₦", "C$", "KSh"); case-sensitive
+    ///
+    /// # Returns
+    /// * `Some(Currency)` - The preferred currency with that symbol, else the
+    ///   first in code order, else the first with it as an alternative symbol
+    /// * `None` - No currency uses the symbol
+    pub fn from_symbol(symbol: &str) -> Option<Currency> {
+        let currency_of = |metadata: &'static CurrencyMetadata| Currency(&metadata.code);
+        Self::PREFERRED
+            .into_iter()
+            .find(|currency| currency.symbol() == symbol)
+            .or_else(|| {
+                CURRENCIES
+                    .currencies
+                    .iter()
+                    .find(|metadata| metadata.symbol == symbol)
+                    .map(currency_of)
+            })
+            .or_else(|| {
+                CURRENCIES
+                    .currencies
+                    .iter()
+                    .find(|metadata| metadata.alt_symbols.iter().any(|alt| alt == symbol))
+                    .map(currency_of)
+            })
+    }
+
+    /// Returns every currency written with a symbol, in code order.
+    ///
+    /// Primary, alternative and locale symbols all count, so "$" returns
+    /// USD, CAD, MXN, SGD and others.
+    pub fn with_symbol(symbol: &str) -> Vec<Currency> {
+        CURRENCIES
+            .currencies
+            .iter()
+            .filter(|metadata| {
+                metadata.symbol == symbol
+                    || metadata.alt_symbols.iter().any(|alt| alt == symbol)
+                    || metadata
+                        .formats
+                        .iter()
+                        .any(|format| format.symbol == symbol)
+            })
+            .map(|metadata| Currency(&metadata.code))
+            .collect()
+    }
+
+    /// Returns the metadata of every currency that is money (has minor units), by code.
+    pub fn all() -> impl Iterator<Item = &'static CurrencyMetadata> {
+        CURRENCIES
+            .currencies
+            .iter()
+            .filter(|currency| currency.minor_units.is_some())
+    }
+
+    /// Returns the ISO 4217 metadata of the currency.
+    pub fn metadata(&self) -> &'static CurrencyMetadata {
+        let index = CURRENCIES.by_code[self.0];
+        &CURRENCIES.currencies[index]
+    }
+
     /// Returns the currency symbol.
-    pub fn symbol(&self) -> &str {
-        match self {
-            Currency::USD => "$",
-            Currency::EUR => "€",
-            Currency::GBP => "£",
-            Currency::NGN => "₦",
-            Currency::INR => "₹",
-            Currency::CAD => "C$",
-            Currency::AUD => "A$",
-            Currency::JPY => "¥",
-        }
+    pub fn symbol(&self) -> &'static str {
+        &self.metadata().symbol
     }
 
     /// Returns the currency code (ISO 4217).
-    pub fn code(&self) -> &str {
-        match self {
-            Currency::USD => "USD",
-            Currency::EUR => "EUR",
-            Currency::GBP => "GBP",
-            Currency::NGN => "NGN",
-            Currency::INR => "INR",
-            Currency::CAD => "CAD",
-            Currency::AUD => "AUD",
-            Currency::JPY => "JPY",
-        }
+    pub fn code(&self) -> &'static str {
+        self.0
+    }
+
+    /// Returns the ISO 4217 currency name (e.g., "Naira").
+    pub fn name(&self) -> &'static str {
+        &self.metadata().name
+    }
+
+    /// Returns the number of decimal places amounts are given in (2 for non-money units).
+    pub fn minor_units(&self) -> u32 {
+        self.metadata().minor_units.unwrap_or(2)
+    }
+
+    /// Returns how amounts are written in a locale.
+    ///
+    /// # Arguments
+    /// * `locale` - BCP 47 tag (e.g., "fr-FR"); "fr_FR" is accepted too
+    ///
+    /// # Returns
+    /// * `Some(&LocaleFormat)` - The currency is used in that locale
+    /// * `None` - The table has no format for that locale
+    pub fn locale_format(&self, locale: &str) -> Option<&'static LocaleFormat> {
+        let locale = locale.replace('_', "-");
+        self.metadata().formats.iter().find(|format| {
+            format
+                .locales
+                .iter()
+                .any(|l| l.eq_ignore_ascii_case(&locale))
+        })
     }
 
-    /// Returns static fallback conversion rate to USD.
-    /// Used only when exchange rate API is unavailable.
-    pub fn fallback_to_usd_rate(&self) -> Decimal {
-        match self {
-            Currency::USD => Decimal::from(1),
-            Currency::EUR => Decimal::from_str("1.08").unwrap(),
-            Currency::GBP => Decimal::from_str("1.27").unwrap(),
-            Currency::NGN => Decimal::from_str("0.0013").unwrap(),
-            Currency::INR => Decimal::from_str("0.012").unwrap(),
-            Currency::CAD => Decimal::from_str("0.74").unwrap(),
-            Currency::AUD => Decimal::from_str("0.66").unwrap(),
-            Currency::JPY => Decimal::from_str("0.0067").unwrap(),
+    /// Formats an amount, rounded to the currency's minor units.
+    ///
+    /// # Arguments
+    /// * `amount` - Amount in this currency
+    /// * `locale` - Optional BCP 47 tag; without a known format the amount is
+    ///   written "<symbol><amount>" with a decimal point and no grouping
+    ///
+    /// # Returns
+    /// * Formatted amount (e.g., "1 299,99 €" for EUR in "fr-FR")
+    pub fn format(&self, amount: Decimal, locale: Option<&str>) -> String {
+        let minor_units = self.minor_units();
+        let digits = format!(
+            "{:.*}",
+            minor_units as usize,
+            amount.abs().round_dp(minor_units)
+        );
+        let sign = if amount.is_sign_negative() && !amount.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+
+        let Some(format) = locale.and_then(|locale| self.locale_format(locale)) else {
+            return format!("{}{}{}", sign, self.symbol(), digits);
+        };
+
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+        let mut grouped = group_digits(integer, &format.group, &format.grouping);
+        if !fraction.is_empty() {
+            grouped.push_str(&format.decimal);
+            grouped.push_str(fraction);
+        }
+
+        let space = if format.space { " " } else { "" };
+        if format.symbol_first {
+            format!("{}{}{}{}", sign, format.symbol, space, grouped)
+        } else {
+            format!("{}{}{}{}", sign, grouped, space, format.symbol)
         }
     }
+
+    /// Returns the static fallback rate in units per USD.
+    /// Used only when no exchange rate source is available.
+    pub fn fallback_rate_per_usd(&self) -> Option<Decimal> {
+        FALLBACK_RATES_PER_USD
+            .iter()
+            .find(|(code, _)| *code == self.0)
+            .and_then(|(_, rate)| Decimal::from_str(rate).ok())
+    }
+
+    /// Returns the static fallback conversion rate to USD (USD per unit).
+    /// Used only when no exchange rate source is available.
+    pub fn fallback_to_usd_rate(&self) -> Option<Decimal> {
+        self.fallback_rate_per_usd().map(|rate| Decimal::ONE / rate)
+    }
+}
+
+/// Inserts group separators into a run of ASCII digits.
+///
+/// # Arguments
+/// * `integer` - Integer digits (e.g., "1234567")
+/// * `separator` - Group separator (empty for no grouping)
+/// * `grouping` - Group sizes from the right, the last one repeating
+///
+/// # Returns
+/// * Grouped digits (e.g., "12,34,567" for `[3, 2]`)
+fn group_digits(integer: &str, separator: &str, grouping: &[usize]) -> String {
+    let mut groups: Vec<&str> = Vec::new();
+    let mut end = integer.len();
+    let mut sizes = grouping.iter().copied().filter(|size| *size > 0);
+    let mut size = sizes.next().unwrap_or(3);
+    while end > size {
+        groups.push(&integer[end - size..end]);
+        end -= size;
+        size = sizes.next().unwrap_or(size);
+    }
+    groups.push(&integer[..end]);
+    groups.reverse();
+    groups.join(separator)
 }
 
 impl FromStr for Currency {
     type Err = AppError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_uppercase().as_str() {
-            "USD" => Ok(Currency::USD),
-            "EUR" => Ok(Currency::EUR),
-            "GBP" => Ok(Currency::GBP),
-            "NGN" => Ok(Currency::NGN),
-            "INR" => Ok(Currency::INR),
-            "CAD" => Ok(Currency::CAD),
-            "AUD" => Ok(Currency::AUD),
-            "JPY" => Ok(Currency::JPY),
-            _ => Err(AppError::Parse(format!("Unsupported currency: {}", s))),
-        }
+        let code = s.trim().to_uppercase();
+        CURRENCIES
+            .by_code
+            .get(&code)
+            .map(|&index| Currency(&CURRENCIES.currencies[index].code))
+            .ok_or_else(|| AppError::Parse(format!("Unsupported currency: {}", s)))
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Currency::from_str(&code).map_err(serde::de::Error::custom)
     }
 }
 
-/// Exchange rate API response structure (ExchangeRate-API format).
-#[derive(Debug, Deserialize)]
-struct ExchangeRateResponse {
-    result: String,
-    base_code: String,
-    conversion_rates: HashMap<String, f64>,
+impl sqlx::Type<sqlx::Postgres> for Currency {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        <&str as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+        <&str as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, sqlx::Postgres> for Currency {
+    fn decode(value: sqlx::postgres::PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let code = <&str as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        Ok(Currency::from_str(code)?)
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
 }
 
 /// Cached exchange rates with timestamp.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExchangeRates {
     pub base: String,
+    /// Units of each currency per `base`
     pub rates: HashMap<String, Decimal>,
+    /// When the rates were fetched
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Provider and publication time of each rate
+    #[serde(default)]
+    pub sources: HashMap<String, RateSource>,
+    /// Provider rates rejected because they disagreed with the others
+    #[serde(default)]
+    pub outliers: Vec<RateOutlier>,
+}
+
+impl ExchangeRates {
+    /// Returns a currency's rate per USD and where it came from.
+    ///
+    /// Currencies the providers do not cover use the built-in fallback rate.
+    ///
+    /// # Arguments
+    /// * `currency` - Currency to look up
+    ///
+    /// # Returns
+    /// * `Some((Decimal, RateSource))` - Units of `currency` per USD, and its source
+    /// * `None` - No non-zero rate is known
+    pub fn rate_per_usd(&self, currency: &Currency) -> Option<(Decimal, RateSource)> {
+        let provided = self.rates.get(currency.code()).copied().map(|rate| {
+            let source = self
+                .sources
+                .get(currency.code())
+                .cloned()
+                .unwrap_or_else(|| RateSource {
+                    provider: "unknown".to_string(),
+                    published_at: self.updated_at,
+                });
+            (rate, source)
+        });
+        provided
+            .or_else(|| Some((currency.fallback_rate_per_usd()?, fallback_source())))
+            .filter(|(rate, _)| !rate.is_zero())
+    }
+
+    /// Returns the USD value of one unit of a currency and where it came from.
+    ///
+    /// # Arguments
+    /// * `currency` - Currency to look up
+    ///
+    /// # Returns
+    /// * `Some(UsdRate)` - USD per unit of `currency`
+    /// * `None` - No non-zero rate is known
+    pub fn usd_rate(&self, currency: &Currency) -> Option<UsdRate> {
+        let (rate_per_usd, source) = self.rate_per_usd(currency)?;
+        Some(UsdRate {
+            rate: Decimal::ONE / rate_per_usd,
+            source,
+        })
+    }
+
+    /// Returns every provided rate per unit of another base currency.
+    ///
+    /// # Arguments
+    /// * `base` - Currency the rates are expressed against
+    ///
+    /// # Returns
+    /// * `Some((rates, sources))` - Units of each currency per `base`, rounded
+    ///   to 10 decimal places, and the sources of the rates
+    /// * `None` - No rate is known for `base`
+    pub fn rebased(&self, base: &Currency) -> Option<(BTreeMap<String, Decimal>, Vec<RateSource>)> {
+        let (base_rate, base_source) = self.rate_per_usd(base)?;
+        let mut sources = vec![base_source];
+        let mut rates = BTreeMap::new();
+        for code in self.rates.keys() {
+            let Some((rate, source)) = Currency::from_str(code)
+                .ok()
+                .and_then(|currency| self.rate_per_usd(&currency))
+            else {
+                continue;
+            };
+            rates.insert(code.clone(), (rate / base_rate).round_dp(10));
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        rates.insert(base.code().to_string(), Decimal::ONE);
+        Some((rates, sources))
+    }
+
+    /// Converts an amount with these rates and reports which rates were used.
+    ///
+    /// Converting several amounts from one snapshot keeps them, and the
+    /// rate metadata built from their sources, consistent with each other.
+    ///
+    /// # Arguments
+    /// * `money` - Amount to convert
+    /// * `to` - Target currency
+    ///
+    /// # Returns
+    /// * `Ok(Conversion)` - Converted amount, effective rate and rate sources
+    /// * `Err(AppError)` - No rate is known for either currency
+    pub fn convert(&self, money: Money, to: &Currency) -> Result<Conversion, AppError> {
+        let from = &money.currency();
+        if from == to {
+            return Ok(Conversion {
+                amount: money,
+                rate: Decimal::ONE,
+                sources: Vec::new(),
+                fetched_at: self.updated_at,
+            });
+        }
+
+        let rate = |currency: &Currency| -> Result<(Decimal, RateSource), AppError> {
+            self.rate_per_usd(currency).ok_or_else(|| {
+                AppError::NotFound(format!("No exchange rate for {}", currency.code()))
+            })
+        };
+        let (from_rate, from_source) = rate(from)?;
+        let (to_rate, to_source) = rate(to)?;
+
+        let mut sources = vec![from_source];
+        if !sources.contains(&to_source) {
+            sources.push(to_source);
+        }
+
+        // Rates are units per USD, so the cross rate goes through USD
+        let rate = to_rate / from_rate;
+        Ok(Conversion {
+            amount: money.exchange(rate, *to),
+            rate,
+            sources,
+            fetched_at: self.updated_at,
+        })
+    }
+}
+
+/// Where the rates behind a conversion came from and how old they are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateMetadata {
+    /// Providers of the rates used (e.g., "ecb"), "fallback" for built-in rates
+    pub providers: Vec<String>,
+    /// When the rates were fetched from the providers
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    /// Publication time of the oldest rate used (absent when no rate was needed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The built-in fallback rates were used for at least one currency
+    pub fallback_used: bool,
+    /// The oldest rate used is older than the staleness limit
+    pub stale: bool,
+}
+
+impl RateMetadata {
+    /// Summarizes the sources of the rates used.
+    ///
+    /// # Arguments
+    /// * `sources` - Sources of every rate used
+    /// * `fetched_at` - When the rates were fetched
+    /// * `stale_after` - Age past which a rate is stale
+    /// * `now` - Current time
+    pub fn new(
+        sources: &[RateSource],
+        fetched_at: chrono::DateTime<chrono::Utc>,
+        stale_after: chrono::Duration,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        let mut providers: Vec<String> = Vec::new();
+        for source in sources {
+            if !providers.contains(&source.provider) {
+                providers.push(source.provider.clone());
+            }
+        }
+
+        let mut metadata = Self {
+            fallback_used: providers.iter().any(|provider| provider == "fallback"),
+            providers,
+            fetched_at,
+            published_at: sources.iter().map(|source| source.published_at).min(),
+            stale: false,
+        };
+        metadata.refresh(stale_after, now);
+        metadata
+    }
+
+    /// Recomputes `stale`, e.g., for a comparison served from the cache.
+    pub fn refresh(&mut self, stale_after: chrono::Duration, now: chrono::DateTime<chrono::Utc>) {
+        let oldest = self
+            .published_at
+            .unwrap_or(self.fetched_at)
+            .min(self.fetched_at);
+        self.stale = self.fallback_used || now - oldest > stale_after;
+    }
+}
+
+/// Rate a listing's price was normalized to USD with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsdRate {
+    /// USD per unit of the listing's currency
+    pub rate: Decimal,
+    /// Provider and publication time of the rate
+    #[serde(flatten)]
+    pub source: RateSource,
+}
+
+/// Normalizes every listing's `price_usd` with the given rates.
+///
+/// Scrapers report prices in the storefront's currency; cross-site sorting,
+/// outlier filtering and the best deal all compare `price_usd`, so a listing
+/// whose currency has no rate cannot be compared and is dropped.
+///
+/// # Arguments
+/// * `prices` - Scraped listings
+/// * `rates` - Current exchange rates
+///
+/// # Returns
+/// * Listings with `price_usd` and `usd_rate` set
+pub fn normalize_to_usd(prices: Vec<SitePrice>, rates: &ExchangeRates) -> Vec<SitePrice> {
+    prices
+        .into_iter()
+        .filter_map(|mut price| {
+            let Some(usd_rate) = rates.usd_rate(&price.price.currency()) else {
+                tracing::warn!(
+                    site = %price.site,
+                    currency = %price.price.currency(),
+                    "Dropping listing without a USD rate"
+                );
+                return None;
+            };
+
+            price.price_usd = price.price.exchange(usd_rate.rate, Currency::USD).amount();
+            price.usd_rate = Some(usd_rate);
+            Some(price)
+        })
+        .collect()
+}
+
+/// Amount converted between currencies, with the rates it used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conversion {
+    /// Converted amount, rounded to the target's minor units
+    pub amount: Money,
+    /// Units of the target currency per unit of the source currency
+    pub rate: Decimal,
+    /// Provider and publication time of the source and target rates
+    pub sources: Vec<RateSource>,
+    /// When the rates were fetched
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
 }
 
 /// Currency service for handling conversions and exchange rates.
 pub struct CurrencyService {
     redis_pool: Pool,
+    db_pool: PgPool,
     http_client: Client,
-    api_url: String,
+    providers: Vec<RateProvider>,
+    outlier_tolerance_percent: Decimal,
     cache_ttl_hours: u64,
+    stale_after_hours: i64,
+    change_alert_percent: Decimal,
+    refresh_retry_seconds: u64,
 }
 
 impl CurrencyService {
@@ -116,24 +679,55 @@ impl CurrencyService {
     ///
     /// # Arguments
     /// * `redis_pool` - Redis connection pool for caching
+    /// * `db_pool` - Database pool for daily rate snapshots
     /// * `http_client` - HTTP client for API requests
-    /// * `api_url` - Exchange rate API URL (default: ExchangeRate-API)
-    /// * `cache_ttl_hours` - Cache time-to-live in hours
+    /// * `config` - Providers, cache TTL, staleness and alert thresholds
     pub fn new(
         redis_pool: Pool,
+        db_pool: PgPool,
         http_client: Client,
-        api_url: String,
-        cache_ttl_hours: u64,
+        config: &CurrencyConfig,
     ) -> Self {
         Self {
             redis_pool,
+            db_pool,
             http_client,
-            api_url,
-            cache_ttl_hours,
+            providers: config.providers.clone(),
+            outlier_tolerance_percent: config.outlier_tolerance_percent,
+            cache_ttl_hours: config.cache_ttl_hours,
+            stale_after_hours: config.stale_after_hours,
+            change_alert_percent: config.change_alert_percent,
+            refresh_retry_seconds: config.refresh_retry_seconds,
         }
     }
 
-    /// Fetches exchange rates from cache or API.
+    /// Summarizes the sources of the rates behind a conversion.
+    ///
+    /// # Arguments
+    /// * `sources` - Sources of every rate used
+    /// * `fetched_at` - When the rates were fetched
+    pub fn rate_metadata(
+        &self,
+        sources: &[RateSource],
+        fetched_at: chrono::DateTime<chrono::Utc>,
+    ) -> RateMetadata {
+        RateMetadata::new(
+            sources,
+            fetched_at,
+            chrono::Duration::hours(self.stale_after_hours),
+            chrono::Utc::now(),
+        )
+    }
+
+    /// Recomputes whether rates reported earlier have gone stale.
+    pub fn refresh_rate_metadata(&self, metadata: &mut RateMetadata) {
+        metadata.refresh(
+            chrono::Duration::hours(self.stale_after_hours),
+            chrono::Utc::now(),
+        );
+    }
+
+    /// Fetches exchange rates from cache or the provider chain.
     ///
     /// # Returns
     /// * `Ok(ExchangeRates)` - Current exchange rates
@@ -145,13 +739,18 @@ impl CurrencyService {
             return Ok(cached);
         }
 
-        // Cache miss or expired - fetch from API
-        tracing::info!("Fetching fresh exchange rates from API");
+        // Cache miss or expired - fetch from the providers
+        tracing::info!("Fetching fresh exchange rates from providers");
         self.fetch_and_cache_rates().await
     }
 
     /// Fetches rates from cache.
     async fn get_cached_rates(&self) -> Result<ExchangeRates, AppError> {
+        self.read_rates(RATES_KEY).await
+    }
+
+    /// Reads rates stored under a Redis key.
+    async fn read_rates(&self, key: &str) -> Result<ExchangeRates, AppError> {
         let mut conn = self
             .redis_pool
             .get()
@@ -159,7 +758,7 @@ impl CurrencyService {
             .map_err(|e| AppError::Cache(format!("Redis connection failed: {}", e)))?;
 
         let cached_json: String = redis::cmd("GET")
-            .arg("exchange_rates:usd")
+            .arg(key)
             .query_async(&mut conn)
             .await
             .map_err(|e| AppError::Cache(format!("Cache read failed: {}", e)))?;
@@ -168,59 +767,137 @@ impl CurrencyService {
             .map_err(|e| AppError::Cache(format!("Invalid cached data: {}", e)))
     }
 
-    /// Fetches rates from API and caches them.
-    async fn fetch_and_cache_rates(&self) -> Result<ExchangeRates, AppError> {
-        // Fetch from API
-        let response = self
-            .http_client
-            .get(&self.api_url)
-            .send()
+    /// Fetches rates from the providers and replaces the cached rates.
+    ///
+    /// The background refresher calls this ahead of the cache expiry and
+    /// retries on failure, so unlike `get_exchange_rates` it never falls
+    /// back. Fetched rates are saved as the day's snapshot, and rates that
+    /// moved more than the alert threshold since the last fetch are reported.
+    ///
+    /// # Returns
+    /// * `Ok(RateRefresh)` - New rates and the rates that moved past the threshold
+    /// * `Err(AppError)` - Every provider failed; the cached rates are kept
+    pub async fn refresh_rates(&self) -> Result<RateRefresh, AppError> {
+        let rates = self
+            .fetch_from_providers()
             .await
-            .map_err(|e| AppError::Network(format!("Exchange rate API request failed: {}", e)))?;
-
-        if !response.status().is_success() {
-            // API failed - try to use fallback rates
-            tracing::warn!(
-                "Exchange rate API returned {}, using fallback rates",
-                response.status()
-            );
-            return Ok(self.fallback_rates());
-        }
+            .ok_or_else(|| AppError::Network("All exchange rate providers failed".to_string()))?;
 
-        let api_response: ExchangeRateResponse = response
-            .json()
-            .await
-            .map_err(|e| AppError::Network(format!("Failed to parse API response: {}", e)))?;
+        let changes = match self.read_rates(LAST_GOOD_RATES_KEY).await {
+            Ok(previous) => {
+                rate_refresher::rate_changes(&previous, &rates, self.change_alert_percent)
+            }
+            Err(_) => Vec::new(),
+        };
+        rate_refresher::report_changes(&changes);
 
-        if api_response.result != "success" {
-            tracing::warn!("Exchange rate API returned non-success result, using fallback");
-            return Ok(self.fallback_rates());
+        // Cache the rates, and keep them as the last good rates without expiry
+        let ttl_seconds = self.cache_ttl_hours * 3600;
+        if let Err(e) = self.cache_rates(RATES_KEY, &rates, Some(ttl_seconds)).await {
+            tracing::warn!("Failed to cache exchange rates: {}", e);
+        }
+        if let Err(e) = self.cache_rates(LAST_GOOD_RATES_KEY, &rates, None).await {
+            tracing::warn!("Failed to save last good exchange rates: {}", e);
+        }
+        if let Err(e) = rate_history::record_snapshots(&self.db_pool, &rates).await {
+            tracing::warn!("Failed to save exchange rate snapshot: {}", e);
         }
 
-        // Convert f64 rates to Decimal
-        let mut rates = HashMap::new();
-        for (code, rate) in api_response.conversion_rates {
-            if let Some(decimal_rate) = Decimal::from_f64_retain(rate) {
-                rates.insert(code, decimal_rate);
+        Ok(RateRefresh { rates, changes })
+    }
+
+    /// Refreshes rates on a cache miss.
+    ///
+    /// When every provider fails, the last rates fetched are served (and
+    /// cached briefly, so the providers are retried soon); the built-in
+    /// fallback rates are used only if no fetch ever succeeded.
+    async fn fetch_and_cache_rates(&self) -> Result<ExchangeRates, AppError> {
+        let error = match self.refresh_rates().await {
+            Ok(refresh) => return Ok(refresh.rates),
+            Err(e) => e,
+        };
+
+        match self.read_rates(LAST_GOOD_RATES_KEY).await {
+            Ok(last_good) => {
+                tracing::warn!(
+                    error = %error,
+                    fetched_at = %last_good.updated_at,
+                    "Exchange rate providers failed, using last good rates"
+                );
+                let ttl_seconds = self.refresh_retry_seconds.max(1);
+                if let Err(e) = self
+                    .cache_rates(RATES_KEY, &last_good, Some(ttl_seconds))
+                    .await
+                {
+                    tracing::warn!("Failed to cache exchange rates: {}", e);
+                }
+                Ok(last_good)
+            }
+            Err(_) => {
+                tracing::warn!("All exchange rate providers failed, using fallback rates");
+                Ok(self.fallback_rates())
             }
         }
+    }
 
-        let exchange_rates = ExchangeRates {
-            base: api_response.base_code,
-            rates,
-            updated_at: chrono::Utc::now(),
-        };
+    /// Fetches every provider concurrently and cross-checks them.
+    ///
+    /// Providers that fail or publish no USD rate are skipped.
+    ///
+    /// # Returns
+    /// * `Some(ExchangeRates)` - Merged rates
+    /// * `None` - Every provider failed
+    async fn fetch_from_providers(&self) -> Option<ExchangeRates> {
+        let mut tasks = JoinSet::new();
+        for (index, provider) in self.providers.iter().cloned().enumerate() {
+            let client = self.http_client.clone();
+            tasks.spawn(async move { (index, provider.fetch(&client).await) });
+        }
 
-        // Cache the rates
-        if let Err(e) = self.cache_rates(&exchange_rates).await {
-            tracing::warn!("Failed to cache exchange rates: {}", e);
+        let mut fetched: Vec<(usize, ProviderRates)> = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, Ok(rates))) => {
+                    let provider = rates.provider.as_str();
+                    match rates.rebased_to_usd() {
+                        Some(rates) => fetched.push((index, rates)),
+                        None => tracing::warn!(provider, "Exchange rate provider has no USD rate"),
+                    }
+                }
+                Ok((index, Err(e))) => tracing::warn!(
+                    provider = self.providers[index].kind.as_str(),
+                    error = %e,
+                    "Exchange rate provider failed"
+                ),
+                Err(e) => tracing::warn!(error = %e, "Exchange rate task failed"),
+            }
         }
 
-        Ok(exchange_rates)
+        if fetched.is_empty() {
+            return None;
+        }
+
+        // Restore chain order, which decides between providers that agree
+        fetched.sort_by_key(|(index, _)| *index);
+        let chain: Vec<ProviderRates> = fetched.into_iter().map(|(_, rates)| rates).collect();
+        let merged = rate_providers::merge(&chain, self.outlier_tolerance_percent);
+
+        Some(ExchangeRates {
+            base: "USD".to_string(),
+            rates: merged.rates,
+            updated_at: chrono::Utc::now(),
+            sources: merged.sources,
+            outliers: merged.outliers,
+        })
     }
 
-    /// Caches exchange rates in Redis with TTL.
-    async fn cache_rates(&self, rates: &ExchangeRates) -> Result<(), AppError> {
+    /// Stores exchange rates in Redis, with a TTL unless `ttl_seconds` is `None`.
+    async fn cache_rates(
+        &self,
+        key: &str,
+        rates: &ExchangeRates,
+        ttl_seconds: Option<u64>,
+    ) -> Result<(), AppError> {
         let json = serde_json::to_string(rates)
             .map_err(|e| AppError::Cache(format!("Failed to serialize rates: {}", e)))?;
 
@@ -230,12 +907,13 @@ impl CurrencyService {
             .await
             .map_err(|e| AppError::Cache(format!("Redis connection failed: {}", e)))?;
 
-        let ttl_seconds = self.cache_ttl_hours * 3600;
+        let mut command = redis::cmd("SET");
+        command.arg(key).arg(json);
+        if let Some(ttl_seconds) = ttl_seconds {
+            command.arg("EX").arg(ttl_seconds);
+        }
 
-        let _: () = redis::cmd("SETEX")
-            .arg("exchange_rates:usd")
-            .arg(ttl_seconds)
-            .arg(json)
+        let _: () = command
             .query_async(&mut conn)
             .await
             .map_err(|e| AppError::Cache(format!("Cache write failed: {}", e)))?;
@@ -243,286 +921,262 @@ impl CurrencyService {
         Ok(())
     }
 
-    /// Returns fallback exchange rates when API is unavailable.
+    /// Returns fallback exchange rates when every provider is unavailable.
+    ///
+    /// Rates are units per USD, like the providers', so `convert` treats both
+    /// alike; their sources carry the table's fixed date, so stale fallback
+    /// conversions are visible rather than passed off as current.
     fn fallback_rates(&self) -> ExchangeRates {
-        let mut rates = HashMap::new();
-
-        // Add all supported currencies with fallback rates
-        for currency in [
-            Currency::USD,
-            Currency::EUR,
-            Currency::GBP,
-            Currency::NGN,
-            Currency::INR,
-            Currency::CAD,
-            Currency::AUD,
-            Currency::JPY,
-        ] {
-            rates.insert(currency.code().to_string(), currency.fallback_to_usd_rate());
-        }
+        let rates: HashMap<String, Decimal> = FALLBACK_RATES_PER_USD
+            .iter()
+            .filter_map(|(code, rate)| Some((code.to_string(), Decimal::from_str(rate).ok()?)))
+            .collect();
+        let sources = rates
+            .keys()
+            .map(|code| (code.clone(), fallback_source()))
+            .collect();
 
         ExchangeRates {
             base: "USD".to_string(),
             rates,
             updated_at: chrono::Utc::now(),
+            sources,
+            outliers: Vec::new(),
         }
     }
 
-    /// Converts an amount from one currency to another.
+    /// Converts an amount to another currency.
+    ///
+    /// # Arguments
+    /// * `money` - Amount to convert
+    /// * `to` - Target currency
+    ///
+    /// # Returns
+    /// * `Ok(Money)` - Amount in `to`, rounded to its minor units
+    /// * `Err(AppError)` - No rate is known for either currency
+    pub async fn convert(&self, money: Money, to: &Currency) -> Result<Money, AppError> {
+        Ok(self.convert_with_sources(money, to).await?.amount)
+    }
+
+    /// Converts an amount and reports which rates were used.
+    ///
+    /// Currencies the providers do not cover use the built-in fallback
+    /// rates, recorded with provider "fallback" and their fixed date.
     ///
     /// # Arguments
-    /// * `amount` - Amount to convert
-    /// * `from` - Source currency
+    /// * `money` - Amount to convert
     /// * `to` - Target currency
     ///
     /// # Returns
-    /// * Converted amount in target currency
-    pub async fn convert(
+    /// * `Ok(Conversion)` - Converted amount, effective rate and rate sources
+    /// * `Err(AppError)` - No rate is known for either currency
+    pub async fn convert_with_sources(
         &self,
-        amount: Decimal,
-        from: &Currency,
+        money: Money,
         to: &Currency,
-    ) -> Result<Decimal, AppError> {
+    ) -> Result<Conversion, AppError> {
+        let from = &money.currency();
         if from == to {
-            return Ok(amount);
+            return Ok(Conversion {
+                amount: money,
+                rate: Decimal::ONE,
+                sources: Vec::new(),
+                fetched_at: chrono::Utc::now(),
+            });
         }
 
-        let rates = self.get_exchange_rates().await?;
-
-        // Convert from source to USD, then USD to target
-        let from_rate = rates
-            .rates
-            .get(from.code())
-            .copied()
-            .unwrap_or_else(|| from.fallback_to_usd_rate());
+        self.get_exchange_rates().await?.convert(money, to)
+    }
 
-        let to_rate = rates
-            .rates
-            .get(to.code())
-            .copied()
-            .unwrap_or_else(|| to.fallback_to_usd_rate());
+    /// Converts an amount to USD using current rates.
+    pub async fn convert_to_usd(&self, money: Money) -> Result<Money, AppError> {
+        self.convert(money, &Currency::USD).await
+    }
+}
 
-        // If base is USD: amount_in_target = amount_in_source / from_rate * to_rate
-        // Since ExchangeRate-API uses USD as base, rates are already USD-based
-        let amount_in_usd = amount / from_rate;
-        let result = amount_in_usd * to_rate;
+/// What a listing's currency was resolved from, strongest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurrencyEvidence {
+    /// Declared by the page's structured data or the site's API
+    Declared,
+    /// ISO code written next to the amount ("2,499 KES")
+    Code,
+    /// Currency of the marketplace the listing comes from
+    Marketplace,
+    /// Symbol used by a single currency ("₦", "€")
+    Symbol,
+    /// Symbol shared by several currencies ("$", "¥"), read as the most traded one
+    SharedSymbol,
+    /// Nothing to go on; USD assumed
+    Default,
+}
 
-        Ok(result)
-    }
+/// Currency of a listing, with how sure the resolution is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedCurrency {
+    pub currency: Currency,
+    pub evidence: CurrencyEvidence,
+    /// Confidence (0-100) that `currency` is right
+    pub confidence: u8,
+}
 
-    /// Converts an amount to USD using current rates.
-    pub async fn convert_to_usd(
-        &self,
-        amount: Decimal,
-        from: &Currency,
-    ) -> Result<Decimal, AppError> {
-        self.convert(amount, from, &Currency::USD).await
+impl ResolvedCurrency {
+    fn new(currency: Currency, evidence: CurrencyEvidence, confidence: u8) -> Self {
+        Self {
+            currency,
+            evidence,
+            confidence,
+        }
     }
 }
 
-/// Detects currency from a price string.
+/// Resolves the currency of a price from what the source declares and shows.
 ///
-/// Looks for currency symbols and codes in the string.
+/// A declared currency (structured data, API field) wins, then an ISO code
+/// written in the text, then the marketplace's currency, then the symbol.
+/// A "$" on amazon.ca is therefore CAD, and a "¥" on jd.com is CNY. When the
+/// text and the source disagree the stronger evidence is kept, a warning is
+/// logged, and the confidence drops.
 ///
 /// # Arguments
-/// * `price_str` - Price string like "$1,299.99" or "₦50,000"
-/// * `site_hint` - Optional site name to help detect currency (e.g., "Jumia" -> NGN)
+/// * `price_str` - Price text like "$1,299.99" or "2,499 KES"
+/// * `declared` - Currency declared by the source, if any
+/// * `site` - Site name, host or URL (e.g., "https://www.amazon.ca")
 ///
 /// # Returns
-/// * Detected currency or USD as default
-pub fn detect_currency(price_str: &str, site_hint: Option<&str>) -> Currency {
-    // Check for currency symbols (order matters - check specific symbols first)
-    if price_str.contains("C$") {
-        return Currency::CAD;
-    }
-    if price_str.contains("A$") {
-        return Currency::AUD;
-    }
-    if price_str.contains('$') {
-        return Currency::USD;
-    }
-    if price_str.contains("€") {
-        return Currency::EUR;
-    }
-    if price_str.contains("£") {
-        return Currency::GBP;
-    }
-    if price_str.contains("₦") {
-        return Currency::NGN;
-    }
-    if price_str.contains("₹") {
-        return Currency::INR;
-    }
-    if price_str.contains("¥") {
-        return Currency::JPY;
-    }
+/// * Resolved currency, its evidence and confidence (USD at low confidence
+///   when nothing is known)
+pub fn resolve_currency(
+    price_str: &str,
+    declared: Option<Currency>,
+    site: Option<&str>,
+) -> ResolvedCurrency {
+    let marketplace = site.and_then(Marketplace::for_site).map(|m| m.currency());
+    let mark = price_parser::currency_mark(price_str);
+    let code = mark
+        .as_deref()
+        .filter(|mark| mark.len() == 3 && mark.chars().all(|c| c.is_ascii_uppercase()))
+        .and_then(|mark| Currency::from_str(mark).ok());
+    let symbol_currencies = match (&mark, code) {
+        (Some(mark), None) => {
+            let candidates = Currency::with_symbol(mark);
+            if candidates.is_empty() {
+                Currency::with_symbol(mark.trim_end_matches('.'))
+            } else {
+                candidates
+            }
+        }
+        _ => Vec::new(),
+    };
 
-    // Check for currency codes
-    let upper = price_str.to_uppercase();
-    if upper.contains("USD") {
-        return Currency::USD;
-    }
-    if upper.contains("EUR") {
-        return Currency::EUR;
-    }
-    if upper.contains("GBP") {
-        return Currency::GBP;
-    }
-    if upper.contains("NGN") {
-        return Currency::NGN;
-    }
-    if upper.contains("INR") {
-        return Currency::INR;
-    }
-    if upper.contains("CAD") {
-        return Currency::CAD;
-    }
-    if upper.contains("AUD") {
-        return Currency::AUD;
-    }
-    if upper.contains("JPY") {
-        return Currency::JPY;
-    }
+    let disagrees = |resolved: Currency, shown: &str| {
+        tracing::warn!(
+            price = %price_str,
+            site = ?site,
+            shown = %shown,
+            resolved = %resolved,
+            "Price currency disagrees with its source"
+        );
+    };
 
-    // Use site hint if available
-    if let Some(site) = site_hint {
-        let site_lower = site.to_lowercase();
-        if site_lower.contains("jumia.com.ng") || site_lower.contains("konga") {
-            return Currency::NGN;
-        }
-        if site_lower.contains("amazon.co.uk") || site_lower.contains("ebay.co.uk") {
-            return Currency::GBP;
-        }
-        if site_lower.contains("amazon.de") || site_lower.contains("amazon.fr") {
-            return Currency::EUR;
+    if let Some(declared) = declared {
+        if code.is_some_and(|code| code != declared)
+            || (!symbol_currencies.is_empty() && !symbol_currencies.contains(&declared))
+        {
+            disagrees(declared, mark.as_deref().unwrap_or_default());
         }
-        if site_lower.contains("amazon.ca") {
-            return Currency::CAD;
+        return ResolvedCurrency::new(declared, CurrencyEvidence::Declared, 100);
+    }
+
+    if let Some(code) = code {
+        if let Some(marketplace) = marketplace.filter(|m| *m != code) {
+            disagrees(code, marketplace.code());
         }
-        if site_lower.contains("amazon.com.au") {
-            return Currency::AUD;
+        return ResolvedCurrency::new(code, CurrencyEvidence::Code, 95);
+    }
+
+    match (mark.as_deref(), marketplace) {
+        (Some(_), Some(marketplace)) if symbol_currencies.contains(&marketplace) => {
+            ResolvedCurrency::new(marketplace, CurrencyEvidence::Marketplace, 95)
         }
-        if site_lower.contains("amazon.in") {
-            return Currency::INR;
+        (Some(mark), Some(marketplace)) => {
+            disagrees(marketplace, mark);
+            ResolvedCurrency::new(marketplace, CurrencyEvidence::Marketplace, 60)
         }
-        if site_lower.contains("amazon.co.jp") {
-            return Currency::JPY;
+        (None, Some(marketplace)) => {
+            ResolvedCurrency::new(marketplace, CurrencyEvidence::Marketplace, 85)
         }
+        (Some(mark), None) => match symbol_currencies.as_slice() {
+            [only] => ResolvedCurrency::new(*only, CurrencyEvidence::Symbol, 90),
+            _ => {
+                let preferred = Currency::from_symbol(mark)
+                    .or_else(|| symbol_currencies.first().copied())
+                    .unwrap_or(Currency::USD);
+                ResolvedCurrency::new(preferred, CurrencyEvidence::SharedSymbol, 60)
+            }
+        },
+        (None, None) => ResolvedCurrency::new(Currency::USD, CurrencyEvidence::Default, 20),
     }
+}
 
-    // Default to USD
-    Currency::USD
+/// Detects currency from a price string.
+///
+/// See `resolve_currency`, which also reports how sure the detection is.
+///
+/// # Arguments
+/// * `price_str` - Price string like "$1,299.99", "₦50,000" or "2,499 KES"
+/// * `site_hint` - Optional site name to help detect currency (e.g., "Jumia" -> NGN)
+///
+/// # Returns
+/// * Detected currency or USD as default
+pub fn detect_currency(price_str: &str, site_hint: Option<&str>) -> Currency {
+    resolve_currency(price_str, None, site_hint).currency
 }
 
 /// Parses price string and extracts numeric value with currency.
 ///
+/// The site's locale resolves separators that could be a decimal point or
+/// digit grouping; for a range ("₦ 12,500 - ₦ 15,000") the lowest amount is
+/// returned. See `price_parser::parse_price` for the range itself.
+///
 /// # Arguments
 /// * `price_str` - Price string like "$1,299.99" or "€1.299,99"
-/// * `site_hint` - Optional site name for currency detection
+/// * `site_hint` - Optional site name or URL for currency and locale detection
 ///
 /// # Returns
 /// * Tuple of (numeric_value, detected_currency)
+/// * `Err(AppError)` - No amount, or an amount that reads two ways
 pub fn parse_price_with_currency(
     price_str: &str,
     site_hint: Option<&str>,
 ) -> Result<(Decimal, Currency), AppError> {
-    let currency = detect_currency(price_str, site_hint);
-
-    // Remove all non-numeric characters except . and ,
-    let cleaned = price_str
-        .chars()
-        .filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
-        .collect::<String>();
-
-    if cleaned.is_empty() {
-        return Err(AppError::Parse(format!(
-            "No numeric value found in price: {}",
-            price_str
-        )));
-    }
-
-    // Handle European format (comma as decimal separator)
-    // European: 1.299,99 -> 1299.99
-    // US: 1,299.99 -> 1299.99
-    // NGN: 50,000 -> 50000
-    let normalized = if cleaned.contains('.') && cleaned.contains(',') {
-        // Both separators present - determine which is decimal
-        let dot_pos = cleaned.rfind('.').unwrap();
-        let comma_pos = cleaned.rfind(',').unwrap();
-        if comma_pos > dot_pos {
-            // Comma is decimal separator (European: 1.299,99)
-            cleaned.replace('.', "").replace(',', ".")
-        } else {
-            // Dot is decimal separator (US: 1,299.99)
-            cleaned.replace(',', "")
-        }
-    } else if cleaned.contains(',') && !cleaned.contains('.') {
-        // Only comma - could be thousands separator (50,000) or decimal (50,00)
-        // If there are digits after comma, check the count
-        if let Some(comma_pos) = cleaned.find(',') {
-            let after_comma = &cleaned[comma_pos + 1..];
-            if after_comma.len() == 2 {
-                // Likely decimal separator (European: 50,00)
-                cleaned.replace(',', ".")
-            } else {
-                // Likely thousands separator (50,000 or 1,000,000)
-                cleaned.replace(',', "")
-            }
-        } else {
-            cleaned
-        }
-    } else {
-        // No comma or only dot - use as is
-        cleaned
-    };
-
-    let value = Decimal::from_str(&normalized)
-        .map_err(|_| AppError::Parse(format!("Invalid price format: {}", price_str)))?;
-
-    Ok((value, currency))
+    let (price, _) = parse_price_in_context(price_str, None, site_hint)?;
+    Ok((price.amount(), price.currency()))
 }
 
-/// Price with currency information.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PriceWithCurrency {
-    pub amount: Decimal,
-    pub currency: Currency,
-    pub amount_usd: Decimal,
-}
-
-impl PriceWithCurrency {
-    /// Creates a new price with a specific USD amount.
-    /// Used when USD conversion is already known (e.g., from service).
-    pub fn new(amount: Decimal, currency: Currency, amount_usd: Decimal) -> Self {
-        Self {
-            amount,
-            currency,
-            amount_usd,
-        }
-    }
-
-    /// Creates a price from a string using fallback conversion rates.
-    /// For production, use CurrencyService::convert() instead.
-    pub fn from_string(price_str: &str, site_hint: Option<&str>) -> Result<Self, AppError> {
-        let (amount, currency) = parse_price_with_currency(price_str, site_hint)?;
-        let amount_usd = amount * currency.fallback_to_usd_rate();
-
-        Ok(Self {
-            amount,
-            currency,
-            amount_usd,
-        })
-    }
-
-    /// Formats the price with its original currency.
-    pub fn format(&self) -> String {
-        format!("{}{}", self.currency.symbol(), self.amount.round_dp(2))
-    }
-
-    /// Formats the price in USD.
-    pub fn format_usd(&self) -> String {
-        format!("${}", self.amount_usd.round_dp(2))
-    }
+/// Parses a listing price using what its source declares.
+///
+/// # Arguments
+/// * `price_str` - Price text
+/// * `declared` - Currency declared by the source (structured data, API field)
+/// * `site` - Site name, host or URL of the listing
+///
+/// # Returns
+/// * `Ok((Money, ResolvedCurrency))` - Lowest amount and how its currency was resolved
+/// * `Err(AppError)` - No amount, or an amount that reads two ways
+pub fn parse_price_in_context(
+    price_str: &str,
+    declared: Option<Currency>,
+    site: Option<&str>,
+) -> Result<(Money, ResolvedCurrency), AppError> {
+    let resolved = resolve_currency(price_str, declared, site);
+    let locale = site
+        .and_then(Marketplace::for_site)
+        .map(|marketplace| marketplace.locale);
+    let parsed = price_parser::parse_price(price_str, locale, Some(resolved.currency))?;
+
+    Ok((Money::new(parsed.min, resolved.currency), resolved))
 }
 
 #[cfg(test)]
@@ -537,6 +1191,8 @@ mod tests {
         assert_eq!(detect_currency("₦50,000", None), Currency::NGN);
         assert_eq!(detect_currency("C$100", None), Currency::CAD);
         assert_eq!(detect_currency("A$200", None), Currency::AUD);
+        assert_eq!(detect_currency("2,499 KES", None).code(), "KES");
+        assert_eq!(detect_currency("KSh 2,499", None).code(), "KES");
     }
 
     #[test]
@@ -556,12 +1212,54 @@ mod tests {
     }
 
     #[test]
-    fn test_price_with_currency() {
-        let price = PriceWithCurrency::from_string("£999", None).unwrap();
-        assert_eq!(price.amount, Decimal::from_str("999").unwrap());
-        assert_eq!(price.currency, Currency::GBP);
-        // £999 * 1.27 ≈ $1268.73
-        assert!(price.amount_usd > Decimal::from_str("1268").unwrap());
+    fn test_resolve_currency_by_site() {
+        let resolve = |text: &str, declared: Option<Currency>, site: Option<&str>| {
+            let resolved = resolve_currency(text, declared, site);
+            (
+                resolved.currency.code(),
+                resolved.evidence,
+                resolved.confidence,
+            )
+        };
+
+        assert_eq!(
+            resolve("$1,299.99", None, Some("https://www.amazon.ca")),
+            ("CAD", CurrencyEvidence::Marketplace, 95)
+        );
+        assert_eq!(
+            resolve("¥ 899", None, Some("https://item.jd.com/1.html")),
+            ("CNY", CurrencyEvidence::Marketplace, 95)
+        );
+        assert_eq!(
+            resolve("$1,299.99", None, None),
+            ("USD", CurrencyEvidence::SharedSymbol, 60)
+        );
+        assert_eq!(
+            resolve("₦50,000", None, None),
+            ("NGN", CurrencyEvidence::Symbol, 90)
+        );
+        // The marketplace outranks a conflicting symbol, at lower confidence
+        assert_eq!(
+            resolve("€12", None, Some("amazon.com")),
+            ("USD", CurrencyEvidence::Marketplace, 60)
+        );
+        assert_eq!(
+            resolve("2,499 KES", None, Some("amazon.com")),
+            ("KES", CurrencyEvidence::Code, 95)
+        );
+        assert_eq!(
+            resolve("$12", Currency::from_str("SGD").ok(), None),
+            ("SGD", CurrencyEvidence::Declared, 100)
+        );
+        assert_eq!(
+            resolve("1,299", None, None),
+            ("USD", CurrencyEvidence::Default, 20)
+        );
+
+        // "¥" on a CNY storefront keeps its fen
+        let (price, resolved) = parse_price_in_context("¥12.50", None, Some("jd.com")).unwrap();
+        assert_eq!(price.amount(), Decimal::from_str("12.50").unwrap());
+        assert_eq!(resolved.currency.code(), "CNY");
     }
 
     #[test]
@@ -578,14 +1276,205 @@ mod tests {
         assert_eq!(Currency::from_str("USD").unwrap(), Currency::USD);
         assert_eq!(Currency::from_str("ngn").unwrap(), Currency::NGN);
         assert!(Currency::from_str("INVALID").is_err());
+
+        // Any ISO 4217 code, not just the ones with constants
+        let kes = Currency::from_str("KES").unwrap();
+        assert_eq!(kes.code(), "KES");
+        assert_eq!(kes.metadata().numeric, "404");
+        assert_eq!(
+            serde_json::from_str::<Currency>("\"xof\"").unwrap().code(),
+            "XOF"
+        );
+        assert_eq!(serde_json::to_string(&kes).unwrap(), "\"KES\"");
+        assert!(serde_json::from_str::<Currency>("\"ABC\"").is_err());
+    }
+
+    #[test]
+    fn test_currency_table() {
+        for currency in [
+            Currency::USD,
+            Currency::EUR,
+            Currency::GBP,
+            Currency::NGN,
+            Currency::INR,
+            Currency::CAD,
+            Currency::AUD,
+            Currency::JPY,
+        ] {
+            assert_eq!(currency.metadata().code, currency.code());
+        }
+        assert_eq!(Currency::CAD.symbol(), "C$");
+        assert_eq!(Currency::JPY.minor_units(), 0);
+        assert_eq!(Currency::from_str("BHD").unwrap().minor_units(), 3);
+
+        let codes: Vec<&str> = Currency::all().map(|c| c.code.as_str()).collect();
+        assert!(codes.len() > 150);
+        assert!(codes.contains(&"GHS"));
+        assert!(!codes.contains(&"XAU"));
+    }
+
+    #[test]
+    fn test_minor_units_fit_price_columns() {
+        // Stored prices keep four decimal places (DECIMAL(18, 4)); a currency
+        // with more minor units would be re-rounded by the database.
+        assert!(Currency::all().all(|c| c.minor_units.unwrap() <= 4));
+        assert_eq!(Currency::from_str("CLF").unwrap().minor_units(), 4);
+    }
+
+    #[test]
+    fn test_format_by_locale() {
+        let amount = Decimal::from_str("1299.5").unwrap();
+        assert_eq!(Currency::USD.format(amount, Some("en-US")), "$1,299.50");
+        assert_eq!(Currency::EUR.format(amount, Some("de_DE")), "1.299,50 €");
+        assert_eq!(Currency::NGN.format(amount, None), "₦1299.50");
+        assert_eq!(
+            Currency::JPY.format(Decimal::from_str("129950.4").unwrap(), Some("ja-JP")),
+            "￥129,950"
+        );
+        assert_eq!(
+            Currency::INR.format(Decimal::from(1234567), Some("en-IN")),
+            "₹12,34,567.00"
+        );
+        assert_eq!(
+            Currency::INR.format(Decimal::from(999), Some("en-IN")),
+            "₹999.00"
+        );
+        assert_eq!(
+            Currency::USD.format(Decimal::from(1234567), Some("en-US")),
+            "$1,234,567.00"
+        );
+    }
+
+    #[test]
+    fn test_normalize_to_usd() {
+        let published_at = chrono::Utc::now();
+        let source = |provider: &str| RateSource {
+            provider: provider.to_string(),
+            published_at,
+        };
+        let rates = ExchangeRates {
+            base: "USD".to_string(),
+            rates: HashMap::from([
+                ("USD".to_string(), Decimal::ONE),
+                ("NGN".to_string(), Decimal::from(1600)),
+            ]),
+            updated_at: published_at,
+            sources: HashMap::from([
+                ("USD".to_string(), source("ecb")),
+                ("NGN".to_string(), source("exchangerate_api")),
+            ]),
+            outliers: Vec::new(),
+        };
+        let listing = |site: &str, price: i64, currency: &str| {
+            SitePrice::new(
+                site.to_string(),
+                "Phone".to_string(),
+                Money::new(Decimal::from(price), Currency::from_str(currency).unwrap()),
+                // Scraped listings arrive with the unconverted price
+                Decimal::from(price),
+                format!("https://{}.example/phone", site),
+                None,
+                None,
+            )
+        };
+
+        let prices = normalize_to_usd(
+            vec![
+                listing("Jumia", 500_000, "NGN"),
+                listing("Amazon", 320, "USD"),
+                // Neither the providers nor the fallback table have KES
+                listing("Jumia Kenya", 1_000, "KES"),
+            ],
+            &rates,
+        );
+
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].price_usd, Decimal::from_str("312.50").unwrap());
+        let ngn = prices[0].usd_rate.as_ref().unwrap();
+        assert_eq!(ngn.rate, Decimal::ONE / Decimal::from(1600));
+        assert_eq!(ngn.source, source("exchangerate_api"));
+        assert_eq!(prices[1].price_usd, Decimal::from(320));
+    }
+
+    #[test]
+    fn test_rate_metadata_and_rebasing() {
+        let at = |date: &str| {
+            chrono::DateTime::parse_from_rfc3339(date)
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+        let ecb = RateSource {
+            provider: "ecb".to_string(),
+            published_at: at("2024-06-03T16:00:00Z"),
+        };
+        let rates = ExchangeRates {
+            base: "USD".to_string(),
+            rates: HashMap::from([
+                ("USD".to_string(), Decimal::ONE),
+                ("EUR".to_string(), Decimal::from_str("0.8").unwrap()),
+            ]),
+            updated_at: at("2024-06-04T08:00:00Z"),
+            sources: HashMap::from([
+                ("USD".to_string(), ecb.clone()),
+                ("EUR".to_string(), ecb.clone()),
+            ]),
+            outliers: Vec::new(),
+        };
+
+        let (per_eur, sources) = rates.rebased(&Currency::EUR).unwrap();
+        assert_eq!(per_eur["USD"], Decimal::from_str("1.25").unwrap());
+        assert_eq!(per_eur["EUR"], Decimal::ONE);
+        assert_eq!(sources, vec![ecb.clone()]);
+        assert!(rates.rebased(&Currency::from_str("KES").unwrap()).is_none());
+
+        let conversion = rates
+            .convert(Money::usd(Decimal::from(100)), &Currency::EUR)
+            .unwrap();
+        assert_eq!(
+            conversion.amount,
+            Money::new(Decimal::from(80), Currency::EUR)
+        );
+        assert_eq!(conversion.fetched_at, rates.updated_at);
+        assert_eq!(conversion.sources, vec![ecb.clone()]);
+
+        let stale_after = chrono::Duration::hours(72);
+        let fresh = RateMetadata::new(
+            &sources,
+            rates.updated_at,
+            stale_after,
+            at("2024-06-05T00:00:00Z"),
+        );
+        assert_eq!(fresh.providers, vec!["ecb"]);
+        assert_eq!(fresh.published_at, Some(ecb.published_at));
+        assert!(!fresh.fallback_used && !fresh.stale);
+
+        let mut cached = fresh.clone();
+        cached.refresh(stale_after, at("2024-06-10T00:00:00Z"));
+        assert!(cached.stale);
+
+        let fallback = RateMetadata::new(
+            &[ecb, fallback_source()],
+            rates.updated_at,
+            stale_after,
+            at("2024-06-05T00:00:00Z"),
+        );
+        assert!(fallback.fallback_used && fallback.stale);
     }
 
     #[test]
     fn test_fallback_rates() {
-        let usd = Currency::USD.fallback_to_usd_rate();
+        let usd = Currency::USD.fallback_to_usd_rate().unwrap();
         assert_eq!(usd, Decimal::from(1));
 
-        let gbp = Currency::GBP.fallback_to_usd_rate();
+        let gbp = Currency::GBP.fallback_to_usd_rate().unwrap();
         assert!(gbp > Decimal::from(1)); // GBP is stronger than USD
+
+        // Rates per USD, like the exchange rate API's: many naira to the dollar
+        let ngn = Currency::NGN.fallback_rate_per_usd().unwrap();
+        assert!(ngn > Decimal::from(100));
+        assert!(Currency::from_str("KES")
+            .unwrap()
+            .fallback_rate_per_usd()
+            .is_none());
     }
 }
@@ -0,0 +1,442 @@
+//! Detail-page verification of borderline search matches.
+//!
+//! Search result pages rarely show a UPC or MPN, so a listing found by
+//! search is scored on its title alone. For listings whose confidence is
+//! borderline, this module fetches the product page, reads its schema.org
+//! Product data (JSON-LD, with Open Graph price tags as a fallback) and
+//! re-scores the listing with the identifiers, specs and price it declares.
+//! Fetches are limited by a per-request budget since each one is a paid
+//! ZenRows call.
+
+use super::currency::{Currency, CurrencyEvidence, ResolvedCurrency};
+use super::matching::{self, MatchExplanation};
+use super::money::Money;
+use super::units::{Quantity, UnitPrice};
+use super::zenrows::{self, ZenRowsConfig};
+use crate::config::{DetailVerificationConfig, MatchingConfig};
+use crate::{AppError, ProductIdentifiers, SitePrice};
+use reqwest::Client;
+use rust_decimal::Decimal;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio::task::JoinSet;
+
+/// Product data read from a detail page.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProductDetails {
+    pub title: Option<String>,
+    /// GTIN, MPN, brand, model and specifications declared by the page
+    pub identifiers: ProductIdentifiers,
+    /// Price and currency of the offer on the page
+    pub price: Option<Money>,
+    pub image: Option<String>,
+}
+
+impl ProductDetails {
+    /// True when the page declared nothing useful for matching.
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.price.is_none() && self.identifiers == Default::default()
+    }
+}
+
+/// Parses schema.org Product data from a detail page.
+///
+/// # Arguments
+/// * `html` - Detail page HTML
+///
+/// # Returns
+/// * Details from the first JSON-LD Product node, with the price falling
+///   back to `product:price:amount` meta tags; empty when nothing is declared
+pub fn parse_detail_page(html: &str) -> ProductDetails {
+    let document = Html::parse_document(html);
+    let mut details = ProductDetails::default();
+
+    let script_selector = Selector::parse(r#"script[type="application/ld+json"]"#).unwrap();
+    let product = document
+        .select(&script_selector)
+        .filter_map(|script| serde_json::from_str::<Value>(&script.text().collect::<String>()).ok())
+        .find_map(|json| find_product_node(&json).cloned());
+
+    if let Some(product) = product {
+        details.title = text_field(&product, "name");
+        details.image = match &product["image"] {
+            Value::Array(images) => images.first().and_then(value_text),
+            image => value_text(image).or_else(|| text_field(image, "url")),
+        };
+        details.identifiers = ProductIdentifiers {
+            gtin: ["gtin", "gtin14", "gtin13", "gtin12", "gtin8"]
+                .iter()
+                .find_map(|key| text_field(&product, key)),
+            mpn: text_field(&product, "mpn"),
+            brand: text_field(&product, "brand").or_else(|| text_field(&product["brand"], "name")),
+            model_number: text_field(&product, "model")
+                .or_else(|| text_field(&product["model"], "name")),
+            specifications: additional_properties(&product),
+            ..Default::default()
+        };
+        details.price = offer_price(&product["offers"]);
+    }
+
+    if details.price.is_none() {
+        details.price = meta_price(&document);
+    }
+
+    details
+}
+
+/// Finds the first node typed `Product` in a JSON-LD document.
+fn find_product_node(json: &Value) -> Option<&Value> {
+    match json {
+        Value::Array(nodes) => nodes.iter().find_map(find_product_node),
+        Value::Object(node) => {
+            let is_product = match node.get("@type") {
+                Some(Value::String(t)) => t == "Product",
+                Some(Value::Array(types)) => types.iter().any(|t| t == "Product"),
+                _ => false,
+            };
+            if is_product {
+                Some(json)
+            } else {
+                node.get("@graph").and_then(find_product_node)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Reads a string or number field as trimmed text.
+fn text_field(node: &Value, key: &str) -> Option<String> {
+    node.get(key).and_then(value_text)
+}
+
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads `additionalProperty` name/value pairs as a specification map.
+fn additional_properties(product: &Value) -> Option<HashMap<String, String>> {
+    let properties: HashMap<String, String> = product["additionalProperty"]
+        .as_array()?
+        .iter()
+        .filter_map(|p| {
+            Some((
+                text_field(p, "name")?.to_lowercase(),
+                text_field(p, "value")?,
+            ))
+        })
+        .collect();
+    (!properties.is_empty()).then_some(properties)
+}
+
+/// Reads the price of an Offer, the first of several, or an AggregateOffer's low price.
+fn offer_price(offers: &Value) -> Option<Money> {
+    let offer = match offers {
+        Value::Array(offers) => offers.first()?,
+        offer => offer,
+    };
+    let amount = text_field(offer, "price").or_else(|| text_field(offer, "lowPrice"))?;
+    let currency = text_field(offer, "priceCurrency")?;
+
+    Some(Money::new(
+        Decimal::from_str(&amount).ok()?,
+        Currency::from_str(&currency).ok()?,
+    ))
+}
+
+/// Reads Open Graph `product:price:amount` and `product:price:currency` tags.
+fn meta_price(document: &Html) -> Option<Money> {
+    let meta = |property: &str| {
+        let selector = Selector::parse(&format!(r#"meta[property="{}"]"#, property)).ok()?;
+        document
+            .select(&selector)
+            .next()?
+            .value()
+            .attr("content")
+            .map(str::to_string)
+    };
+
+    Some(Money::new(
+        Decimal::from_str(meta("product:price:amount")?.trim()).ok()?,
+        Currency::from_str(meta("product:price:currency")?.trim()).ok()?,
+    ))
+}
+
+/// Fetches and parses a listing's detail page through ZenRows.
+///
+/// # Arguments
+/// * `client` - HTTP client
+/// * `config` - ZenRows configuration
+/// * `link` - Listing URL
+///
+/// # Returns
+/// * `Ok(ProductDetails)` - Parsed details (may be empty)
+/// * `Err(AppError)` - Network or API error
+pub async fn fetch_details(
+    client: &Client,
+    config: &ZenRowsConfig,
+    link: &str,
+) -> Result<ProductDetails, AppError> {
+    // Product pages embed JSON-LD server-side, so JavaScript rendering is not needed
+    let html = zenrows::scrape_url(client, config, link, false).await?;
+    Ok(parse_detail_page(&html))
+}
+
+/// Applies detail-page data to a listing and re-scores it.
+///
+/// The page's price replaces the search result's, and the listing is
+/// scored again with the page's identifiers and specs.
+///
+/// # Arguments
+/// * `price` - Listing found by search
+/// * `details` - Data read from its detail page
+/// * `identifiers` - Identifiers of the source product
+/// * `search_query` - Title or search query of the source product
+/// * `config` - Matching configuration
+///
+/// # Returns
+/// * Updated listing and its new explanation
+pub fn apply_details(
+    mut price: SitePrice,
+    details: &ProductDetails,
+    identifiers: &ProductIdentifiers,
+    search_query: &str,
+    config: &MatchingConfig,
+) -> (SitePrice, MatchExplanation) {
+    if let Some(title) = &details.title {
+        price.title = title.clone();
+    }
+    if let Some(declared) = details.price {
+        price.price = declared;
+        price.currency_resolution = Some(ResolvedCurrency {
+            currency: declared.currency(),
+            evidence: CurrencyEvidence::Declared,
+            confidence: 100,
+        });
+        // Same as search results: normalized to USD by the comparison pipeline
+        price.price_usd = declared.amount();
+    }
+    if price.image.is_none() {
+        price.image = details.image.clone();
+    }
+    // A declared pack size or volume beats one guessed from the title
+    if let Some(quantity) = details
+        .identifiers
+        .specifications
+        .as_ref()
+        .and_then(Quantity::from_map)
+    {
+        price.unit_price = UnitPrice::new(&price, quantity);
+    }
+
+    let explanation = matching::explain_verified(
+        identifiers,
+        search_query,
+        &price,
+        &details.identifiers,
+        config,
+    );
+    (price, explanation)
+}
+
+/// Verifies borderline listings against their detail pages.
+///
+/// Listings whose confidence lies in the configured borderline range are
+/// fetched, closest to the match threshold first, up to `budget` pages in
+/// parallel. Failed fetches and pages without product data leave the
+/// listing unchanged.
+///
+/// # Arguments
+/// * `prices` - Scored listings with their explanations
+/// * `identifiers` - Identifiers of the source product
+/// * `search_query` - Title or search query of the source product
+/// * `client` - HTTP client
+/// * `zenrows` - ZenRows configuration
+/// * `verification` - Borderline range
+/// * `budget` - Maximum number of detail pages to fetch for this request
+/// * `threshold` - Match confidence threshold
+/// * `config` - Matching configuration
+///
+/// # Returns
+/// * Listings in their original order, verified ones updated
+#[allow(clippy::too_many_arguments)]
+pub async fn verify_borderline(
+    mut prices: Vec<(SitePrice, MatchExplanation)>,
+    identifiers: &ProductIdentifiers,
+    search_query: &str,
+    client: &Client,
+    zenrows: &ZenRowsConfig,
+    verification: &DetailVerificationConfig,
+    budget: usize,
+    threshold: u8,
+    config: &MatchingConfig,
+) -> Vec<(SitePrice, MatchExplanation)> {
+    let mut borderline: Vec<usize> = prices
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, explanation))| {
+            !explanation.tier.is_identifier()
+                && (verification.min_confidence..=verification.max_confidence)
+                    .contains(&explanation.confidence)
+        })
+        .map(|(index, _)| index)
+        .collect();
+    borderline.sort_by_key(|&index| prices[index].1.confidence.abs_diff(threshold));
+    borderline.truncate(budget);
+
+    if borderline.is_empty() {
+        return prices;
+    }
+
+    let mut fetches = JoinSet::new();
+    for index in borderline {
+        let client = client.clone();
+        let zenrows = zenrows.clone();
+        let link = prices[index].0.link.clone();
+        fetches.spawn(async move { (index, fetch_details(&client, &zenrows, &link).await) });
+    }
+
+    while let Some(joined) = fetches.join_next().await {
+        let Ok((index, result)) = joined else {
+            continue;
+        };
+        let (price, explanation) = &prices[index];
+
+        match result {
+            Ok(details) if !details.is_empty() => {
+                let before = explanation.confidence;
+                let verified =
+                    apply_details(price.clone(), &details, identifiers, search_query, config);
+                tracing::info!(
+                    site = %price.site,
+                    link = %price.link,
+                    before = before,
+                    after = verified.1.confidence,
+                    tier = ?verified.1.tier,
+                    "Listing verified against detail page"
+                );
+                prices[index] = verified;
+            }
+            Ok(_) => {
+                tracing::debug!(link = %price.link, "Detail page has no product data");
+            }
+            Err(e) => {
+                tracing::warn!(link = %price.link, error = %e, "Detail page fetch failed");
+            }
+        }
+    }
+
+    prices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DETAIL_PAGE: &str = r#"<html><head>
+        <meta property="product:price:amount" content="1.00">
+        <meta property="product:price:currency" content="USD">
+        <script type="application/ld+json">{"@context":"https://schema.org","@type":"BreadcrumbList"}</script>
+        <script type="application/ld+json">
+        {"@context":"https://schema.org","@graph":[{"@type":["Product","Thing"],
+          "name":"Samsung Galaxy A54 5G 8GB 256GB Awesome Black",
+          "image":["https://img.example.com/a54.jpg"],
+          "gtin13":"8806094725971","mpn":"SM-A546BZKDEUB",
+          "brand":{"@type":"Brand","name":"Samsung"},
+          "additionalProperty":[{"name":"Storage","value":"256GB"},{"name":"RAM","value":"8GB"}],
+          "offers":[{"@type":"Offer","price":"329000","priceCurrency":"NGN"}]}]}
+        </script></head><body></body></html>"#;
+
+    #[test]
+    fn test_parse_detail_page_reads_json_ld() {
+        let details = parse_detail_page(DETAIL_PAGE);
+
+        assert_eq!(
+            details.title.as_deref(),
+            Some("Samsung Galaxy A54 5G 8GB 256GB Awesome Black")
+        );
+        assert_eq!(details.identifiers.gtin.as_deref(), Some("8806094725971"));
+        assert_eq!(details.identifiers.mpn.as_deref(), Some("SM-A546BZKDEUB"));
+        assert_eq!(details.identifiers.brand.as_deref(), Some("Samsung"));
+        assert_eq!(
+            details.identifiers.specifications.as_ref().unwrap()["storage"],
+            "256GB"
+        );
+        assert_eq!(
+            details.price,
+            Some(Money::new(Decimal::from(329000), Currency::NGN))
+        );
+        assert_eq!(
+            details.image.as_deref(),
+            Some("https://img.example.com/a54.jpg")
+        );
+    }
+
+    #[test]
+    fn test_parse_detail_page_meta_price_fallback() {
+        let html = r#"<html><head>
+            <meta property="product:price:amount" content="499.99">
+            <meta property="product:price:currency" content="USD">
+            </head></html>"#;
+        let details = parse_detail_page(html);
+
+        assert_eq!(details.price, Some(Money::usd(Decimal::new(49999, 2))));
+        assert!(details.title.is_none());
+        assert!(parse_detail_page("<html></html>").is_empty());
+    }
+
+    #[test]
+    fn test_apply_details_upgrades_gtin_match() {
+        let source = ProductIdentifiers {
+            ean: Some("8806094725971".to_string()),
+            ..Default::default()
+        };
+        let listing = SitePrice::new(
+            "Jumia".to_string(),
+            "Samsung Galaxy A54".to_string(),
+            Money::new(Decimal::from(300000), Currency::NGN),
+            Decimal::from(300000),
+            "https://www.jumia.com.ng/samsung-a54.html".to_string(),
+            None,
+            Some(70),
+        );
+
+        let (price, explanation) = apply_details(
+            listing,
+            &parse_detail_page(DETAIL_PAGE),
+            &source,
+            "Samsung Galaxy A54 256GB",
+            &MatchingConfig::default(),
+        );
+
+        assert_eq!(explanation.confidence, 100);
+        assert!(explanation.verified);
+
+        // A page declaring another GTIN is a different item
+        let other = ProductIdentifiers {
+            upc: Some("012345678905".to_string()),
+            brand: Some("Samsung".to_string()),
+            model_number: Some("Galaxy A54".to_string()),
+            ..Default::default()
+        };
+        let (_, rejected) = apply_details(
+            price.clone(),
+            &parse_detail_page(DETAIL_PAGE),
+            &other,
+            "Samsung Galaxy A54 256GB",
+            &MatchingConfig::default(),
+        );
+        assert_eq!(rejected.confidence, 0);
+        assert_eq!(rejected.penalties[0].reason, "gtin_mismatch");
+        assert_eq!(price.price.amount(), Decimal::from(329000));
+        assert_eq!(
+            price.image.as_deref(),
+            Some("https://img.example.com/a54.jpg")
+        );
+    }
+}
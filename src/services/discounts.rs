@@ -0,0 +1,312 @@
+//! Advertised discounts checked against recorded prices.
+//!
+//! A listing that shows "was ₦80,000, now ₦55,000" claims it sold at
+//! ₦80,000. The claimed reference price is compared with the prices
+//! recorded for the same link over the last `window_days`: when none of
+//! them came close to it, the discount is flagged as inflated. A reference
+//! price that keeps moving (the "was" prices recorded with the listing's
+//! history) is flagged as shifting. The true discount is measured against
+//! the median recorded price instead.
+
+use super::currency::parse_price_in_context;
+use super::feedback;
+use super::money::Money;
+use crate::config::DiscountConfig;
+use crate::db::{self, ReferencePriceStats};
+use crate::SitePrice;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// Whether a listing's advertised reference price holds up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscountVerdict {
+    /// The reference price was recorded within the window
+    Genuine,
+    /// No recorded price came close to the reference price
+    Inflated,
+    /// The reference price changed too often to be a real former price
+    Shifting,
+    /// Too few prices recorded to judge
+    Unverified,
+}
+
+/// An advertised discount checked against recorded prices.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscountCheck {
+    pub verdict: DiscountVerdict,
+    /// Discount the listing advertises, in percent of the reference price
+    pub claimed_percent: Decimal,
+    /// Discount from the median recorded price, in percent (negative when above it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub true_percent: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_median: Option<Money>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_max: Option<Money>,
+    /// Prices recorded in the window
+    pub observations: i64,
+    /// Distinct reference prices advertised in the window, the current one included
+    pub reference_prices: i64,
+    pub window_days: i64,
+}
+
+/// Parses a listing's reference (list or strike-through) price.
+///
+/// The currency is resolved from the text and the site, like the listed
+/// price's; a reference that is missing, unreadable, in another currency or
+/// not above the listed price is dropped.
+///
+/// # Arguments
+/// * `text` - Reference price text (e.g., "₦80,000")
+/// * `price` - Listed price
+/// * `site` - Site name, host or URL of the listing
+///
+/// # Returns
+/// * Reference price, when it advertises a discount
+pub fn reference_price(text: &str, price: &Money, site: Option<&str>) -> Option<Money> {
+    let (reference, _) = parse_price_in_context(text, None, site).ok()?;
+    (reference.currency() == price.currency() && reference.amount() > price.amount())
+        .then_some(reference)
+}
+
+/// Sets `discount_check` on listings that advertise a reference price.
+///
+/// A database failure is logged and the listings are left unchecked, so
+/// history never blocks a comparison.
+///
+/// # Arguments
+/// * `prices` - Listings of the comparison
+/// * `pool` - Database connection pool
+/// * `config` - Window, minimum observations and tolerance
+pub async fn annotate(prices: &mut [SitePrice], pool: &PgPool, config: &DiscountConfig) {
+    let mut listings: Vec<(String, String, Decimal)> = prices
+        .iter()
+        .filter_map(|p| {
+            let list_price = p.list_price?;
+            let link_key = feedback::candidate_key(&p.link);
+            Some((
+                link_key,
+                p.price.currency().code().to_string(),
+                list_price.amount(),
+            ))
+        })
+        .collect();
+    if listings.is_empty() {
+        return;
+    }
+    listings.sort();
+    listings.dedup_by(|a, b| a.0 == b.0);
+
+    let since = Utc::now() - chrono::Duration::days(config.window_days);
+    let stats = match db::get_reference_price_stats(pool, &listings, since).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load listing price history");
+            return;
+        }
+    };
+    let stats: HashMap<&str, &ReferencePriceStats> =
+        stats.iter().map(|s| (s.link_key.as_str(), s)).collect();
+
+    for price in prices.iter_mut() {
+        let Some(list_price) = price.list_price else {
+            continue;
+        };
+        let link_key = feedback::candidate_key(&price.link);
+        let check = check(
+            price.price,
+            list_price,
+            stats.get(link_key.as_str()).copied(),
+            config,
+        );
+        if matches!(
+            check.verdict,
+            DiscountVerdict::Inflated | DiscountVerdict::Shifting
+        ) {
+            tracing::info!(
+                site = %price.site,
+                link = %price.link,
+                list_price = %list_price,
+                verdict = ?check.verdict,
+                observed_max = ?check.observed_max.map(|m| m.to_string()),
+                reference_prices = check.reference_prices,
+                "Advertised reference price does not hold up"
+            );
+        }
+        price.discount_check = Some(check);
+    }
+}
+
+/// Checks an advertised discount against recorded prices.
+///
+/// # Arguments
+/// * `price` - Listed price
+/// * `list_price` - Advertised reference price, in the same currency
+/// * `stats` - Prices recorded for the listing in the window
+/// * `config` - Minimum observations, tolerance and reference price limit
+pub fn check(
+    price: Money,
+    list_price: Money,
+    stats: Option<&ReferencePriceStats>,
+    config: &DiscountConfig,
+) -> DiscountCheck {
+    let hundred = Decimal::from(100);
+    let percent_off = |reference: Decimal| {
+        (!reference.is_zero())
+            .then(|| ((reference - price.amount()) / reference * hundred).round_dp(1))
+    };
+    let currency = price.currency();
+    let observations = stats.map_or(0, |s| s.observations);
+    let observed_max = stats.and_then(|s| s.observed_max);
+    let observed_median = stats.and_then(|s| s.observed_median).map(|m| m.round_dp(6));
+    let reference_prices = stats.map_or(1, |s| s.reference_prices);
+
+    // A reference counts as observed when a recorded price came within the tolerance
+    let threshold = list_price.amount() * (hundred - config.tolerance_percent) / hundred;
+    let verdict = if observed_max.is_some_and(|max| max >= threshold) {
+        DiscountVerdict::Genuine
+    } else if reference_prices >= config.max_reference_prices {
+        DiscountVerdict::Shifting
+    } else if observations < config.min_observations {
+        DiscountVerdict::Unverified
+    } else {
+        DiscountVerdict::Inflated
+    };
+
+    DiscountCheck {
+        verdict,
+        claimed_percent: percent_off(list_price.amount()).unwrap_or_default(),
+        true_percent: observed_median.and_then(percent_off),
+        observed_median: observed_median.map(|m| Money::rounded(m, currency)),
+        observed_max: observed_max.map(|m| Money::new(m, currency)),
+        observations,
+        reference_prices,
+        window_days: config.window_days,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::currency::Currency;
+
+    fn naira(amount: i64) -> Money {
+        Money::new(Decimal::from(amount), Currency::NGN)
+    }
+
+    fn stats(observations: i64, max: i64, median: i64) -> ReferencePriceStats {
+        ReferencePriceStats {
+            link_key: "jumia.com.ng/phone-123.html".to_string(),
+            observations,
+            observed_max: Some(Decimal::from(max)),
+            observed_median: Some(Decimal::from(median)),
+            reference_prices: 1,
+        }
+    }
+
+    #[test]
+    fn test_flags_unobserved_reference_price() {
+        let config = DiscountConfig::default();
+
+        let inflated = check(
+            naira(55_000),
+            naira(80_000),
+            Some(&stats(12, 60_000, 58_000)),
+            &config,
+        );
+        assert_eq!(inflated.verdict, DiscountVerdict::Inflated);
+        assert_eq!(inflated.claimed_percent, Decimal::new(312, 1));
+        assert_eq!(inflated.true_percent, Some(Decimal::new(52, 1)));
+        assert_eq!(inflated.observed_median, Some(naira(58_000)));
+        assert_eq!(inflated.observed_max, Some(naira(60_000)));
+
+        let genuine = check(
+            naira(55_000),
+            naira(80_000),
+            Some(&stats(12, 79_000, 78_000)),
+            &config,
+        );
+        assert_eq!(genuine.verdict, DiscountVerdict::Genuine);
+
+        // A reference that keeps moving is flagged even with few prices recorded
+        let mut moving = stats(2, 60_000, 58_000);
+        moving.reference_prices = config.max_reference_prices;
+        let shifting = check(naira(55_000), naira(80_000), Some(&moving), &config);
+        assert_eq!(shifting.verdict, DiscountVerdict::Shifting);
+        assert_eq!(shifting.reference_prices, 3);
+    }
+
+    #[test]
+    fn test_tolerance_boundary() {
+        // 2% below ₦80,000 is ₦78,400
+        let config = DiscountConfig::default();
+        let at = |max: i64| {
+            check(
+                naira(55_000),
+                naira(80_000),
+                Some(&stats(12, max, 60_000)),
+                &config,
+            )
+            .verdict
+        };
+
+        assert_eq!(at(78_400), DiscountVerdict::Genuine);
+        assert_eq!(at(78_399), DiscountVerdict::Inflated);
+        assert_eq!(at(80_000), DiscountVerdict::Genuine);
+    }
+
+    #[test]
+    fn test_unverified_without_enough_history() {
+        let config = DiscountConfig::default();
+
+        let sparse = check(
+            naira(55_000),
+            naira(80_000),
+            Some(&stats(config.min_observations - 1, 60_000, 60_000)),
+            &config,
+        );
+        assert_eq!(sparse.verdict, DiscountVerdict::Unverified);
+
+        let at_minimum = check(
+            naira(55_000),
+            naira(80_000),
+            Some(&stats(config.min_observations, 60_000, 60_000)),
+            &config,
+        );
+        assert_eq!(at_minimum.verdict, DiscountVerdict::Inflated);
+
+        let untracked = check(naira(55_000), naira(80_000), None, &config);
+        assert_eq!(untracked.verdict, DiscountVerdict::Unverified);
+        assert_eq!(untracked.observations, 0);
+        assert_eq!(untracked.true_percent, None);
+        assert_eq!(untracked.claimed_percent, Decimal::new(312, 1));
+    }
+
+    #[test]
+    fn test_reference_price_parsing() {
+        assert_eq!(
+            reference_price("₦80,000", &naira(55_000), Some("jumia.com.ng")),
+            Some(naira(80_000))
+        );
+
+        // Not a discount
+        assert_eq!(
+            reference_price("₦50,000", &naira(55_000), Some("jumia.com.ng")),
+            None
+        );
+
+        // A reference in another currency cannot be compared with the price
+        assert_eq!(
+            reference_price("GBP 90,000", &naira(55_000), Some("jumia.com.ng")),
+            None
+        );
+        assert_eq!(
+            reference_price("see offers", &naira(55_000), Some("jumia.com.ng")),
+            None
+        );
+    }
+}
@@ -64,6 +64,7 @@ pub async fn fetch_price(
         price: ".s-item__price".to_string(),
         link: ".s-item__link".to_string(),
         image: ".s-item__image-img".to_string(),
+        list_price: Some(".s-item__trending-price .STRIKETHROUGH".to_string()),
     };
 
     let mut result = search_product(client, zenrows, &search_url, &selectors).await?;
@@ -0,0 +1,520 @@
+//! Offline evaluation of product matching against labeled pairs.
+//!
+//! A dataset is a JSONL file where each line pairs a source product with a
+//! candidate listing and labels them "same" or "different". Running a
+//! matcher profile over it yields precision, recall, F1 and a confusion
+//! breakdown per confidence tier, so two profiles can be compared before a
+//! change to matching ships. See `data/matching/labeled_pairs.jsonl` and the
+//! `evaluate_matching` binary.
+
+use super::matching::{self, MatchTier, SimilarityWeights, TierWeights};
+use super::money::Money;
+use crate::config::MatchingConfig;
+use crate::{AppError, ProductIdentifiers, SitePrice};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Whether a labeled pair is the same product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PairLabel {
+    Same,
+    Different,
+}
+
+/// Source side of a labeled pair.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PairSource {
+    /// Title or search query of the source product
+    pub title: String,
+    #[serde(default)]
+    pub identifiers: ProductIdentifiers,
+}
+
+/// Candidate side of a labeled pair.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PairCandidate {
+    pub site: String,
+    pub title: String,
+    #[serde(default)]
+    pub link: String,
+}
+
+/// One line of a labeled dataset.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LabeledPair {
+    /// Name used in reports (defaults to the line number)
+    #[serde(default)]
+    pub id: String,
+    pub source: PairSource,
+    pub candidate: PairCandidate,
+    pub label: PairLabel,
+}
+
+impl LabeledPair {
+    /// Builds the listing the matcher scores; prices play no part in matching.
+    fn candidate_price(&self) -> SitePrice {
+        SitePrice::new(
+            self.candidate.site.clone(),
+            self.candidate.title.clone(),
+            Money::usd(Decimal::ZERO),
+            Decimal::ZERO,
+            self.candidate.link.clone(),
+            None,
+            None,
+        )
+    }
+}
+
+/// Parses a JSONL dataset; blank lines are skipped.
+///
+/// # Arguments
+/// * `jsonl` - Dataset contents, one labeled pair per line
+///
+/// # Returns
+/// * `Ok(Vec<LabeledPair>)` - Pairs in file order
+/// * `Err(AppError)` - A line is not a valid pair (the error names the line)
+pub fn parse_dataset(jsonl: &str) -> Result<Vec<LabeledPair>, AppError> {
+    let mut pairs = Vec::new();
+
+    for (index, line) in jsonl.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut pair: LabeledPair = serde_json::from_str(line)
+            .map_err(|e| AppError::Parse(format!("Dataset line {}: {}", index + 1, e)))?;
+        if pair.id.is_empty() {
+            pair.id = format!("line-{}", index + 1);
+        }
+        pairs.push(pair);
+    }
+
+    Ok(pairs)
+}
+
+/// Reads and parses a JSONL dataset file.
+pub fn load_dataset(path: &Path) -> Result<Vec<LabeledPair>, AppError> {
+    let jsonl = std::fs::read_to_string(path)
+        .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+    parse_dataset(&jsonl)
+}
+
+/// Matcher configuration under evaluation.
+///
+/// Loaded from JSON; missing fields keep the defaults, so a profile only
+/// needs the values it changes (e.g., `{"similarity_weights": {"jaro_winkler": 0.7}}`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MatcherProfile {
+    pub name: String,
+    /// Minimum confidence to accept a match
+    pub min_confidence: u8,
+    pub tier_weights: TierWeights,
+    pub similarity_weights: SimilarityWeights,
+    pub spec_mismatch_penalty: u8,
+    pub accessory_confidence_cap: u8,
+}
+
+impl Default for MatcherProfile {
+    fn default() -> Self {
+        let config = MatchingConfig::default();
+        Self {
+            name: "default".to_string(),
+            min_confidence: 70,
+            tier_weights: config.tier_weights,
+            similarity_weights: config.similarity_weights,
+            spec_mismatch_penalty: config.spec_mismatch_penalty,
+            accessory_confidence_cap: config.accessory_confidence_cap,
+        }
+    }
+}
+
+impl MatcherProfile {
+    /// Reads a profile from a JSON file, named after the file if unnamed.
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        let mut profile: Self = serde_json::from_str(&json)
+            .map_err(|e| AppError::Parse(format!("Invalid profile {}: {}", path.display(), e)))?;
+        if profile.name == Self::default().name {
+            if let Some(stem) = path.file_stem() {
+                profile.name = stem.to_string_lossy().into_owned();
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Builds the matching configuration for this profile.
+    pub fn matching_config(&self) -> MatchingConfig {
+        MatchingConfig {
+            accessory_confidence_cap: self.accessory_confidence_cap,
+            spec_mismatch_penalty: self.spec_mismatch_penalty,
+            tier_weights: self.tier_weights,
+            similarity_weights: self.similarity_weights,
+            ..MatchingConfig::default()
+        }
+    }
+}
+
+/// Counts of predicted vs labeled outcomes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ConfusionMatrix {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub true_negatives: usize,
+    pub false_negatives: usize,
+}
+
+impl ConfusionMatrix {
+    /// Adds one outcome.
+    pub fn record(&mut self, predicted_match: bool, is_match: bool) {
+        match (predicted_match, is_match) {
+            (true, true) => self.true_positives += 1,
+            (true, false) => self.false_positives += 1,
+            (false, false) => self.true_negatives += 1,
+            (false, true) => self.false_negatives += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.true_positives + self.false_positives + self.true_negatives + self.false_negatives
+    }
+
+    /// Share of accepted matches that are correct (0.0 when none accepted).
+    pub fn precision(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        )
+    }
+
+    /// Share of true matches that were accepted (0.0 when there are none).
+    pub fn recall(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        )
+    }
+
+    /// Harmonic mean of precision and recall.
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall > 0.0 {
+            2.0 * precision * recall / (precision + recall)
+        } else {
+            0.0
+        }
+    }
+}
+
+fn ratio(num: usize, den: usize) -> f64 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f64 / den as f64
+    }
+}
+
+/// Confusion counts for pairs scored by one tier.
+#[derive(Debug, Clone, Serialize)]
+pub struct TierBreakdown {
+    pub tier: MatchTier,
+    pub confusion: ConfusionMatrix,
+}
+
+/// A pair the matcher got wrong.
+#[derive(Debug, Clone, Serialize)]
+pub struct Misclassified {
+    pub id: String,
+    pub label: PairLabel,
+    pub tier: MatchTier,
+    pub confidence: u8,
+}
+
+/// Result of running one profile over a dataset.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationReport {
+    pub profile: String,
+    pub threshold: u8,
+    pub confusion: ConfusionMatrix,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Breakdown in tier order, only tiers that scored at least one pair
+    pub tiers: Vec<TierBreakdown>,
+    pub misclassified: Vec<Misclassified>,
+}
+
+/// Runs a matcher profile over labeled pairs.
+///
+/// # Arguments
+/// * `pairs` - Labeled dataset
+/// * `profile` - Matcher configuration and acceptance threshold
+///
+/// # Returns
+/// * Overall metrics, per-tier confusion and misclassified pairs
+pub fn evaluate(pairs: &[LabeledPair], profile: &MatcherProfile) -> EvaluationReport {
+    let config = profile.matching_config();
+    let mut confusion = ConfusionMatrix::default();
+    let mut tiers: Vec<TierBreakdown> = Vec::new();
+    let mut misclassified = Vec::new();
+
+    for pair in pairs {
+        let explanation = matching::explain_match(
+            &pair.source.identifiers,
+            &pair.source.title,
+            &pair.candidate_price(),
+            &config,
+        );
+        let predicted_match = explanation.confidence >= profile.min_confidence;
+        let is_match = pair.label == PairLabel::Same;
+
+        confusion.record(predicted_match, is_match);
+        match tiers.iter_mut().find(|t| t.tier == explanation.tier) {
+            Some(breakdown) => breakdown.confusion.record(predicted_match, is_match),
+            None => {
+                let mut breakdown = TierBreakdown {
+                    tier: explanation.tier,
+                    confusion: ConfusionMatrix::default(),
+                };
+                breakdown.confusion.record(predicted_match, is_match);
+                tiers.push(breakdown);
+            }
+        }
+
+        if predicted_match != is_match {
+            misclassified.push(Misclassified {
+                id: pair.id.clone(),
+                label: pair.label,
+                tier: explanation.tier,
+                confidence: explanation.confidence,
+            });
+        }
+    }
+
+    tiers.sort_by_key(|t| tier_order(t.tier));
+
+    EvaluationReport {
+        profile: profile.name.clone(),
+        threshold: profile.min_confidence,
+        confusion,
+        precision: confusion.precision(),
+        recall: confusion.recall(),
+        f1: confusion.f1(),
+        tiers,
+        misclassified,
+    }
+}
+
+/// Position of a tier in reports, strongest first.
+fn tier_order(tier: MatchTier) -> u8 {
+    match tier {
+        MatchTier::Gtin => 0,
+        MatchTier::Asin => 1,
+        MatchTier::EbayItemId => 2,
+        MatchTier::ModelBrandSpecs => 3,
+        MatchTier::ModelBrand => 4,
+        MatchTier::Partial => 5,
+        MatchTier::FuzzyTitle => 6,
+        MatchTier::Preset => 7,
+    }
+}
+
+/// Formats a report as a plain-text table.
+pub fn format_report(report: &EvaluationReport) -> String {
+    let c = &report.confusion;
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "Profile: {} (threshold {})",
+        report.profile, report.threshold
+    );
+    let _ = writeln!(
+        out,
+        "Pairs: {}  TP {}  FP {}  TN {}  FN {}",
+        c.total(),
+        c.true_positives,
+        c.false_positives,
+        c.true_negatives,
+        c.false_negatives
+    );
+    let _ = writeln!(
+        out,
+        "Precision {:.3}  Recall {:.3}  F1 {:.3}",
+        report.precision, report.recall, report.f1
+    );
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "{:<18} {:>5} {:>4} {:>4} {:>4} {:>4} {:>9} {:>7}",
+        "Tier", "Pairs", "TP", "FP", "TN", "FN", "Precision", "Recall"
+    );
+    for breakdown in &report.tiers {
+        let t = &breakdown.confusion;
+        let _ = writeln!(
+            out,
+            "{:<18} {:>5} {:>4} {:>4} {:>4} {:>4} {:>9.3} {:>7.3}",
+            breakdown.tier.as_str(),
+            t.total(),
+            t.true_positives,
+            t.false_positives,
+            t.true_negatives,
+            t.false_negatives,
+            t.precision(),
+            t.recall()
+        );
+    }
+
+    if !report.misclassified.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Misclassified:");
+        for miss in &report.misclassified {
+            let label = match miss.label {
+                PairLabel::Same => "same",
+                PairLabel::Different => "different",
+            };
+            let _ = writeln!(
+                out,
+                "  {:<32} {:<9} {:<18} {:>3}",
+                miss.id,
+                label,
+                miss.tier.as_str(),
+                miss.confidence
+            );
+        }
+    }
+
+    out
+}
+
+/// Formats two reports side by side with the change from the first to the second.
+pub fn format_comparison(base: &EvaluationReport, other: &EvaluationReport) -> String {
+    let mut out = String::new();
+    let row = |out: &mut String, metric: &str, a: f64, b: f64| {
+        let _ = writeln!(
+            out,
+            "{:<26} {:>10.3} {:>10.3} {:>+8.3}",
+            metric,
+            a,
+            b,
+            b - a
+        );
+    };
+
+    let _ = writeln!(
+        out,
+        "{:<26} {:>10} {:>10} {:>8}",
+        "Metric",
+        truncate(&base.profile, 10),
+        truncate(&other.profile, 10),
+        "Delta"
+    );
+    row(&mut out, "precision", base.precision, other.precision);
+    row(&mut out, "recall", base.recall, other.recall);
+    row(&mut out, "f1", base.f1, other.f1);
+
+    let mut tiers: Vec<MatchTier> = base
+        .tiers
+        .iter()
+        .chain(&other.tiers)
+        .map(|t| t.tier)
+        .collect();
+    tiers.sort_by_key(|tier| tier_order(*tier));
+    tiers.dedup();
+
+    for tier in tiers {
+        let f1_of = |report: &EvaluationReport| {
+            report
+                .tiers
+                .iter()
+                .find(|t| t.tier == tier)
+                .map_or(0.0, |t| t.confusion.f1())
+        };
+        row(
+            &mut out,
+            &format!("f1 [{}]", tier.as_str()),
+            f1_of(base),
+            f1_of(other),
+        );
+    }
+
+    out
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    s.chars().take(max).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATASET: &str = include_str!("../../data/matching/labeled_pairs.jsonl");
+    /// F1 of the default matcher on the bundled dataset was 0.80 with multilingual matching.
+    const BASELINE_F1_FLOOR: f64 = 0.78;
+
+    #[test]
+    fn test_parse_dataset_reports_line() {
+        let jsonl = r#"{"source":{"title":"A"},"candidate":{"site":"S","title":"A"},"label":"same"}
+
+{"source":{"title":"B"},"candidate":{"site":"S","title":"B"},"label":"maybe"}"#;
+        let err = parse_dataset(jsonl).unwrap_err();
+        assert!(err.to_string().contains("line 3"));
+
+        let pairs = parse_dataset(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(pairs[0].id, "line-1");
+    }
+
+    #[test]
+    fn test_confusion_matrix_metrics() {
+        let mut confusion = ConfusionMatrix::default();
+        confusion.record(true, true);
+        confusion.record(true, false);
+        confusion.record(false, true);
+        confusion.record(false, false);
+
+        assert_eq!(confusion.total(), 4);
+        assert_eq!(confusion.precision(), 0.5);
+        assert_eq!(confusion.recall(), 0.5);
+        assert_eq!(confusion.f1(), 0.5);
+        assert_eq!(ConfusionMatrix::default().f1(), 0.0);
+    }
+
+    #[test]
+    fn test_bundled_dataset_baseline() {
+        let pairs = parse_dataset(DATASET).unwrap();
+        let report = evaluate(&pairs, &MatcherProfile::default());
+
+        assert_eq!(report.confusion.total(), pairs.len());
+        // Identifier tiers are never wrong on the bundled pairs
+        for breakdown in report.tiers.iter().filter(|t| t.tier.is_identifier()) {
+            assert_eq!(breakdown.confusion.false_positives, 0);
+            assert_eq!(breakdown.confusion.false_negatives, 0);
+        }
+        // Regression floor for the default matcher; raise it as matching improves
+        assert!(report.f1 >= BASELINE_F1_FLOOR, "{}", format_report(&report));
+    }
+
+    #[test]
+    fn test_profiles_compare_side_by_side() {
+        let pairs = parse_dataset(DATASET).unwrap();
+        let base = evaluate(&pairs, &MatcherProfile::default());
+        let strict = evaluate(
+            &pairs,
+            &MatcherProfile {
+                name: "strict".to_string(),
+                min_confidence: 95,
+                ..Default::default()
+            },
+        );
+
+        assert!(strict.precision >= base.precision);
+        let table = format_comparison(&base, &strict);
+        assert!(table.contains("default"));
+        assert!(table.contains("strict"));
+        assert!(table.contains("f1 [gtin]"));
+    }
+}
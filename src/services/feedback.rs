@@ -0,0 +1,574 @@
+//! User feedback on match quality.
+//!
+//! Users report whether a compared listing is the same product. Confirmed
+//! mismatches are dropped from later comparisons of the same source
+//! product, and the accumulated verdicts are used offline to tune tier
+//! weights and the confidence threshold (see the `tune_matching` binary).
+
+use super::catalog;
+use super::evaluation::ConfusionMatrix;
+use super::matching::{self, MatchTier, TierWeights};
+use super::money::Money;
+use crate::config::MatchingConfig;
+use crate::db::{self, MatchFeedbackRecord};
+use crate::{AppError, MatchVerdict, ProductIdentifiers, SitePrice};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Verdicts a tier needs before its weight is tuned.
+const MIN_TIER_SAMPLES: usize = 10;
+
+/// Tiers in the order they are reported.
+const TIERS: [MatchTier; 7] = [
+    MatchTier::Gtin,
+    MatchTier::Asin,
+    MatchTier::EbayItemId,
+    MatchTier::ModelBrandSpecs,
+    MatchTier::ModelBrand,
+    MatchTier::Partial,
+    MatchTier::FuzzyTitle,
+];
+
+/// Builds the key that identifies a source product across comparisons.
+///
+/// Identifiers are preferred over the title, since the same product is
+/// often listed under slightly different titles. This is the strongest of
+/// the product's catalog alias keys.
+///
+/// # Arguments
+/// * `identifiers` - Identifiers of the source product
+/// * `title` - Title or search query of the source product
+///
+/// # Returns
+/// * Key such as "gtin:00012345678905", "asin:B07FZ8S74R" or "title:dell xps 13"
+pub fn source_key(identifiers: &ProductIdentifiers, title: &str) -> String {
+    // The title key is always present, so there is at least one key
+    catalog::alias_keys(identifiers, title).swap_remove(0)
+}
+
+/// Query parameters that carry a redirect target (Amazon sponsored links, eBay rover links).
+const REDIRECT_PARAMS: [&str; 3] = ["url", "mpre", "redirect_url"];
+
+/// Redirects followed before a link is given up on as its own key.
+const MAX_REDIRECTS: usize = 2;
+
+/// Query parameters that track a visit rather than identify a listing.
+const TRACKING_PARAMS: [&str; 16] = [
+    "ref", "ref_", "tag", "qid", "sr", "keywords", "crid", "sprefix", "hash", "spm", "gclid",
+    "fbclid", "msclkid", "campid", "customid", "toolid",
+];
+
+/// Prefixes of tracking parameter families (e.g., "utm_source", "_trksid").
+const TRACKING_PREFIXES: [&str; 6] = ["utm_", "_trk", "pd_rd_", "pf_rd_", "mk", "sp_"];
+
+/// Normalizes a listing link so redirects and tracking parameters don't hide a match.
+///
+/// Sponsored and redirect links are followed to their target. Amazon
+/// listings are keyed by ASIN and eBay listings by item ID; other links
+/// keep their path (case preserved, since IDs can be case-sensitive) and
+/// the query parameters that are not tracking.
+///
+/// # Arguments
+/// * `link` - Listing URL
+///
+/// # Returns
+/// * Key such as "amazon.com/dp/B09XS7JWHH", "ebay.com/itm/123456789012"
+///   or "shop.example/product?id=42"
+pub fn candidate_key(link: &str) -> String {
+    let mut url = match reqwest::Url::parse(link.trim()) {
+        Ok(url) => url,
+        Err(_) => {
+            let end = link.find(['?', '#']).unwrap_or(link.len());
+            return link[..end].trim().trim_end_matches('/').to_string();
+        }
+    };
+    for _ in 0..MAX_REDIRECTS {
+        match redirect_target(&url) {
+            Some(target) => url = target,
+            None => break,
+        }
+    }
+
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let path = url.path().trim_end_matches('/');
+
+    if host.contains("amazon.") {
+        if let Some(asin) = matching::extract_asin(path) {
+            return format!("{}/dp/{}", host, asin.to_uppercase());
+        }
+    }
+    if host.contains("ebay.") {
+        if let Some(item_id) = matching::extract_ebay_item_id(path) {
+            return format!("{}/itm/{}", host, item_id);
+        }
+    }
+
+    let mut params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    params.sort();
+
+    let mut key = format!("{}{}", host, path);
+    if !params.is_empty() {
+        let query: Vec<String> = params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        key.push('?');
+        key.push_str(&query.join("&"));
+    }
+    key
+}
+
+/// Returns the link a sponsored or redirect link points to.
+fn redirect_target(url: &reqwest::Url) -> Option<reqwest::Url> {
+    let (_, target) = url
+        .query_pairs()
+        .find(|(name, _)| REDIRECT_PARAMS.contains(&name.as_ref()))?;
+    // Relative targets (Amazon's "/Name/dp/ASIN/...") resolve against the redirect's host
+    let target = url.join(&target).ok()?;
+    matches!(target.scheme(), "http" | "https").then_some(target)
+}
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_lowercase();
+    TRACKING_PARAMS.contains(&name.as_str())
+        || TRACKING_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// Removes listings users have confirmed are a different product.
+///
+/// A database failure is logged and the listings are returned unchanged,
+/// so feedback storage never blocks a comparison.
+///
+/// # Arguments
+/// * `prices` - Scored listings
+/// * `source_key` - Key of the source product (see `source_key`)
+/// * `pool` - Database connection pool
+///
+/// # Returns
+/// * Listings without rejected candidates
+pub async fn suppress_rejected(
+    mut prices: Vec<SitePrice>,
+    source_key: &str,
+    pool: &PgPool,
+) -> Vec<SitePrice> {
+    let rejected = match db::get_rejected_candidates(pool, source_key).await {
+        Ok(rejected) => rejected,
+        Err(e) => {
+            tracing::warn!(source_key = %source_key, error = %e, "Failed to load match feedback");
+            return prices;
+        }
+    };
+    if rejected.is_empty() {
+        return prices;
+    }
+
+    let original_count = prices.len();
+    prices.retain(|p| !rejected.contains(&candidate_key(&p.link)));
+
+    if prices.len() < original_count {
+        tracing::info!(
+            source_key = %source_key,
+            removed = original_count - prices.len(),
+            "Suppressed listings rejected by user feedback"
+        );
+    }
+
+    prices
+}
+
+/// Outcome of a link key backfill.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LinkKeyBackfill {
+    /// History rows that received a link key
+    pub history_rows: u64,
+    /// Feedback rows whose candidate key changed
+    pub feedback_rows: u64,
+    /// Catalog listings whose link key changed
+    pub listings: u64,
+    /// Catalog listings kept on their old key because the new one is taken
+    pub listings_kept: u64,
+}
+
+/// Keys rows stored before link keys were computed by `candidate_key`.
+///
+/// History rows recorded without a key get one; feedback and catalog keys
+/// written by earlier normalizations are replaced, so old rejections and
+/// listings match the keys computed for new comparisons.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `batch_size` - History links keyed per update
+///
+/// # Returns
+/// * `Ok(LinkKeyBackfill)` - Counts of updated rows
+/// * `Err(AppError)` - Database error
+pub async fn backfill_link_keys(
+    pool: &PgPool,
+    batch_size: i64,
+) -> Result<LinkKeyBackfill, AppError> {
+    let keyed = |links: Vec<String>| -> Vec<(String, String)> {
+        links
+            .into_iter()
+            .map(|link| {
+                let key = candidate_key(&link);
+                (link, key)
+            })
+            .collect()
+    };
+    let mut report = LinkKeyBackfill::default();
+
+    loop {
+        let links = db::get_history_links_without_key(pool, batch_size).await?;
+        if links.is_empty() {
+            break;
+        }
+        report.history_rows += db::set_history_link_keys(pool, &keyed(links)).await?;
+    }
+
+    let feedback = keyed(db::get_feedback_candidate_links(pool).await?);
+    report.feedback_rows = db::set_feedback_candidate_keys(pool, &feedback).await?;
+
+    let listings = keyed(db::get_product_listing_links(pool).await?);
+    report.listings = db::set_product_listing_link_keys(pool, &listings).await?;
+    report.listings_kept = db::count_product_listings_with_other_key(pool, &listings).await? as u64;
+
+    Ok(report)
+}
+
+/// One user verdict, ready to be re-scored.
+#[derive(Debug, Clone)]
+pub struct FeedbackSample {
+    pub identifiers: ProductIdentifiers,
+    pub source_title: String,
+    pub candidate: SitePrice,
+    pub verdict: MatchVerdict,
+}
+
+impl TryFrom<MatchFeedbackRecord> for FeedbackSample {
+    type Error = AppError;
+
+    fn try_from(record: MatchFeedbackRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            identifiers: record.source_identifiers.0,
+            source_title: record.source_title,
+            // Matching only looks at site, title and link
+            candidate: SitePrice::new(
+                record.candidate_site,
+                record.candidate_title,
+                Money::usd(Decimal::ZERO),
+                Decimal::ZERO,
+                record.candidate_link,
+                None,
+                None,
+            ),
+            verdict: MatchVerdict::from_str(&record.verdict)?,
+        })
+    }
+}
+
+/// Precision and recall of accepting matches at a confidence threshold.
+///
+/// Ratios are 0.0 when undefined (no accepted or no true matches).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrecisionRecall {
+    pub threshold: u8,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+/// Verdict counts for one matching tier.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TierStats {
+    pub tier: MatchTier,
+    pub samples: usize,
+    pub matches: usize,
+}
+
+/// Result of tuning tier weights and threshold against user feedback.
+#[derive(Debug, Clone, Serialize)]
+pub struct TuningReport {
+    pub samples: usize,
+    /// Current weights at the configured threshold
+    pub baseline: PrecisionRecall,
+    /// Tuned weights at the recommended threshold
+    pub tuned: PrecisionRecall,
+    pub tier_weights: TierWeights,
+    pub tiers: Vec<TierStats>,
+}
+
+/// Measures precision and recall of a matching config on feedback samples.
+///
+/// # Arguments
+/// * `samples` - User verdicts
+/// * `config` - Matching configuration to score with
+/// * `threshold` - Minimum confidence to accept a match
+///
+/// # Returns
+/// * Precision and recall at the threshold
+pub fn evaluate(
+    samples: &[FeedbackSample],
+    config: &MatchingConfig,
+    threshold: u8,
+) -> PrecisionRecall {
+    metrics_at(&score_all(samples, config), threshold)
+}
+
+/// Tunes tier weights and the confidence threshold from user feedback.
+///
+/// Each tier with at least `MIN_TIER_SAMPLES` verdicts gets a weight equal
+/// to its observed precision (Laplace-smoothed, as a percentage). The
+/// recommended threshold maximizes F1 under the tuned weights.
+///
+/// # Arguments
+/// * `samples` - User verdicts
+/// * `config` - Current matching configuration
+/// * `min_confidence` - Current acceptance threshold
+///
+/// # Returns
+/// * Baseline and tuned precision/recall with the tuned weights
+pub fn tune(
+    samples: &[FeedbackSample],
+    config: &MatchingConfig,
+    min_confidence: u8,
+) -> TuningReport {
+    let mut counts: HashMap<MatchTier, (usize, usize)> = HashMap::new();
+    for sample in samples {
+        let explanation = matching::explain_match(
+            &sample.identifiers,
+            &sample.source_title,
+            &sample.candidate,
+            config,
+        );
+        let entry = counts.entry(explanation.tier).or_default();
+        entry.0 += 1;
+        if sample.verdict == MatchVerdict::Match {
+            entry.1 += 1;
+        }
+    }
+
+    let mut tier_weights = config.tier_weights;
+    let mut tiers = Vec::new();
+    for tier in TIERS {
+        let (total, matches) = counts.get(&tier).copied().unwrap_or_default();
+        if total >= MIN_TIER_SAMPLES {
+            if let Some(weight) = tier_weights.weight_mut(tier) {
+                let precision = (matches + 1) as f64 / (total + 2) as f64;
+                *weight = (precision * 100.0).round() as u8;
+            }
+        }
+        if total > 0 {
+            tiers.push(TierStats {
+                tier,
+                samples: total,
+                matches,
+            });
+        }
+    }
+
+    let tuned_config = MatchingConfig {
+        tier_weights,
+        ..config.clone()
+    };
+    let scored = score_all(samples, &tuned_config);
+    // Ties go to the higher threshold, which favors precision
+    let tuned = (0..=100)
+        .map(|threshold| metrics_at(&scored, threshold))
+        .fold(None::<PrecisionRecall>, |best, current| match best {
+            Some(best) if best.f1 > current.f1 => Some(best),
+            _ => Some(current),
+        })
+        .expect("threshold range is not empty");
+
+    TuningReport {
+        samples: samples.len(),
+        baseline: evaluate(samples, config, min_confidence),
+        tuned,
+        tier_weights,
+        tiers,
+    }
+}
+
+/// Scores every sample, pairing the confidence with the user verdict.
+fn score_all(samples: &[FeedbackSample], config: &MatchingConfig) -> Vec<(u8, bool)> {
+    samples
+        .iter()
+        .map(|sample| {
+            let confidence = matching::calculate_match_confidence(
+                &sample.identifiers,
+                &sample.source_title,
+                &sample.candidate,
+                config,
+            );
+            (confidence, sample.verdict == MatchVerdict::Match)
+        })
+        .collect()
+}
+
+/// Computes precision and recall of scored samples at a threshold.
+fn metrics_at(scored: &[(u8, bool)], threshold: u8) -> PrecisionRecall {
+    let mut confusion = ConfusionMatrix::default();
+    for &(confidence, is_match) in scored {
+        confusion.record(confidence >= threshold, is_match);
+    }
+
+    PrecisionRecall {
+        threshold,
+        precision: confusion.precision(),
+        recall: confusion.recall(),
+        f1: confusion.f1(),
+        true_positives: confusion.true_positives,
+        false_positives: confusion.false_positives,
+        false_negatives: confusion.false_negatives,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::currency::Currency;
+
+    fn sample(
+        source: &ProductIdentifiers,
+        candidate_title: &str,
+        verdict: MatchVerdict,
+    ) -> FeedbackSample {
+        FeedbackSample {
+            identifiers: source.clone(),
+            source_title: "Samsung Galaxy A54".to_string(),
+            candidate: SitePrice::new(
+                "Jumia".to_string(),
+                candidate_title.to_string(),
+                Money::new(Decimal::ZERO, Currency::NGN),
+                Decimal::ZERO,
+                "https://www.jumia.com.ng/a54".to_string(),
+                None,
+                None,
+            ),
+            verdict,
+        }
+    }
+
+    #[test]
+    fn test_source_key_prefers_identifiers() {
+        let gtin = ProductIdentifiers {
+            upc: Some("012345678905".to_string()),
+            asin: Some("B07FZ8S74R".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(source_key(&gtin, "Widget"), "gtin:00012345678905");
+
+        let asin = ProductIdentifiers {
+            asin: Some("b07fz8s74r".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(source_key(&asin, "Widget"), "asin:B07FZ8S74R");
+
+        assert_eq!(
+            source_key(&ProductIdentifiers::default(), "  Dell XPS-13 (2024) "),
+            "title:dell xps 13 2024"
+        );
+    }
+
+    #[test]
+    fn test_candidate_key_strips_tracking() {
+        assert_eq!(
+            candidate_key("https://www.Jumia.com.ng/a54/?utm_source=x#reviews"),
+            "jumia.com.ng/a54"
+        );
+        assert_eq!(candidate_key("https://konga.com/p/1"), "konga.com/p/1");
+
+        // Identifying parameters and case-sensitive paths are kept
+        assert_eq!(
+            candidate_key("https://shop.example/product?utm_medium=cpc&id=42"),
+            "shop.example/product?id=42"
+        );
+        assert_ne!(
+            candidate_key("https://shop.example/product?id=42"),
+            candidate_key("https://shop.example/product?id=43")
+        );
+        assert_ne!(
+            candidate_key("https://shop.example/p/AbC12"),
+            candidate_key("https://shop.example/p/abc12")
+        );
+    }
+
+    #[test]
+    fn test_candidate_key_follows_sponsored_and_redirect_links() {
+        let listing =
+            "https://www.amazon.com/Sony-WH-1000XM5/dp/B09XS7JWHH/ref=sr_1_1?keywords=sony";
+        let sponsored = "https://www.amazon.com/sspa/click?ie=UTF8&spc=MTo&url=%2FSony-WH-1000XM5%2Fdp%2FB09XS7JWHH%2Fref%3Dsr_1_1_sspa%3Fkeywords%3Dsony&sp_csd=d2lk";
+        let other = "https://www.amazon.com/sspa/click?ie=UTF8&url=%2FBose-QC45%2Fdp%2FB098FKXT8L";
+        assert_eq!(candidate_key(listing), "amazon.com/dp/B09XS7JWHH");
+        assert_eq!(candidate_key(sponsored), candidate_key(listing));
+        assert_eq!(candidate_key(other), "amazon.com/dp/B098FKXT8L");
+
+        let rover = "https://rover.ebay.com/rover/1/711-53200-19255-0/1?campid=5338&mpre=https%3A%2F%2Fwww.ebay.com%2Fitm%2F123456789012";
+        assert_eq!(candidate_key(rover), "ebay.com/itm/123456789012");
+        assert_eq!(
+            candidate_key("https://www.ebay.com/itm/Sony-Headphones/123456789012?hash=item1c"),
+            "ebay.com/itm/123456789012"
+        );
+    }
+
+    #[test]
+    fn test_metrics_at_threshold() {
+        let scored = [(95, true), (90, false), (70, true), (40, false)];
+        let metrics = metrics_at(&scored, 80);
+        assert_eq!(metrics.true_positives, 1);
+        assert_eq!(metrics.false_positives, 1);
+        assert_eq!(metrics.false_negatives, 1);
+        assert_eq!(metrics.precision, 0.5);
+        assert_eq!(metrics.recall, 0.5);
+
+        assert_eq!(metrics_at(&[], 80).f1, 0.0);
+    }
+
+    #[test]
+    fn test_tune_lowers_unreliable_tier() {
+        let source = ProductIdentifiers {
+            model_number: Some("Galaxy A54".to_string()),
+            brand: Some("Samsung".to_string()),
+            ..Default::default()
+        };
+        let config = MatchingConfig::default();
+
+        // Partial matches (brand only) are mostly wrong
+        let mut samples = Vec::new();
+        for i in 0..12 {
+            let verdict = if i < 2 {
+                MatchVerdict::Match
+            } else {
+                MatchVerdict::Mismatch
+            };
+            samples.push(sample(&source, "Samsung Galaxy S23", verdict));
+        }
+        for _ in 0..12 {
+            samples.push(sample(
+                &source,
+                "Samsung Galaxy A54 5G",
+                MatchVerdict::Match,
+            ));
+        }
+
+        let report = tune(&samples, &config, 70);
+
+        assert_eq!(report.samples, 24);
+        assert!(report.tier_weights.partial < config.tier_weights.partial);
+        assert!(report.tuned.precision > report.baseline.precision);
+        assert!(report.tuned.threshold > report.tier_weights.partial);
+    }
+}
@@ -0,0 +1,169 @@
+//! GTIN/UPC/EAN normalization and check digit validation.
+//!
+//! UPC-A (12 digits), EAN-8, EAN-13 and GTIN-14 are all encodings of the
+//! same GS1 trade item number. Normalizing every format to a zero-padded
+//! GTIN-14 lets identifiers scraped from different sites be compared directly.
+
+use crate::AppError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Lengths of the GS1 formats accepted as trade item numbers.
+const VALID_LENGTHS: [usize; 4] = [8, 12, 13, 14];
+
+/// Trade item number normalized to GTIN-14.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Gtin(String);
+
+impl Gtin {
+    /// Parses a UPC-A, EAN-8, EAN-13 or GTIN-14 code.
+    ///
+    /// Spaces and hyphens are ignored. The check digit must be valid.
+    ///
+    /// # Arguments
+    /// * `raw` - Code as entered or scraped (e.g., "0 12345 67890 5")
+    ///
+    /// # Returns
+    /// * `Ok(Gtin)` - Code normalized to 14 digits
+    /// * `Err(AppError)` - Wrong length, non-digit characters or bad check digit
+    pub fn parse(raw: &str) -> Result<Self, AppError> {
+        let digits: String = raw
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect();
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::InvalidInput(format!(
+                "GTIN must contain only digits: {}",
+                raw
+            )));
+        }
+
+        if !VALID_LENGTHS.contains(&digits.len()) {
+            return Err(AppError::InvalidInput(format!(
+                "GTIN must be 8, 12, 13 or 14 digits, got {}: {}",
+                digits.len(),
+                raw
+            )));
+        }
+
+        if !has_valid_check_digit(&digits) {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid GTIN check digit: {}",
+                raw
+            )));
+        }
+
+        Ok(Self(format!("{:0>14}", digits)))
+    }
+
+    /// Returns the 14-digit normalized form.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the 12-digit UPC-A form, if the code fits in one.
+    pub fn to_upc_a(&self) -> Option<&str> {
+        self.0.strip_prefix("00")
+    }
+
+    /// Returns the 13-digit EAN-13 form, if the code fits in one.
+    pub fn to_ean13(&self) -> Option<&str> {
+        self.0.strip_prefix('0')
+    }
+}
+
+impl FromStr for Gtin {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Gtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Validates the GS1 mod-10 check digit of an all-digit code.
+///
+/// Starting from the digit left of the check digit, digits are weighted
+/// alternately 3 and 1. The check digit brings the sum to a multiple of 10.
+fn has_valid_check_digit(digits: &str) -> bool {
+    let values: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    let Some((&check, body)) = values.split_last() else {
+        return false;
+    };
+
+    let sum: u32 = body
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+        .sum();
+
+    (10 - sum % 10) % 10 == check
+}
+
+/// Finds every valid trade item number that appears as a standalone token.
+///
+/// Digit runs embedded in longer alphanumeric tokens (e.g., inside a URL
+/// slug or a longer serial) are ignored to avoid false matches.
+///
+/// # Arguments
+/// * `text` - Text to scan (usually a product title)
+///
+/// # Returns
+/// * Normalized GTINs found in the text
+pub fn find_in_text(text: &str) -> Vec<Gtin> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| VALID_LENGTHS.contains(&token.len()))
+        .filter_map(|token| Gtin::parse(token).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_normalize_to_same_gtin() {
+        let upc = Gtin::parse("012345678905").unwrap();
+        let ean = Gtin::parse("0012345678905").unwrap();
+        let gtin = Gtin::parse("00012345678905").unwrap();
+
+        assert_eq!(upc, ean);
+        assert_eq!(ean, gtin);
+        assert_eq!(upc.as_str(), "00012345678905");
+        assert_eq!(upc.to_upc_a(), Some("012345678905"));
+        assert_eq!(upc.to_ean13(), Some("0012345678905"));
+    }
+
+    #[test]
+    fn test_ean8_and_separators() {
+        let ean8 = Gtin::parse("9638-5074").unwrap();
+        assert_eq!(ean8.as_str(), "00000096385074");
+
+        let spaced = Gtin::parse("0 12345 67890 5").unwrap();
+        assert_eq!(spaced.as_str(), "00012345678905");
+    }
+
+    #[test]
+    fn test_rejects_malformed_codes() {
+        assert!(Gtin::parse("012345678906").is_err()); // bad check digit
+        assert!(Gtin::parse("12345").is_err()); // wrong length
+        assert!(Gtin::parse("01234567890A").is_err()); // non-digit
+        assert!(Gtin::parse("").is_err());
+    }
+
+    #[test]
+    fn test_find_in_text() {
+        let found = find_in_text("Widget (UPC: 012345678905) blue");
+        assert_eq!(found, vec![Gtin::parse("00012345678905").unwrap()]);
+
+        // Embedded in a longer token is not a standalone code
+        assert!(find_in_text("item/x012345678905").is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! Batched price history writer.
+//!
+//! Comparisons queue their listings on a bounded channel and return; a
+//! background task writes them to `price_history` in multi-row inserts,
+//! flushing when a batch fills up or the flush interval passes. When the
+//! queue is full, rows are dropped and counted in
+//! `price_history_rows_total{outcome="dropped"}` rather than slowing the
+//! request down.
+
+use super::feedback;
+use super::rate_history;
+use crate::config::HistoryConfig;
+use crate::db;
+use crate::models::RankBy;
+use crate::observability::metrics;
+use crate::{ProductIdentifiers, SitePrice};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// Most rows in one insert, keeping the bind parameters under Postgres' limit.
+const MAX_BATCH_SIZE: usize = 2000;
+
+/// Request a listing was recorded for.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RequestContext {
+    /// Site the user was browsing (e.g., "amazon")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_site: Option<String>,
+    /// Currency the prices were converted to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_currency: Option<String>,
+    /// Order the listings were ranked by
+    pub ranked_by: RankBy,
+}
+
+/// One listing queued for `price_history`.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub price: SitePrice,
+    /// Normalized listing link (see `feedback::candidate_key`)
+    pub link_key: String,
+    pub search_query: String,
+    pub product_id: Option<Uuid>,
+    /// USD per unit of the listing's currency, when it came from a rate provider
+    pub exchange_rate: Option<Decimal>,
+    pub identifiers: ProductIdentifiers,
+    pub context: RequestContext,
+}
+
+impl HistoryEntry {
+    /// Builds the entries for a comparison's listings.
+    ///
+    /// # Arguments
+    /// * `prices` - Listings returned by the comparison
+    /// * `identifiers` - Identifiers of the source product
+    /// * `search_query` - Search query of the comparison
+    /// * `product_id` - Canonical product, when the catalog resolved one
+    /// * `context` - Request the comparison was made for
+    pub fn for_comparison(
+        prices: &[SitePrice],
+        identifiers: &ProductIdentifiers,
+        search_query: &str,
+        product_id: Option<Uuid>,
+        context: &RequestContext,
+    ) -> Vec<Self> {
+        prices
+            .iter()
+            .map(|price| Self {
+                link_key: feedback::candidate_key(&price.link),
+                exchange_rate: rate_history::history_rate(price.usd_rate.as_ref()),
+                price: price.clone(),
+                search_query: search_query.to_string(),
+                product_id,
+                identifiers: identifiers.clone(),
+                context: context.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Handle for queueing rows; cheap to clone.
+#[derive(Debug, Clone)]
+pub struct HistoryWriter {
+    sender: mpsc::Sender<HistoryEntry>,
+}
+
+impl HistoryWriter {
+    /// Starts the writer task.
+    ///
+    /// The task exits once every handle is dropped, after writing the rows
+    /// still queued.
+    ///
+    /// # Arguments
+    /// * `pool` - Database connection pool
+    /// * `config` - Queue capacity, batch size and flush interval
+    ///
+    /// # Returns
+    /// * Handle for queueing rows, and the writer task
+    pub fn spawn(pool: PgPool, config: &HistoryConfig) -> (Self, JoinHandle<()>) {
+        let (writer, receiver) = Self::channel(config.queue_capacity);
+        let batch_size = config.batch_size.clamp(1, MAX_BATCH_SIZE);
+        let flush_interval = Duration::from_millis(config.flush_interval_ms);
+
+        let task = tokio::spawn(run(pool, receiver, batch_size, flush_interval));
+        (writer, task)
+    }
+
+    fn channel(capacity: usize) -> (Self, mpsc::Receiver<HistoryEntry>) {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        (Self { sender }, receiver)
+    }
+
+    /// Queues rows without waiting; rows that do not fit are dropped.
+    ///
+    /// # Returns
+    /// * Number of rows queued
+    pub fn record(&self, entries: Vec<HistoryEntry>) -> usize {
+        let total = entries.len();
+        let mut queued = 0;
+        for entry in entries {
+            match self.sender.try_send(entry) {
+                Ok(()) => queued += 1,
+                Err(TrySendError::Full(_)) => break,
+                Err(TrySendError::Closed(_)) => {
+                    tracing::error!("Price history writer stopped");
+                    break;
+                }
+            }
+        }
+
+        let dropped = total - queued;
+        if dropped > 0 {
+            tracing::warn!(dropped, "Price history queue full, dropping rows");
+            metrics::record_price_history_rows("dropped", dropped);
+        }
+        queued
+    }
+}
+
+/// Writes queued rows in batches until every handle is dropped.
+async fn run(
+    pool: PgPool,
+    mut receiver: mpsc::Receiver<HistoryEntry>,
+    batch_size: usize,
+    flush_interval: Duration,
+) {
+    let mut batch = Vec::with_capacity(batch_size);
+
+    // Wait for a first row, then collect until the batch is full or the interval passes
+    while receiver.recv_many(&mut batch, batch_size).await > 0 {
+        let deadline = Instant::now() + flush_interval;
+        while batch.len() < batch_size {
+            let limit = batch_size - batch.len();
+            match tokio::time::timeout_at(deadline, receiver.recv_many(&mut batch, limit)).await {
+                Ok(received) if received > 0 => {}
+                _ => break,
+            }
+        }
+
+        match db::save_price_history_batch(&pool, &batch).await {
+            Ok(written) => {
+                let written = written as usize;
+                tracing::debug!(rows = written, "Price history batch written");
+                metrics::record_price_history_rows("written", written);
+                let missing = batch.len().saturating_sub(written);
+                if missing > 0 {
+                    tracing::warn!(rows = missing, "Price history rows not inserted");
+                    metrics::record_price_history_rows("failed", missing);
+                }
+            }
+            Err(e) => {
+                tracing::warn!(rows = batch.len(), error = %e, "Failed to write price history");
+                metrics::record_price_history_rows("failed", batch.len());
+            }
+        }
+        batch.clear();
+    }
+
+    tracing::info!("Price history writer stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{currency::Currency, money::Money};
+
+    #[test]
+    fn test_record_drops_rows_when_queue_full() {
+        let price = SitePrice::new(
+            "Amazon".to_string(),
+            "Dell XPS 13".to_string(),
+            Money::new(Decimal::from(999), Currency::USD),
+            Decimal::from(999),
+            "https://amazon.com/dp/B0C".to_string(),
+            None,
+            Some(95),
+        );
+        let context = RequestContext {
+            current_site: Some("ebay".to_string()),
+            ..Default::default()
+        };
+        let entries = HistoryEntry::for_comparison(
+            &[price.clone(), price.clone(), price],
+            &ProductIdentifiers::default(),
+            "dell xps 13",
+            None,
+            &context,
+        );
+
+        let (writer, mut receiver) = HistoryWriter::channel(2);
+        assert_eq!(writer.record(entries), 2);
+
+        let queued = receiver.try_recv().unwrap();
+        assert_eq!(queued.search_query, "dell xps 13");
+        assert_eq!(queued.context, context);
+        assert_eq!(queued.exchange_rate, None);
+        assert_eq!(
+            serde_json::to_value(&queued.context).unwrap(),
+            serde_json::json!({"current_site": "ebay", "ranked_by": "price"})
+        );
+    }
+}
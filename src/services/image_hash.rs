@@ -0,0 +1,317 @@
+//! Perceptual image hashing of listing images.
+//!
+//! Marketplaces often reuse the manufacturer's product shot even when the
+//! titles diverge ("Galaxy A54" vs "SM-A546E/DS"). Each listing image is
+//! reduced to a 64-bit difference hash (dHash); the Hamming distance to the
+//! source image's hash is then a cheap "same picture" signal for matching.
+//! Hashes are cached in Redis by image URL, so a picture is downloaded once.
+
+use crate::config::ScraperConfig;
+use crate::utils::{fetch_bytes_with_retry, RateLimiter};
+use crate::{AppError, ProductIdentifiers, SitePrice};
+use deadpool_redis::Pool;
+use image::imageops::FilterType;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use tokio::task::JoinSet;
+
+/// Largest image accepted for hashing; product thumbnails are far smaller.
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// 64-bit difference hash of an image, serialized as 16 hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ImageHash(pub u64);
+
+impl ImageHash {
+    /// Hashes an encoded image (JPEG, PNG or WebP).
+    ///
+    /// The image is reduced to 9x8 grayscale pixels; each bit records whether
+    /// a pixel is brighter than its right neighbour. Rescaling, recompression
+    /// and small watermarks barely change the hash.
+    ///
+    /// # Arguments
+    /// * `bytes` - Encoded image
+    ///
+    /// # Returns
+    /// * `Ok(ImageHash)` - Difference hash
+    /// * `Err(AppError)` - Unsupported or corrupt image
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AppError> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| AppError::Parse(format!("Failed to decode image: {}", e)))?;
+        let pixels = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+        let mut hash = 0u64;
+        for y in 0..8 {
+            for x in 0..8 {
+                let brighter = pixels.get_pixel(x, y)[0] > pixels.get_pixel(x + 1, y)[0];
+                hash = (hash << 1) | brighter as u64;
+            }
+        }
+        Ok(Self(hash))
+    }
+
+    /// Number of differing bits (0 for the same picture, about 32 for unrelated ones).
+    pub fn distance(&self, other: &ImageHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+impl fmt::Display for ImageHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl TryFrom<String> for ImageHash {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        u64::from_str_radix(value.trim(), 16)
+            .map(Self)
+            .map_err(|e| format!("Invalid image hash {}: {}", value, e))
+    }
+}
+
+impl From<ImageHash> for String {
+    fn from(hash: ImageHash) -> Self {
+        hash.to_string()
+    }
+}
+
+/// Sets the source image hash from the image URL sent with the request.
+///
+/// A hash already present in the identifiers (computed by the client) is kept.
+///
+/// # Arguments
+/// * `identifiers` - Identifiers of the source product
+/// * `image_url` - Image of the product on the page being viewed
+/// * `client` - HTTP client
+/// * `redis_pool` - Redis pool for the hash cache
+/// * `config` - Scraper configuration with retries and image hashing limits
+///
+/// # Returns
+/// * Identifiers with `image_hash` set when the image could be hashed
+pub async fn hash_source(
+    identifiers: &ProductIdentifiers,
+    image_url: Option<&str>,
+    client: &Client,
+    redis_pool: &Pool,
+    config: &ScraperConfig,
+) -> ProductIdentifiers {
+    let mut identifiers = identifiers.clone();
+    if identifiers.image_hash.is_some() || config.image_hashing.max_fetches == 0 {
+        return identifiers;
+    }
+    let Some(url) = image_url.filter(|url| url.starts_with("http")) else {
+        return identifiers;
+    };
+
+    match hash_url(url, client, redis_pool, config).await {
+        Ok(hash) => identifiers.image_hash = Some(hash),
+        Err(e) => tracing::debug!(url = %url, error = %e, "Failed to hash source image"),
+    }
+    identifiers
+}
+
+/// Sets `image_hash` on listings, from the cache or by fetching their images.
+///
+/// Up to `max_fetches` uncached images are downloaded; downloads start no
+/// faster than the scraper rate limit but run concurrently. Listings whose
+/// image cannot be fetched or decoded are left without a hash.
+///
+/// # Arguments
+/// * `prices` - Scraped listings
+/// * `client` - HTTP client
+/// * `redis_pool` - Redis pool for the hash cache
+/// * `config` - Scraper configuration with retries, rate limit and image hashing limits
+pub async fn hash_listings(
+    prices: &mut [SitePrice],
+    client: &Client,
+    redis_pool: &Pool,
+    config: &ScraperConfig,
+) {
+    let mut hashes: HashMap<String, ImageHash> = HashMap::new();
+    let mut uncached: Vec<String> = Vec::new();
+
+    for url in prices
+        .iter()
+        .filter(|p| p.image_hash.is_none())
+        .filter_map(|p| p.image.clone())
+        .filter(|url| url.starts_with("http"))
+    {
+        if hashes.contains_key(&url) || uncached.contains(&url) {
+            continue;
+        }
+        match get_cached_hash(redis_pool, &url).await {
+            Ok(Some(hash)) => {
+                hashes.insert(url, hash);
+            }
+            Ok(None) => uncached.push(url),
+            Err(e) => {
+                tracing::debug!(error = %e, "Image hash cache unavailable");
+                uncached.push(url);
+            }
+        }
+    }
+
+    let limiter = RateLimiter::new(config.rate_limit_per_second);
+    let mut tasks = JoinSet::new();
+    for (i, url) in uncached
+        .into_iter()
+        .take(config.image_hashing.max_fetches)
+        .enumerate()
+    {
+        if i > 0 {
+            limiter.wait().await;
+        }
+        let (client, redis_pool, config) = (client.clone(), redis_pool.clone(), config.clone());
+        tasks.spawn(async move {
+            let hash = hash_url(&url, &client, &redis_pool, &config).await;
+            (url, hash)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((url, Ok(hash))) => {
+                hashes.insert(url, hash);
+            }
+            Ok((url, Err(e))) => tracing::debug!(url = %url, error = %e, "Failed to hash image"),
+            Err(e) => tracing::warn!(error = %e, "Image hashing task failed"),
+        }
+    }
+
+    for price in prices.iter_mut().filter(|p| p.image_hash.is_none()) {
+        price.image_hash = price
+            .image
+            .as_ref()
+            .and_then(|url| hashes.get(url))
+            .copied();
+    }
+}
+
+/// Hashes the image at a URL, using and filling the cache.
+async fn hash_url(
+    url: &str,
+    client: &Client,
+    redis_pool: &Pool,
+    config: &ScraperConfig,
+) -> Result<ImageHash, AppError> {
+    if let Ok(Some(hash)) = get_cached_hash(redis_pool, url).await {
+        return Ok(hash);
+    }
+
+    let bytes = fetch_bytes_with_retry(client, url, config.max_retries).await?;
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(AppError::Parse(format!(
+            "Image too large to hash ({} bytes): {}",
+            bytes.len(),
+            url
+        )));
+    }
+    // Decoding and resizing are CPU-bound
+    let hash = tokio::task::spawn_blocking(move || ImageHash::from_bytes(&bytes))
+        .await
+        .map_err(|e| AppError::Internal(format!("Image hashing task failed: {}", e)))??;
+
+    let ttl_seconds = config.image_hashing.cache_ttl_seconds;
+    if let Err(e) = cache_hash(redis_pool, url, hash, ttl_seconds).await {
+        tracing::debug!(url = %url, error = %e, "Failed to cache image hash");
+    }
+    Ok(hash)
+}
+
+/// Redis key of an image URL's hash.
+fn cache_key(url: &str) -> String {
+    format!("image_hash:{:x}", Sha256::digest(url.as_bytes()))
+}
+
+async fn get_cached_hash(redis_pool: &Pool, url: &str) -> Result<Option<ImageHash>, AppError> {
+    let mut conn = redis_pool
+        .get()
+        .await
+        .map_err(|e| AppError::Cache(format!("Redis connection failed: {}", e)))?;
+
+    let cached: Option<String> = redis::cmd("GET")
+        .arg(cache_key(url))
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| AppError::Cache(format!("Cache read failed: {}", e)))?;
+
+    cached
+        .map(|hex| ImageHash::try_from(hex).map_err(AppError::Cache))
+        .transpose()
+}
+
+async fn cache_hash(
+    redis_pool: &Pool,
+    url: &str,
+    hash: ImageHash,
+    ttl_seconds: u64,
+) -> Result<(), AppError> {
+    let mut conn = redis_pool
+        .get()
+        .await
+        .map_err(|e| AppError::Cache(format!("Redis connection failed: {}", e)))?;
+
+    let _: () = redis::cmd("SETEX")
+        .arg(cache_key(url))
+        .arg(ttl_seconds)
+        .arg(hash.to_string())
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| AppError::Cache(format!("Cache write failed: {}", e)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    /// Encodes a 64x64 image whose pixels are `shade(x, y)`.
+    fn png(shade: impl Fn(u32, u32) -> u8) -> Vec<u8> {
+        let image = RgbImage::from_fn(64, 64, |x, y| {
+            let v = shade(x, y);
+            Rgb([v, v, v])
+        });
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn test_similar_images_have_close_hashes() {
+        let gradient = ImageHash::from_bytes(&png(|x, y| (x * 3 + y) as u8)).unwrap();
+        // Same picture, slightly brighter and with a small watermark
+        let edited = ImageHash::from_bytes(&png(|x, y| {
+            if x > 56 && y > 56 {
+                255
+            } else {
+                (x * 3 + y + 10) as u8
+            }
+        }))
+        .unwrap();
+        let mirrored = ImageHash::from_bytes(&png(|x, y| (200 - x * 3 + y) as u8)).unwrap();
+
+        assert!(gradient.distance(&edited) <= 4);
+        assert!(gradient.distance(&mirrored) >= 32);
+    }
+
+    #[test]
+    fn test_hash_serializes_as_hex() {
+        let hash = ImageHash(0x00ff_00ff_1234_abcd);
+        let json = serde_json::to_string(&hash).unwrap();
+
+        assert_eq!(json, "\"00ff00ff1234abcd\"");
+        assert_eq!(serde_json::from_str::<ImageHash>(&json).unwrap(), hash);
+        assert!(serde_json::from_str::<ImageHash>("\"not hex\"").is_err());
+        assert!(ImageHash::from_bytes(b"not an image").is_err());
+    }
+}
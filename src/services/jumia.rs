@@ -52,6 +52,7 @@ pub async fn fetch_price(
         price: ".prc".to_string(),
         link: "a.core".to_string(),
         image: "img.img".to_string(),
+        list_price: Some(".old".to_string()),
     };
 
     let mut result = search_product(client, zenrows, &search_url, &selectors).await?;
@@ -52,6 +52,7 @@ pub async fn fetch_price(
         price: "span._0a8d6_1nrBS".to_string(),
         link: "a._0a8d6_3pJo1".to_string(),
         image: "img._0a8d6_8jVS9".to_string(),
+        list_price: None,
     };
 
     let mut result = search_product(client, zenrows, &search_url, &selectors).await?;
@@ -0,0 +1,355 @@
+//! Language detection, stop words and stemming for multilingual matching.
+//!
+//! Jumia CI/MA/EG and Amazon.de/.fr list products in French, Arabic or
+//! German. Titles are normalized first (which also translates spec words
+//! such as "noir" or "schwarz" to English, see `normalize`), then reduced
+//! to stemmed keywords with the stop words of the listing's language, so
+//! "Coque noire pour les téléphones" and "black phone case" share keywords.
+
+use serde::{Deserialize, Serialize};
+
+/// Language of a listing title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    En,
+    Fr,
+    De,
+    Ar,
+}
+
+/// Share of letters that must be Arabic script for a title to be Arabic.
+const ARABIC_SCRIPT_SHARE: f64 = 0.3;
+
+const EN_STOP_WORDS: [&str; 37] = [
+    "the",
+    "a",
+    "an",
+    "and",
+    "or",
+    "but",
+    "in",
+    "on",
+    "at",
+    "to",
+    "for",
+    "of",
+    "with",
+    "by",
+    "from",
+    "as",
+    "is",
+    "was",
+    "are",
+    "were",
+    "been",
+    "be",
+    "have",
+    "has",
+    "had",
+    "new",
+    "original",
+    "official",
+    "genuine",
+    "authentic",
+    "brand",
+    "product",
+    "-",
+    "|",
+    "/",
+    ":",
+    ";",
+];
+
+const FR_STOP_WORDS: [&str; 28] = [
+    "le",
+    "la",
+    "les",
+    "un",
+    "une",
+    "des",
+    "du",
+    "de",
+    "d",
+    "l",
+    "et",
+    "ou",
+    "en",
+    "au",
+    "aux",
+    "pour",
+    "avec",
+    "sans",
+    "sur",
+    "par",
+    "dans",
+    "est",
+    "neuf",
+    "neuve",
+    "nouveau",
+    "nouvelle",
+    "original",
+    "originale",
+];
+
+const DE_STOP_WORDS: [&str; 27] = [
+    "der", "die", "das", "den", "dem", "des", "ein", "eine", "einen", "einem", "und", "oder",
+    "für", "fur", "mit", "ohne", "von", "zu", "im", "in", "auf", "bei", "ist", "neu", "neue",
+    "neues", "original",
+];
+
+/// Arabic stop words in normalized form (alef variants folded to a bare alef).
+const AR_STOP_WORDS: [&str; 14] = [
+    "في",
+    "من",
+    "على",
+    "الى",
+    "مع",
+    "عن",
+    "و",
+    "او",
+    "بدون",
+    "هذا",
+    "هذه",
+    "جديد",
+    "جديدة",
+    "اصلي",
+];
+
+impl Language {
+    /// Returns the ISO 639-1 code (e.g., "fr").
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Fr => "fr",
+            Self::De => "de",
+            Self::Ar => "ar",
+        }
+    }
+
+    /// Stop words of the language, lowercase.
+    pub fn stop_words(&self) -> &'static [&'static str] {
+        match self {
+            Self::En => &EN_STOP_WORDS,
+            Self::Fr => &FR_STOP_WORDS,
+            Self::De => &DE_STOP_WORDS,
+            Self::Ar => &AR_STOP_WORDS,
+        }
+    }
+
+    /// Maps a listing URL to the language its storefront lists in.
+    ///
+    /// Jumia's Côte d'Ivoire, Morocco and Senegal storefronts and French
+    /// marketplaces list in French, German and Austrian ones in German and
+    /// Jumia Egypt in Arabic; everything else defaults to English.
+    pub fn from_link(link: &str) -> Self {
+        let host = link
+            .split("://")
+            .nth(1)
+            .unwrap_or(link)
+            .split(['/', '?'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+
+        let french_suffixes = [".fr", ".ci", ".ma", ".sn", ".cm", ".be"];
+        if french_suffixes.iter().any(|suffix| host.ends_with(suffix)) {
+            return Self::Fr;
+        }
+        if host.ends_with(".de") || host.ends_with(".at") {
+            return Self::De;
+        }
+        if host.ends_with(".eg") {
+            return Self::Ar;
+        }
+
+        Self::En
+    }
+
+    /// Detects the language of a listing title.
+    ///
+    /// Arabic script decides on its own; otherwise the language with the most
+    /// stop words in the title wins, and the storefront breaks ties (many
+    /// storefronts mix English product names into local titles).
+    ///
+    /// # Arguments
+    /// * `title` - Listing title, raw or normalized
+    /// * `link` - Listing URL, used for the storefront hint
+    ///
+    /// # Returns
+    /// * Detected language
+    pub fn detect(title: &str, link: &str) -> Self {
+        let letters: Vec<char> = title.chars().filter(|c| c.is_alphabetic()).collect();
+        let arabic = letters.iter().filter(|c| is_arabic(**c)).count();
+        if !letters.is_empty() && arabic as f64 / letters.len() as f64 >= ARABIC_SCRIPT_SHARE {
+            return Self::Ar;
+        }
+
+        let storefront = Self::from_link(link);
+        let lowered = title.to_lowercase();
+        let words: Vec<&str> = lowered
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        let hits = |language: Self| {
+            words
+                .iter()
+                .filter(|w| language.stop_words().contains(w))
+                .count()
+        };
+
+        let mut best = (storefront, hits(storefront));
+        for language in [Self::En, Self::Fr, Self::De] {
+            let count = hits(language);
+            if count > best.1 {
+                best = (language, count);
+            }
+        }
+        best.0
+    }
+
+    /// Reduces a word to a light stem by removing plural and inflection endings.
+    ///
+    /// Deliberately conservative: words containing digits (model numbers,
+    /// capacities) and short words are returned unchanged.
+    pub fn stem(&self, word: &str) -> String {
+        if word.chars().any(|c| c.is_ascii_digit()) || word.chars().count() <= 3 {
+            return word.to_string();
+        }
+
+        match self {
+            Self::En => strip_first(
+                word,
+                &[
+                    ("ies", "y"),
+                    ("sses", "ss"),
+                    ("ches", "ch"),
+                    ("shes", "sh"),
+                    ("xes", "x"),
+                    ("s", ""),
+                ],
+            )
+            .filter(|stem| !word.ends_with("ss") && stem.chars().count() >= 3),
+            Self::Fr => strip_first(
+                word,
+                &[
+                    ("aux", "al"),
+                    ("euses", "eur"),
+                    ("euse", "eur"),
+                    ("es", "e"),
+                    ("s", ""),
+                    ("x", ""),
+                ],
+            )
+            .filter(|stem| stem.chars().count() >= 3),
+            Self::De => strip_first(
+                word,
+                &[
+                    ("ern", ""),
+                    ("en", ""),
+                    ("er", ""),
+                    ("es", ""),
+                    ("e", ""),
+                    ("n", ""),
+                    ("s", ""),
+                ],
+            )
+            .filter(|stem| stem.chars().count() >= 4),
+            Self::Ar => {
+                let without_article = word.strip_prefix("ال").filter(|w| w.chars().count() >= 3);
+                let base = without_article.unwrap_or(word);
+                strip_first(base, &[("ات", ""), ("ون", ""), ("ين", ""), ("ة", "")])
+                    .filter(|stem| stem.chars().count() >= 3)
+                    .or_else(|| without_article.map(str::to_string))
+            }
+        }
+        .unwrap_or_else(|| word.to_string())
+    }
+
+    /// Extracts stemmed keywords from a normalized title.
+    ///
+    /// Stop words of the language and of English are dropped (local titles
+    /// mix in English words), as are single letters other than digits.
+    ///
+    /// # Arguments
+    /// * `title` - Title after `TitleNormalizer::normalize`
+    ///
+    /// # Returns
+    /// * Stemmed keywords in title order
+    pub fn keywords(&self, title: &str) -> Vec<String> {
+        title
+            .split_whitespace()
+            .filter(|word| {
+                !self.stop_words().contains(word)
+                    && !Self::En.stop_words().contains(word)
+                    && (word.chars().count() > 1 || word.chars().any(|c| c.is_ascii_digit()))
+            })
+            .map(|word| self.stem(word))
+            .collect()
+    }
+}
+
+/// Replaces the first matching suffix, keeping the replacement.
+fn strip_first(word: &str, suffixes: &[(&str, &str)]) -> Option<String> {
+    suffixes.iter().find_map(|(suffix, replacement)| {
+        word.strip_suffix(suffix)
+            .map(|stem| format!("{}{}", stem, replacement))
+    })
+}
+
+fn is_arabic(c: char) -> bool {
+    matches!(c, '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' | '\u{FB50}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            Language::detect("Samsung Galaxy A15 avec chargeur pour les étudiants", ""),
+            Language::Fr
+        );
+        assert_eq!(
+            Language::detect(
+                "Samsung Galaxy A15 Hülle für das Handy",
+                "https://www.amazon.de/dp/X"
+            ),
+            Language::De
+        );
+        assert_eq!(
+            Language::detect("سامسونج جالكسي A15 هاتف ذكي", "https://www.jumia.com.eg/x"),
+            Language::Ar
+        );
+        // No stop words: the storefront decides
+        assert_eq!(
+            Language::detect("Samsung Galaxy A15 128GB", "https://www.jumia.ci/x"),
+            Language::Fr
+        );
+        assert_eq!(
+            Language::detect("Samsung Galaxy A15 128GB", ""),
+            Language::En
+        );
+    }
+
+    #[test]
+    fn test_stem() {
+        assert_eq!(Language::En.stem("batteries"), "battery");
+        assert_eq!(Language::En.stem("glass"), "glass");
+        assert_eq!(Language::En.stem("phones"), "phone");
+        assert_eq!(Language::Fr.stem("écouteurs"), "écouteur");
+        assert_eq!(Language::Fr.stem("originaux"), "original");
+        assert_eq!(Language::De.stem("kopfhörer"), "kopfhör");
+        assert_eq!(Language::Ar.stem("الهواتف"), "هواتف");
+        assert_eq!(Language::En.stem("a15s"), "a15s");
+    }
+
+    #[test]
+    fn test_keywords_drop_local_and_english_stop_words() {
+        assert_eq!(
+            Language::Fr.keywords("coque black pour les samsung galaxy a15 with 128 gb"),
+            vec!["coque", "black", "samsung", "galaxy", "a15", "128", "gb"]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Storefronts the scrapers know, with the locale and currency they price in.
+//!
+//! A storefront's currency is a stronger signal than the symbol in its
+//! price text: "$" on amazon.ca is CAD, "¥" on jd.com is CNY.
+
+use super::currency::Currency;
+use std::str::FromStr;
+
+/// A storefront and how it writes prices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marketplace {
+    /// Registered domain (e.g., "amazon.co.uk")
+    pub domain: &'static str,
+    /// BCP 47 tag of the price format (e.g., "en-GB")
+    pub locale: &'static str,
+    /// ISO 4217 code prices are listed in
+    currency: &'static str,
+}
+
+impl Marketplace {
+    /// Returns the currency the storefront lists prices in.
+    pub fn currency(&self) -> Currency {
+        Currency::from_str(self.currency).expect("marketplace currencies are in the ISO table")
+    }
+
+    /// Finds the storefront of a site name, host or URL.
+    ///
+    /// # Arguments
+    /// * `site` - Site name ("Jumia"), host ("www.amazon.de") or URL
+    ///
+    /// # Returns
+    /// * `Some(&Marketplace)` - Known storefront; bare names are the storefront the scraper targets
+    /// * `None` - Unknown site
+    pub fn for_site(site: &str) -> Option<&'static Marketplace> {
+        let site = site.trim().to_lowercase();
+        let host = site
+            .split_once("://")
+            .map_or(site.as_str(), |(_, rest)| rest)
+            .split(['/', '?', ':'])
+            .next()
+            .unwrap_or_default();
+
+        let domain = SITE_NAMES
+            .iter()
+            .find(|(name, _)| *name == host)
+            .map_or(host, |(_, domain)| *domain);
+        MARKETPLACES.iter().find(|marketplace| {
+            domain == marketplace.domain
+                || domain
+                    .strip_suffix(marketplace.domain)
+                    .is_some_and(|sub| sub.ends_with('.'))
+        })
+    }
+}
+
+const fn marketplace(
+    domain: &'static str,
+    locale: &'static str,
+    currency: &'static str,
+) -> Marketplace {
+    Marketplace {
+        domain,
+        locale,
+        currency,
+    }
+}
+
+/// Known storefronts.
+const MARKETPLACES: [Marketplace; 22] = [
+    marketplace("amazon.com", "en-US", "USD"),
+    marketplace("ebay.com", "en-US", "USD"),
+    marketplace("amazon.ca", "en-CA", "CAD"),
+    marketplace("ebay.ca", "en-CA", "CAD"),
+    marketplace("amazon.com.mx", "es-MX", "MXN"),
+    marketplace("amazon.co.uk", "en-GB", "GBP"),
+    marketplace("ebay.co.uk", "en-GB", "GBP"),
+    marketplace("amazon.de", "de-DE", "EUR"),
+    marketplace("ebay.de", "de-DE", "EUR"),
+    marketplace("amazon.fr", "fr-FR", "EUR"),
+    marketplace("amazon.com.au", "en-AU", "AUD"),
+    marketplace("ebay.com.au", "en-AU", "AUD"),
+    marketplace("amazon.in", "en-IN", "INR"),
+    marketplace("amazon.co.jp", "ja-JP", "JPY"),
+    marketplace("amazon.sg", "en-SG", "SGD"),
+    marketplace("jd.com", "zh-CN", "CNY"),
+    marketplace("taobao.com", "zh-CN", "CNY"),
+    marketplace("tmall.com", "zh-CN", "CNY"),
+    marketplace("jumia.com.ng", "en-NG", "NGN"),
+    marketplace("konga.com", "en-NG", "NGN"),
+    marketplace("jumia.co.ke", "sw-KE", "KES"),
+    marketplace("jumia.com.eg", "ar-EG", "EGP"),
+];
+
+/// Site names used in `SitePrice::site`, and the storefront each scraper targets.
+const SITE_NAMES: [(&str, &str); 4] = [
+    ("amazon", "amazon.com"),
+    ("ebay", "ebay.com"),
+    ("jumia", "jumia.com.ng"),
+    ("konga", "konga.com"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marketplace_for_site() {
+        let domain = |site: &str| Marketplace::for_site(site).map(|m| m.domain);
+
+        assert_eq!(
+            domain("https://www.amazon.com.mx/s?k=x"),
+            Some("amazon.com.mx")
+        );
+        assert_eq!(domain("www.amazon.com"), Some("amazon.com"));
+        assert_eq!(domain("Jumia"), Some("jumia.com.ng"));
+        assert_eq!(domain("https://www.konga.com"), Some("konga.com"));
+        // Not a subdomain of amazon.com
+        assert_eq!(domain("notamazon.com"), None);
+        assert_eq!(
+            Marketplace::for_site("amazon.ca").unwrap().currency(),
+            Currency::CAD
+        );
+        for marketplace in &MARKETPLACES {
+            marketplace.currency();
+        }
+    }
+}
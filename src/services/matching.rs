@@ -3,104 +3,524 @@
 //! Implements algorithms to determine if products from different sites
 //! are the same item based on identifiers, model numbers, and titles.
 
+use super::language::Language;
+use super::specs::{self, ProductSpecs, SpecComparison};
+use super::{accessory, gtin};
+use crate::config::MatchingConfig;
 use crate::{ProductIdentifiers, SitePrice};
+use serde::{Deserialize, Serialize};
 use strsim::jaro_winkler;
 
 /// Minimum confidence score to consider a product match valid.
 pub const MIN_MATCH_CONFIDENCE: u8 = 60;
 
-/// Checks if an identifier (UPC/EAN/GTIN) appears as an exact match in text.
+/// Matching rule that set the base confidence of a listing.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchTier {
+    /// UPC/EAN/GTIN found in the listing title
+    Gtin,
+    /// ASIN found in the Amazon listing link
+    Asin,
+    /// eBay item ID found in the eBay listing link
+    EbayItemId,
+    /// Model number, brand and specs match
+    ModelBrandSpecs,
+    /// Model number and brand match
+    ModelBrand,
+    /// Model number or brand matches
+    Partial,
+    /// Fuzzy title similarity
+    FuzzyTitle,
+    /// Confidence assigned by the scraper (ASIN lookup, search result)
+    Preset,
+}
+
+impl MatchTier {
+    /// Returns the serialized name (e.g., "model_brand").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gtin => "gtin",
+            Self::Asin => "asin",
+            Self::EbayItemId => "ebay_item_id",
+            Self::ModelBrandSpecs => "model_brand_specs",
+            Self::ModelBrand => "model_brand",
+            Self::Partial => "partial",
+            Self::FuzzyTitle => "fuzzy_title",
+            Self::Preset => "preset",
+        }
+    }
+
+    /// True for tiers proven by an identifier (GTIN, ASIN, eBay item ID).
+    pub fn is_identifier(&self) -> bool {
+        matches!(self, Self::Gtin | Self::Asin | Self::EbayItemId)
+    }
+}
+
+/// Base confidence assigned by each matching tier.
+///
+/// Defaults are the hand-set scores; `tune_matching` derives tuned values
+/// from user feedback, loaded through `MATCHING_WEIGHTS_PATH`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct TierWeights {
+    pub gtin: u8,
+    pub asin: u8,
+    pub ebay_item_id: u8,
+    pub model_brand_specs: u8,
+    pub model_brand: u8,
+    pub partial: u8,
+    /// Confidence of a perfect fuzzy title score; lower scores scale linearly
+    pub fuzzy_title_max: u8,
+}
+
+impl Default for TierWeights {
+    fn default() -> Self {
+        Self {
+            gtin: 100,
+            asin: 100,
+            ebay_item_id: 100,
+            model_brand_specs: 95,
+            model_brand: 90,
+            partial: 75,
+            fuzzy_title_max: 80,
+        }
+    }
+}
+
+impl TierWeights {
+    /// Returns the weight of a tier, or `None` for presets set by scrapers.
+    pub fn weight_mut(&mut self, tier: MatchTier) -> Option<&mut u8> {
+        match tier {
+            MatchTier::Gtin => Some(&mut self.gtin),
+            MatchTier::Asin => Some(&mut self.asin),
+            MatchTier::EbayItemId => Some(&mut self.ebay_item_id),
+            MatchTier::ModelBrandSpecs => Some(&mut self.model_brand_specs),
+            MatchTier::ModelBrand => Some(&mut self.model_brand),
+            MatchTier::Partial => Some(&mut self.partial),
+            MatchTier::FuzzyTitle => Some(&mut self.fuzzy_title_max),
+            MatchTier::Preset => None,
+        }
+    }
+}
+
+/// Components of the fuzzy title similarity score.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SimilarityBreakdown {
+    /// Detected language of the listing title
+    pub language: Language,
+    /// Jaro-Winkler similarity of brand + model and the listing keywords (0.0-1.0)
+    pub jaro_winkler: f64,
+    /// Share of source keywords found in the listing title (0.0-1.0)
+    pub keyword_overlap: f64,
+    /// Weighted score, 60% Jaro-Winkler + 40% keyword overlap by default (0.0-1.0)
+    pub combined: f64,
+}
+
+/// Weights of the fuzzy title similarity components.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct SimilarityWeights {
+    pub jaro_winkler: f64,
+    pub keyword_overlap: f64,
+}
+
+impl Default for SimilarityWeights {
+    fn default() -> Self {
+        Self {
+            jaro_winkler: 0.6,
+            keyword_overlap: 0.4,
+        }
+    }
+}
+
+/// Perceptual comparison of the listing image with the source image.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageComparison {
+    /// Hamming distance between the image hashes (0-64, 0 is the same picture)
+    pub distance: u32,
+    /// Confidence points added for the same picture
+    pub boost: u8,
+}
+
+/// Confidence deduction applied on top of the tier score.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MatchPenalty {
+    /// Kind of penalty ("spec_mismatch", "accessory", "gtin_mismatch" or "image_mismatch")
+    pub reason: String,
+    /// What triggered it (e.g., "storage: 128GB vs 256GB", "case")
+    pub detail: String,
+    /// Confidence points removed
+    pub points: u8,
+}
+
+/// Machine-readable explanation of how a match confidence was reached.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchExplanation {
+    /// Rule that set the base confidence
+    pub tier: MatchTier,
+    /// Confidence before penalties
+    pub base_confidence: u8,
+    /// Identifiers found on the listing (e.g., "gtin:00012345678905")
+    pub matched_identifiers: Vec<String>,
+    /// Specs equal on both sides (e.g., "storage: 256GB")
+    pub matched_specs: Vec<String>,
+    /// Specs that conflict (e.g., "storage: 128GB vs 256GB")
+    pub mismatched_specs: Vec<String>,
+    /// Title similarity components, for fuzzy title matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<SimilarityBreakdown>,
+    /// Deductions applied to the base confidence
+    pub penalties: Vec<MatchPenalty>,
+    /// Image similarity with the source, when both images were hashed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageComparison>,
+    /// Final confidence (0-100)
+    pub confidence: u8,
+    /// Scored with data from the listing's detail page
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verified: bool,
+}
+
+impl MatchExplanation {
+    /// Creates an explanation for a tier score before any penalties.
+    fn new(tier: MatchTier, base_confidence: u8, specs: &SpecComparison) -> Self {
+        Self {
+            tier,
+            base_confidence,
+            matched_identifiers: Vec::new(),
+            matched_specs: specs.matched.clone(),
+            mismatched_specs: specs.mismatched.clone(),
+            similarity: None,
+            penalties: Vec::new(),
+            image: None,
+            confidence: base_confidence,
+            verified: false,
+        }
+    }
+
+    /// Records an identifier that tied the listing to the source.
+    fn with_identifier(mut self, kind: &str, value: &str) -> Self {
+        self.matched_identifiers.push(format!("{}:{}", kind, value));
+        self
+    }
+
+    /// Lowers the confidence and records why.
+    fn penalize(&mut self, reason: &str, detail: &str, points: u8) {
+        let points = points.min(self.confidence);
+        if points == 0 {
+            return;
+        }
+        self.confidence -= points;
+        self.penalties.push(MatchPenalty {
+            reason: reason.to_string(),
+            detail: detail.to_string(),
+            points,
+        });
+    }
+}
+
+/// Calculates match confidence between a source product and a candidate listing.
+///
+/// Scores the candidate with the identifier/title tiers, then applies
+/// adjustments: each conflicting spec (128GB vs 256GB) costs
+/// `spec_mismatch_penalty`, and accessory listings ("Case for iPhone 15 Pro")
+/// are capped unless the source is an accessory too.
+///
+/// # Arguments
+/// * `source` - Product identifiers from the original page
+/// * `source_title` - Title or search query of the original product
+/// * `candidate` - Product being compared
+/// * `config` - Matching configuration with penalties and word lists
+///
+/// # Returns
+/// * Confidence score from 0-100, where 100 is exact match
+pub fn calculate_match_confidence(
+    source: &ProductIdentifiers,
+    source_title: &str,
+    candidate: &SitePrice,
+    config: &MatchingConfig,
+) -> u8 {
+    explain_match(source, source_title, candidate, config).confidence
+}
+
+/// Scores a candidate listing and explains the result.
 ///
-/// Ensures the identifier is not just part of a longer number or URL.
-/// For example, UPC "12345" should match "UPC: 12345" but not "item/12345678"
+/// Same scoring as `calculate_match_confidence`, keeping the tier, matched
+/// identifiers, spec comparison, similarity components and penalties.
 ///
 /// # Arguments
-/// * `text` - Text to search in (usually product title)
-/// * `identifier` - Identifier to look for
+/// * `source` - Product identifiers from the original page
+/// * `source_title` - Title or search query of the original product
+/// * `candidate` - Product being compared
+/// * `config` - Matching configuration with penalties and word lists
 ///
 /// # Returns
-/// * `true` if identifier appears as standalone value
-fn is_exact_identifier_match(text: &str, identifier: &str) -> bool {
-    if identifier.len() < 8 {
-        // Identifiers should be at least 8 digits (short ones cause false matches)
-        return false;
+/// * Explanation whose `confidence` is the final score
+pub fn explain_match(
+    source: &ProductIdentifiers,
+    source_title: &str,
+    candidate: &SitePrice,
+    config: &MatchingConfig,
+) -> MatchExplanation {
+    let candidate_title = config.normalizer.normalize(&candidate.title);
+    let specs = compare_specs(source, source_title, &candidate_title, config);
+    let explanation = tier_confidence(source, candidate, &candidate_title, &specs, config);
+    apply_adjustments(explanation, source, source_title, candidate, config)
+}
+
+/// Scores a listing with the identifiers and specs its detail page declares.
+///
+/// The declared GTIN, MPN, model, brand and specs are added to the listing
+/// title so every tier can use them, and a declared GTIN that differs from
+/// the source's rules the listing out: different GTINs are different items.
+///
+/// # Arguments
+/// * `source` - Product identifiers from the original page
+/// * `source_title` - Title or search query of the original product
+/// * `candidate` - Listing, with title and price from its detail page
+/// * `declared` - Identifiers and specifications read from the detail page
+/// * `config` - Matching configuration with penalties and word lists
+///
+/// # Returns
+/// * Explanation marked `verified` whose `confidence` is the final score
+pub fn explain_verified(
+    source: &ProductIdentifiers,
+    source_title: &str,
+    candidate: &SitePrice,
+    declared: &ProductIdentifiers,
+    config: &MatchingConfig,
+) -> MatchExplanation {
+    let mut extra: Vec<String> = Vec::new();
+    if let Some(gtin) = declared.gtin14() {
+        extra.push(gtin.to_string());
+    }
+    extra.extend(
+        [&declared.mpn, &declared.model_number, &declared.brand]
+            .into_iter()
+            .flatten()
+            .cloned(),
+    );
+    if let Some(specifications) = &declared.specifications {
+        let mut entries: Vec<_> = specifications.iter().collect();
+        entries.sort();
+        // "<value> <key>" so specs reads the label ("256GB storage")
+        extra.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| format!("{} {}", value, key)),
+        );
     }
 
-    // Split text by non-alphanumeric characters and check for exact match
+    let augmented = SitePrice {
+        title: format!("{} {}", candidate.title, extra.join(" ")),
+        ..candidate.clone()
+    };
+    let mut explanation = explain_match(source, source_title, &augmented, config);
 
-    for word in text.split(|c: char| !c.is_alphanumeric()) {
-        if word == identifier {
-            return true;
+    if let (Some(expected), Some(found)) = (source.gtin14(), declared.gtin14()) {
+        if expected != found {
+            let points = explanation.confidence;
+            explanation.penalize("gtin_mismatch", found.as_str(), points);
         }
     }
+    explanation.verified = true;
+    explanation
+}
+
+/// Applies spec and accessory adjustments to a confidence set by a scraper.
+///
+/// Search results and ASIN lookups arrive with a preset confidence; they
+/// still need the same penalties as listings scored by the tiers.
+///
+/// # Arguments
+/// * `preset` - Confidence assigned when the listing was fetched
+/// * `source` - Product identifiers from the original page
+/// * `source_title` - Title or search query of the original product
+/// * `candidate` - Product being compared
+/// * `config` - Matching configuration with penalties and word lists
+///
+/// # Returns
+/// * Explanation with tier `Preset` whose `confidence` is the adjusted score
+pub fn explain_preset(
+    preset: u8,
+    source: &ProductIdentifiers,
+    source_title: &str,
+    candidate: &SitePrice,
+    config: &MatchingConfig,
+) -> MatchExplanation {
+    let candidate_title = config.normalizer.normalize(&candidate.title);
+    let specs = compare_specs(source, source_title, &candidate_title, config);
+    let explanation = MatchExplanation::new(MatchTier::Preset, preset, &specs);
+    apply_adjustments(explanation, source, source_title, candidate, config)
+}
+
+/// Compares specs of the source (title plus declared map) with the candidate title.
+///
+/// Both titles are read after normalization, so "128 Go" and "128GB" agree.
+fn compare_specs(
+    source: &ProductIdentifiers,
+    source_title: &str,
+    candidate_title: &str,
+    config: &MatchingConfig,
+) -> SpecComparison {
+    let declared = source
+        .specifications
+        .as_ref()
+        .map(ProductSpecs::from_map)
+        .unwrap_or_default();
+    // Declared specs take precedence over values parsed from the title
+    let source_title = config.normalizer.normalize(source_title);
+    let source_specs = declared.or(ProductSpecs::from_title(&source_title));
 
-    false
+    specs::compare(&source_specs, &ProductSpecs::from_title(candidate_title))
 }
 
-/// Calculates match confidence between product identifiers.
+/// Applies spec mismatch penalties, the image adjustment and the accessory cap.
 ///
-/// Uses a tiered approach:
+/// Identifier matches (and presets of 100) are left alone, since the
+/// identifier proves the listing is the source product whatever its title says.
+fn apply_adjustments(
+    mut explanation: MatchExplanation,
+    source: &ProductIdentifiers,
+    source_title: &str,
+    candidate: &SitePrice,
+    config: &MatchingConfig,
+) -> MatchExplanation {
+    if explanation.tier.is_identifier() || explanation.confidence >= 100 {
+        return explanation;
+    }
+
+    if !explanation.mismatched_specs.is_empty() {
+        tracing::debug!(
+            site = %candidate.site,
+            title = %candidate.title,
+            mismatched = ?explanation.mismatched_specs,
+            penalty = config.spec_mismatch_penalty,
+            "Specification mismatch, reducing confidence"
+        );
+    }
+    for mismatch in explanation.mismatched_specs.clone() {
+        explanation.penalize("spec_mismatch", &mismatch, config.spec_mismatch_penalty);
+    }
+
+    if let (Some(expected), Some(found)) = (source.image_hash, candidate.image_hash) {
+        compare_images(&mut explanation, expected.distance(&found), config);
+    }
+
+    cap_accessory_confidence(&mut explanation, source_title, candidate, config);
+    explanation
+}
+
+/// Adjusts confidence by how close the listing image is to the source image.
+///
+/// The same picture (distance up to `similar_distance`) adds `boost`, never
+/// reaching 100, which is reserved for identifier matches; a different
+/// picture (`different_distance` or more) costs `penalty`. Distances in
+/// between are inconclusive, e.g. another shot of the same product.
+///
+/// # Arguments
+/// * `explanation` - Match explanation, adjusted in place
+/// * `distance` - Hamming distance between the source and listing image hashes
+/// * `config` - Matching configuration with the image thresholds
+fn compare_images(explanation: &mut MatchExplanation, distance: u32, config: &MatchingConfig) {
+    let image = &config.image;
+    let mut comparison = ImageComparison { distance, boost: 0 };
+
+    if distance <= image.similar_distance {
+        let boost = image.boost.min(99u8.saturating_sub(explanation.confidence));
+        explanation.confidence += boost;
+        comparison.boost = boost;
+    } else if distance >= image.different_distance {
+        explanation.penalize(
+            "image_mismatch",
+            &format!("distance {}", distance),
+            image.penalty,
+        );
+    }
+    explanation.image = Some(comparison);
+}
+
+/// Caps confidence for accessory listings of a non-accessory source.
+///
+/// # Arguments
+/// * `explanation` - Match explanation, lowered to `accessory_confidence_cap` for accessories
+/// * `source_title` - Title or search query of the original product
+/// * `candidate` - Product being compared
+/// * `config` - Matching configuration with accessory word lists
+fn cap_accessory_confidence(
+    explanation: &mut MatchExplanation,
+    source_title: &str,
+    candidate: &SitePrice,
+    config: &MatchingConfig,
+) {
+    let cap = config.accessory_confidence_cap;
+    let confidence = explanation.confidence;
+    if confidence <= cap {
+        return;
+    }
+    let lexicon = &config.accessory_lexicon;
+    if lexicon.is_accessory_source(source_title) {
+        return;
+    }
+
+    let locale = accessory::locale_for_link(&candidate.link);
+    if let Some(reason) = lexicon.detect(&candidate.title, source_title, locale) {
+        tracing::debug!(
+            site = %candidate.site,
+            title = %candidate.title,
+            locale = locale,
+            reason = %reason,
+            confidence = confidence,
+            capped_to = cap,
+            "Accessory listing detected, capping confidence"
+        );
+        explanation.penalize("accessory", &reason, confidence - cap);
+    }
+}
+
+/// Scores a candidate using identifier and title tiers.
+///
+/// Uses a tiered approach (default weights):
 /// - Exact UPC/EAN/GTIN match: 100% confidence
 /// - ASIN/eBay ID match: 100% confidence
 /// - Model number + brand + specs match: 95% confidence
-/// - Model number + brand match: 90% confidence
+/// - Model number + brand match: 90% confidence (specs unconfirmed)
 /// - Partial match (model or brand): 75% confidence
 /// - Fuzzy title match: 60-80% confidence based on similarity
 ///
 /// # Arguments
 /// * `source` - Product identifiers from the original page
 /// * `candidate` - Product being compared
+/// * `candidate_title` - Normalized title of the candidate
+/// * `specs` - Spec comparison between source and candidate
+/// * `config` - Matching configuration with tier and similarity weights
 ///
 /// # Returns
-/// * Confidence score from 0-100, where 100 is exact match
-pub fn calculate_match_confidence(source: &ProductIdentifiers, candidate: &SitePrice) -> u8 {
+/// * Explanation with the tier and its confidence, before penalties
+fn tier_confidence(
+    source: &ProductIdentifiers,
+    candidate: &SitePrice,
+    candidate_title: &str,
+    specs: &SpecComparison,
+    config: &MatchingConfig,
+) -> MatchExplanation {
+    let weights = &config.tier_weights;
+
     // Exact UPC/EAN/GTIN match (highest confidence)
     // IMPORTANT: Only match in title/description, NOT in URLs (URLs might contain random numbers)
-    if let Some(upc) = &source.upc {
-        // UPC should be in title/description, surrounded by spaces or special chars
-        let title_lower = candidate.title.to_lowercase();
-        let upc_lower = upc.to_lowercase();
-
-        // Check if UPC appears as a separate word (not part of other numbers)
-        if is_exact_identifier_match(&title_lower, &upc_lower) {
+    // All three formats are compared as GTIN-14, so a UPC-A matches its EAN-13 form
+    if let Some(gtin) = source.gtin14() {
+        if gtin::find_in_text(&candidate.title).contains(&gtin) {
             tracing::debug!(
                 site = %candidate.site,
                 title = %candidate.title,
-                upc = %upc,
-                confidence = 100,
-                "Exact UPC match found in title"
-            );
-            return 100;
-        }
-    }
-
-    if let Some(ean) = &source.ean {
-        let title_lower = candidate.title.to_lowercase();
-        let ean_lower = ean.to_lowercase();
-
-        if is_exact_identifier_match(&title_lower, &ean_lower) {
-            tracing::debug!(
-                site = %candidate.site,
-                ean = %ean,
-                confidence = 100,
-                "Exact EAN match found in title"
-            );
-            return 100;
-        }
-    }
-
-    if let Some(gtin) = &source.gtin {
-        let title_lower = candidate.title.to_lowercase();
-        let gtin_lower = gtin.to_lowercase();
-
-        if is_exact_identifier_match(&title_lower, &gtin_lower) {
-            tracing::debug!(
-                site = %candidate.site,
                 gtin = %gtin,
-                confidence = 100,
+                confidence = weights.gtin,
                 "Exact GTIN match found in title"
             );
-            return 100;
+            return MatchExplanation::new(MatchTier::Gtin, weights.gtin, specs)
+                .with_identifier("gtin", gtin.as_str());
         }
     }
 
@@ -110,10 +530,11 @@ pub fn calculate_match_confidence(source: &ProductIdentifiers, candidate: &SiteP
             tracing::debug!(
                 site = %candidate.site,
                 asin = %asin,
-                confidence = 100,
+                confidence = weights.asin,
                 "Exact ASIN match found"
             );
-            return 100;
+            return MatchExplanation::new(MatchTier::Asin, weights.asin, specs)
+                .with_identifier("asin", asin);
         }
     }
 
@@ -123,59 +544,76 @@ pub fn calculate_match_confidence(source: &ProductIdentifiers, candidate: &SiteP
             tracing::debug!(
                 site = %candidate.site,
                 ebay_item_id = %ebay_id,
-                confidence = 100,
+                confidence = weights.ebay_item_id,
                 "Exact eBay item ID match found"
             );
-            return 100;
+            return MatchExplanation::new(MatchTier::EbayItemId, weights.ebay_item_id, specs)
+                .with_identifier("ebay_item_id", ebay_id);
         }
     }
 
     // Model number + brand match
     if let (Some(model), Some(brand)) = (&source.model_number, &source.brand) {
-        let model_match = candidate
-            .title
-            .to_lowercase()
-            .contains(&model.to_lowercase());
-        let brand_match = candidate
-            .title
-            .to_lowercase()
-            .contains(&brand.to_lowercase());
-
-        // Check specification match if available
-        let spec_match = check_specification_match(&source.specifications, &candidate.title);
-
-        if model_match && brand_match && spec_match {
+        // Brand aliases and spelling are normalized on both sides ("Hewlett-Packard" is "hp")
+        let contains = |value: &str| {
+            let value = config.normalizer.normalize(value);
+            !value.is_empty() && candidate_title.contains(&value)
+        };
+        let model_match = contains(model);
+        let brand_match = contains(brand);
+
+        // Specs confirm the variant only if some matched and none conflict
+        let spec_match = specs.confirms_variant();
+
+        let tier = if model_match && brand_match && spec_match {
             tracing::debug!(
                 site = %candidate.site,
                 model = %model,
                 brand = %brand,
-                confidence = 95,
+                confidence = weights.model_brand_specs,
                 "Model + brand + specs match"
             );
-            return 95; // All match
+            Some((MatchTier::ModelBrandSpecs, weights.model_brand_specs)) // All match
         } else if model_match && brand_match {
             tracing::debug!(
                 site = %candidate.site,
                 model = %model,
                 brand = %brand,
-                confidence = 90,
+                confidence = weights.model_brand,
                 "Model + brand match (specs may differ)"
             );
-            return 90; // Model and brand match
+            Some((MatchTier::ModelBrand, weights.model_brand)) // Model and brand match
         } else if model_match || brand_match {
             tracing::debug!(
                 site = %candidate.site,
                 model_match = %model_match,
                 brand_match = %brand_match,
-                confidence = 75,
+                confidence = weights.partial,
                 "Partial match (model or brand only)"
             );
-            return 75; // Partial match
+            Some((MatchTier::Partial, weights.partial)) // Partial match
+        } else {
+            None
+        };
+
+        if let Some((tier, confidence)) = tier {
+            let mut explanation = MatchExplanation::new(tier, confidence, specs);
+            if model_match {
+                explanation = explanation.with_identifier("model_number", model);
+            }
+            if brand_match {
+                explanation = explanation.with_identifier("brand", brand);
+            }
+            return explanation;
         }
     }
 
     // Fallback to fuzzy title similarity
-    let title_confidence = calculate_title_similarity(&source, &candidate);
+    let similarity = calculate_title_similarity(source, candidate, candidate_title, config);
+    // Convert to a 0-80 confidence score (with default weights)
+    let title_confidence = similarity.as_ref().map_or(0, |s| {
+        (s.combined * f64::from(weights.fuzzy_title_max)) as u8
+    });
 
     tracing::debug!(
         site = %candidate.site,
@@ -185,42 +623,56 @@ pub fn calculate_match_confidence(source: &ProductIdentifiers, candidate: &SiteP
         "Fuzzy title similarity match"
     );
 
-    title_confidence
+    let mut explanation = MatchExplanation::new(MatchTier::FuzzyTitle, title_confidence, specs);
+    explanation.similarity = similarity;
+    explanation
 }
 
 /// Calculates title similarity using fuzzy string matching.
 ///
 /// Uses Jaro-Winkler distance and keyword overlap to determine similarity.
+/// Both are computed on stemmed keywords with the stop words of the
+/// listing's language removed, and Jaro-Winkler compares the source with
+/// the best run of listing keywords of the same length, so the words a
+/// French or German title wraps around the product name do not count
+/// against it.
 ///
 /// # Arguments
 /// * `source` - Source product identifiers
-/// * `candidate` - Candidate product to match
+/// * `candidate` - Candidate listing (raw title and link pick the language)
+/// * `candidate_title` - Normalized title of the candidate
+/// * `config` - Matching configuration with the normalizer and similarity weights
 ///
 /// # Returns
-/// * Confidence score from 0-80 based on title similarity
-fn calculate_title_similarity(source: &ProductIdentifiers, candidate: &SitePrice) -> u8 {
+/// * `Some(SimilarityBreakdown)` - Similarity components
+/// * `None` - Source has no brand or model to compare
+fn calculate_title_similarity(
+    source: &ProductIdentifiers,
+    candidate: &SitePrice,
+    candidate_title: &str,
+    config: &MatchingConfig,
+) -> Option<SimilarityBreakdown> {
+    let weights = &config.similarity_weights;
+
     // Extract brand and model from source if available
-    let source_title = format!(
+    let source_title = config.normalizer.normalize(&format!(
         "{} {}",
         source.brand.as_deref().unwrap_or(""),
         source.model_number.as_deref().unwrap_or("")
-    )
-    .trim()
-    .to_lowercase();
-
-    let candidate_title = candidate.title.to_lowercase();
+    ));
 
     // If we have no source title info, we can't match reliably
     if source_title.is_empty() {
-        return 0;
+        return None;
     }
 
-    // Calculate Jaro-Winkler similarity (0.0 to 1.0)
-    let similarity = jaro_winkler(&source_title, &candidate_title);
+    // Brand and model are language-neutral; the listing uses its own stop words
+    let language = Language::detect(&candidate.title, &candidate.link);
+    let source_keywords = Language::En.keywords(&source_title);
+    let candidate_keywords = language.keywords(candidate_title);
 
-    // Extract important keywords from titles
-    let source_keywords = extract_keywords(&source_title);
-    let candidate_keywords = extract_keywords(&candidate_title);
+    // Calculate Jaro-Winkler similarity (0.0 to 1.0)
+    let similarity = best_window_similarity(&source_keywords, &candidate_keywords);
 
     // Calculate keyword overlap
     let matching_keywords = source_keywords
@@ -235,116 +687,30 @@ fn calculate_title_similarity(source: &ProductIdentifiers, candidate: &SitePrice
     };
 
     // Combine fuzzy similarity and keyword overlap
-    // Weight: 60% fuzzy similarity + 40% keyword overlap
-    let combined_score = (similarity * 0.6) + (keyword_overlap * 0.4);
+    // Default weight: 60% fuzzy similarity + 40% keyword overlap
+    let combined_score =
+        (similarity * weights.jaro_winkler) + (keyword_overlap * weights.keyword_overlap);
 
-    // Convert to 0-80 confidence score
-    (combined_score * 80.0) as u8
+    Some(SimilarityBreakdown {
+        language,
+        jaro_winkler: similarity,
+        keyword_overlap,
+        combined: combined_score,
+    })
 }
 
-/// Extracts important keywords from a product title.
-///
-/// Filters out common words and keeps meaningful terms like brand,
-/// model numbers, specifications, etc.
-///
-/// # Arguments
-/// * `title` - Product title to extract keywords from
-///
-/// # Returns
-/// * Vector of important keywords
-fn extract_keywords(title: &str) -> Vec<String> {
-    // Common stop words to filter out
-    let stop_words = vec![
-        "the",
-        "a",
-        "an",
-        "and",
-        "or",
-        "but",
-        "in",
-        "on",
-        "at",
-        "to",
-        "for",
-        "of",
-        "with",
-        "by",
-        "from",
-        "as",
-        "is",
-        "was",
-        "are",
-        "were",
-        "been",
-        "be",
-        "have",
-        "has",
-        "had",
-        "new",
-        "original",
-        "official",
-        "genuine",
-        "authentic",
-        "brand",
-        "product",
-        "-",
-        "|",
-        "/",
-        ":",
-        ";",
-    ];
-
-    title
-        .split_whitespace()
-        .map(|word| {
-            word.trim_matches(|c: char| !c.is_alphanumeric())
-                .to_lowercase()
-        })
-        .filter(|word| !word.is_empty() && word.len() > 2 && !stop_words.contains(&word.as_str()))
-        .collect()
-}
-
-/// Checks if product specifications match between source and candidate.
-///
-/// Compares specifications like color, size, storage capacity.
-///
-/// # Arguments
-/// * `source_specs` - Source product specifications
-/// * `candidate_title` - Candidate product title to check against
-///
-/// # Returns
-/// * `true` if specifications match or no specs to compare
-/// * `false` if specifications conflict
-fn check_specification_match(
-    source_specs: &Option<std::collections::HashMap<String, String>>,
-    candidate_title: &str,
-) -> bool {
-    let Some(specs) = source_specs else {
-        return true; // No specs to compare
-    };
-
-    let candidate_lower = candidate_title.to_lowercase();
-
-    // Check key specifications
-    for (key, value) in specs.iter() {
-        let key_lower = key.to_lowercase();
-        let value_lower = value.to_lowercase();
-
-        // Important specs that must match
-        if key_lower == "color" || key_lower == "size" || key_lower == "storage" {
-            // If the candidate title doesn't contain this specification value,
-            // it might be a different variant
-            if !value_lower.is_empty() && !candidate_lower.contains(&value_lower) {
-                // Check for common variations (e.g., "64gb" vs "64 gb")
-                let normalized_value = value_lower.replace(" ", "");
-                if !candidate_lower.replace(" ", "").contains(&normalized_value) {
-                    return false;
-                }
-            }
-        }
+/// Best Jaro-Winkler similarity between the source keywords and any run of
+/// as many consecutive candidate keywords (the whole title if shorter).
+fn best_window_similarity(source: &[String], candidate: &[String]) -> f64 {
+    let source_text = source.join(" ");
+    if source.is_empty() || candidate.len() <= source.len() {
+        return jaro_winkler(&source_text, &candidate.join(" "));
     }
 
-    true
+    candidate
+        .windows(source.len())
+        .map(|window| jaro_winkler(&source_text, &window.join(" ")))
+        .fold(0.0, f64::max)
 }
 
 /// Filters price results by minimum confidence threshold.
@@ -358,7 +724,7 @@ fn check_specification_match(
 pub fn filter_by_confidence(mut prices: Vec<SitePrice>, min_confidence: u8) -> Vec<SitePrice> {
     prices.retain(|p| {
         p.match_confidence
-            .map_or(false, |conf| conf >= min_confidence)
+            .is_some_and(|conf| conf >= min_confidence)
     });
     prices
 }
@@ -384,6 +750,7 @@ pub fn extract_identifiers_from_url(url: &str) -> ProductIdentifiers {
         model_number: None,
         brand: None,
         specifications: None,
+        image_hash: None,
     };
 
     // Extract Amazon ASIN
@@ -409,7 +776,7 @@ pub fn extract_identifiers_from_url(url: &str) -> ProductIdentifiers {
 /// - /dp/B07FZ8S74R
 /// - /gp/product/B07FZ8S74R
 /// - /product/B07FZ8S74R
-fn extract_asin(url: &str) -> Option<String> {
+pub(crate) fn extract_asin(url: &str) -> Option<String> {
     let patterns = ["/dp/", "/gp/product/", "/product/"];
 
     for pattern in &patterns {
@@ -438,7 +805,7 @@ fn extract_asin(url: &str) -> Option<String> {
 /// Handles formats like:
 /// - /itm/12345678910
 /// - /itm/Product-Name/12345678910
-fn extract_ebay_item_id(url: &str) -> Option<String> {
+pub(crate) fn extract_ebay_item_id(url: &str) -> Option<String> {
     if let Some(itm_idx) = url.find("/itm/") {
         let after_itm = &url[itm_idx + 5..];
 
@@ -460,6 +827,9 @@ fn extract_ebay_item_id(url: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::currency::Currency;
+    use crate::services::image_hash::ImageHash;
+    use crate::services::money::Money;
     use rust_decimal::Decimal;
     use std::str::FromStr;
 
@@ -493,22 +863,33 @@ mod tests {
             model_number: None,
             brand: None,
             specifications: None,
+            image_hash: None,
         };
 
         let candidate = SitePrice {
             site: "Test".to_string(),
             title: "Product with UPC 123456789012".to_string(),
-            price: Decimal::from_str("99.99").unwrap(),
-            currency: "NGN".to_string(),
+            price: Money::new(Decimal::from_str("99.99").unwrap(), Currency::NGN),
             price_usd: Decimal::from_str("999.9").unwrap(),
+            usd_rate: None,
             price_converted: None,
-            target_currency: None,
             link: "https://example.com".to_string(),
             image: None,
             match_confidence: None,
+            match_explanation: None,
+            unit_price: None,
+            image_hash: None,
+            rate_sources: Vec::new(),
+            price_insight: None,
+            list_price: None,
+            discount_check: None,
+            currency_resolution: None,
         };
 
-        assert_eq!(calculate_match_confidence(&source, &candidate), 100);
+        assert_eq!(
+            calculate_match_confidence(&source, "", &candidate, &MatchingConfig::default()),
+            100
+        );
     }
 
     #[test]
@@ -523,21 +904,308 @@ mod tests {
             model_number: Some("XPS-13".to_string()),
             brand: Some("Dell".to_string()),
             specifications: None,
+            image_hash: None,
         };
 
         let candidate = SitePrice {
             site: "Test".to_string(),
             title: "Dell XPS-13 Laptop".to_string(),
-            price: Decimal::from_str("999.99").unwrap(),
-            currency: "NGN".to_string(),
+            price: Money::new(Decimal::from_str("999.99").unwrap(), Currency::NGN),
             price_usd: Decimal::from_str("999.9").unwrap(),
+            usd_rate: None,
+            price_converted: None,
+            link: "https://example.com".to_string(),
+            image: None,
+            match_confidence: None,
+            match_explanation: None,
+            unit_price: None,
+            image_hash: None,
+            rate_sources: Vec::new(),
+            price_insight: None,
+            list_price: None,
+            discount_check: None,
+            currency_resolution: None,
+        };
+
+        assert_eq!(
+            calculate_match_confidence(
+                &source,
+                "Dell XPS-13",
+                &candidate,
+                &MatchingConfig::default()
+            ),
+            90
+        );
+    }
+
+    #[test]
+    fn test_normalized_titles_match_brand_alias_and_units() {
+        let source = ProductIdentifiers {
+            model_number: Some("15s-fq5000".to_string()),
+            brand: Some("Hewlett-Packard".to_string()),
+            ..Default::default()
+        };
+        let candidate = SitePrice::new(
+            "Jumia".to_string(),
+            "PROMO HP 15S-FQ5000 15,6 pouces 8 Go 512 Go".to_string(),
+            Money::new(Decimal::ZERO, Currency::from_str("XOF").unwrap()),
+            Decimal::ZERO,
+            "https://www.jumia.ci/hp-15s".to_string(),
+            None,
+            None,
+        );
+
+        let explanation = explain_match(
+            &source,
+            "Hewlett-Packard 15s-fq5000 15.6\" 512GB",
+            &candidate,
+            &MatchingConfig::default(),
+        );
+        assert_eq!(explanation.tier, MatchTier::ModelBrandSpecs);
+        assert!(explanation.mismatched_specs.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_match_confidence_upc_matches_ean13_form() {
+        let source = ProductIdentifiers {
+            upc: Some("012345678905".to_string()),
+            ..Default::default()
+        };
+
+        let candidate = SitePrice {
+            site: "Test".to_string(),
+            title: "Widget EAN 0012345678905".to_string(),
+            price: Money::new(Decimal::from_str("19.99").unwrap(), Currency::EUR),
+            price_usd: Decimal::from_str("21.59").unwrap(),
+            usd_rate: None,
             price_converted: None,
-            target_currency: None,
             link: "https://example.com".to_string(),
             image: None,
             match_confidence: None,
+            match_explanation: None,
+            unit_price: None,
+            image_hash: None,
+            rate_sources: Vec::new(),
+            price_insight: None,
+            list_price: None,
+            discount_check: None,
+            currency_resolution: None,
+        };
+
+        assert_eq!(
+            calculate_match_confidence(&source, "", &candidate, &MatchingConfig::default()),
+            100
+        );
+    }
+
+    #[test]
+    fn test_accessory_candidate_is_capped() {
+        let source = ProductIdentifiers {
+            model_number: Some("iPhone 15 Pro".to_string()),
+            brand: Some("Apple".to_string()),
+            ..Default::default()
+        };
+        let config = MatchingConfig::default();
+
+        let candidate = SitePrice {
+            site: "Jumia".to_string(),
+            title: "Apple iPhone 15 Pro Silicone Case".to_string(),
+            price: Money::new(Decimal::from_str("15000").unwrap(), Currency::NGN),
+            price_usd: Decimal::from_str("10").unwrap(),
+            usd_rate: None,
+            price_converted: None,
+            link: "https://www.jumia.com.ng/case".to_string(),
+            image: None,
+            match_confidence: None,
+            match_explanation: None,
+            unit_price: None,
+            image_hash: None,
+            rate_sources: Vec::new(),
+            price_insight: None,
+            list_price: None,
+            discount_check: None,
+            currency_resolution: None,
+        };
+
+        assert_eq!(
+            calculate_match_confidence(&source, "Apple iPhone 15 Pro 256GB", &candidate, &config),
+            config.accessory_confidence_cap
+        );
+
+        // An accessory source keeps the full tier confidence
+        assert_eq!(
+            calculate_match_confidence(&source, "Apple iPhone 15 Pro Case", &candidate, &config),
+            90
+        );
+    }
+
+    #[test]
+    fn test_spec_match_and_mismatch() {
+        let source = ProductIdentifiers {
+            model_number: Some("Galaxy A54".to_string()),
+            brand: Some("Samsung".to_string()),
+            ..Default::default()
         };
+        let config = MatchingConfig::default();
+        let listing = |title: &str| SitePrice {
+            site: "Jumia".to_string(),
+            title: title.to_string(),
+            price: Money::new(Decimal::from_str("350000").unwrap(), Currency::NGN),
+            price_usd: Decimal::from_str("230").unwrap(),
+            usd_rate: None,
+            price_converted: None,
+            link: "https://www.jumia.com.ng/a54".to_string(),
+            image: None,
+            match_confidence: None,
+            match_explanation: None,
+            unit_price: None,
+            image_hash: None,
+            rate_sources: Vec::new(),
+            price_insight: None,
+            list_price: None,
+            discount_check: None,
+            currency_resolution: None,
+        };
+        let source_title = "Samsung Galaxy A54 5G 8GB RAM 256GB";
+
+        let same = listing("Samsung Galaxy A54 5G (256GB ROM, 8GB RAM) Black");
+        assert_eq!(
+            calculate_match_confidence(&source, source_title, &same, &config),
+            95
+        );
+
+        let other = listing("Samsung Galaxy A54 5G 8GB RAM 128GB Black");
+        assert_eq!(
+            calculate_match_confidence(&source, source_title, &other, &config),
+            90 - config.spec_mismatch_penalty
+        );
+    }
+
+    #[test]
+    fn test_explain_match_records_tier_and_penalties() {
+        let source = ProductIdentifiers {
+            upc: Some("012345678905".to_string()),
+            model_number: Some("Galaxy A54".to_string()),
+            brand: Some("Samsung".to_string()),
+            ..Default::default()
+        };
+        let config = MatchingConfig::default();
+        let listing = |title: &str| SitePrice {
+            site: "Jumia".to_string(),
+            title: title.to_string(),
+            price: Money::new(Decimal::from_str("350000").unwrap(), Currency::NGN),
+            price_usd: Decimal::from_str("230").unwrap(),
+            usd_rate: None,
+            price_converted: None,
+            link: "https://www.jumia.com.ng/a54".to_string(),
+            image: None,
+            match_confidence: None,
+            match_explanation: None,
+            unit_price: None,
+            image_hash: None,
+            rate_sources: Vec::new(),
+            price_insight: None,
+            list_price: None,
+            discount_check: None,
+            currency_resolution: None,
+        };
+        let source_title = "Samsung Galaxy A54 8GB RAM 256GB";
+
+        let by_upc = explain_match(
+            &source,
+            source_title,
+            &listing("Samsung Galaxy A54 128GB 012345678905"),
+            &config,
+        );
+        assert_eq!(by_upc.tier, MatchTier::Gtin);
+        assert_eq!(by_upc.matched_identifiers, vec!["gtin:00012345678905"]);
+        assert_eq!(by_upc.confidence, 100);
+        assert!(by_upc.penalties.is_empty());
+
+        let other = explain_match(
+            &source,
+            source_title,
+            &listing("Samsung Galaxy A54 8GB RAM 128GB"),
+            &config,
+        );
+        assert_eq!(other.tier, MatchTier::ModelBrand);
+        assert_eq!(other.base_confidence, 90);
+        assert_eq!(other.matched_specs, vec!["ram: 8GB"]);
+        assert_eq!(other.penalties.len(), 1);
+        assert_eq!(other.penalties[0].reason, "spec_mismatch");
+        assert_eq!(other.confidence, 90 - config.spec_mismatch_penalty);
+
+        let fuzzy = explain_match(
+            &ProductIdentifiers {
+                brand: Some("Samsung".to_string()),
+                ..Default::default()
+            },
+            source_title,
+            &listing("Galaxy phone"),
+            &config,
+        );
+        assert_eq!(fuzzy.tier, MatchTier::FuzzyTitle);
+        assert!(fuzzy.similarity.is_some());
+    }
+
+    #[test]
+    fn test_image_similarity_boosts_and_penalizes() {
+        let source = ProductIdentifiers {
+            model_number: Some("Galaxy A54".to_string()),
+            brand: Some("Samsung".to_string()),
+            image_hash: Some(ImageHash(0xf0f0_f0f0_0f0f_0f0f)),
+            ..Default::default()
+        };
+        let config = MatchingConfig::default();
+        let listing = |image_hash: Option<ImageHash>| SitePrice {
+            site: "Jumia".to_string(),
+            title: "Samsung Galaxy A54 Black".to_string(),
+            price: Money::new(Decimal::from_str("350000").unwrap(), Currency::NGN),
+            price_usd: Decimal::from_str("230").unwrap(),
+            usd_rate: None,
+            price_converted: None,
+            link: "https://www.jumia.com.ng/a54".to_string(),
+            image: None,
+            match_confidence: None,
+            match_explanation: None,
+            unit_price: None,
+            image_hash,
+            rate_sources: Vec::new(),
+            price_insight: None,
+            list_price: None,
+            discount_check: None,
+            currency_resolution: None,
+        };
+        let source_title = "Samsung Galaxy A54";
+
+        let unhashed = explain_match(&source, source_title, &listing(None), &config);
+        assert_eq!(unhashed.confidence, 90);
+        assert!(unhashed.image.is_none());
+
+        // Three bits apart: the same product shot, boosted but kept below 100
+        let same = explain_match(
+            &source,
+            source_title,
+            &listing(Some(ImageHash(0xf0f0_f0f0_0f0f_0f08))),
+            &config,
+        );
+        assert_eq!(
+            same.image,
+            Some(ImageComparison {
+                distance: 3,
+                boost: 9
+            })
+        );
+        assert_eq!(same.confidence, 99);
 
-        assert_eq!(calculate_match_confidence(&source, &candidate), 90);
+        let different = explain_match(
+            &source,
+            source_title,
+            &listing(Some(ImageHash(0x0f0f_0f0f_f0f0_f0f0))),
+            &config,
+        );
+        assert_eq!(different.image.map(|i| i.distance), Some(64));
+        assert_eq!(different.penalties[0].reason, "image_mismatch");
+        assert_eq!(different.confidence, 90 - config.image.penalty);
     }
 }
@@ -2,6 +2,7 @@
 //!
 //! Generates realistic product data when official APIs are not configured.
 
+use super::money::Money;
 use crate::{AppError, SitePrice};
 use rand::Rng;
 use rust_decimal::Decimal;
@@ -38,11 +39,10 @@ pub fn generate_mock_price(item: &str, site: &str) -> Result<SitePrice, AppError
     Ok(SitePrice {
         site: site.to_string(),
         title: generate_product_title(item, site),
-        price: price_decimal,
-        currency: "USD".to_string(), // Mock data defaults to USD
-        price_usd: price_decimal,    // Same as price for mock USD data
+        price: Money::usd(price_decimal), // Mock data defaults to USD
+        price_usd: price_decimal,         // Same as price for mock USD data
+        usd_rate: None,
         price_converted: None,
-        target_currency: None,
         link: format!(
             "https://www.{}.com/product/{}",
             site.to_lowercase(),
@@ -54,6 +54,14 @@ pub fn generate_mock_price(item: &str, site: &str) -> Result<SitePrice, AppError
             hash_string(item)
         )),
         match_confidence: Some(100), // Mock data is always 100% "match"
+        match_explanation: None,
+        unit_price: None,
+        image_hash: None,
+        rate_sources: Vec::new(),
+        price_insight: None,
+        list_price: None,
+        discount_check: None,
+        currency_resolution: None,
     })
 }
 
@@ -128,7 +136,7 @@ mod tests {
         let result = generate_mock_price("laptop", "Amazon");
         if let Ok(price) = result {
             assert_eq!(price.site, "Amazon");
-            assert!(price.price > Decimal::ZERO);
+            assert!(price.price.amount() > Decimal::ZERO);
             assert!(!price.title.is_empty());
             assert!(price.link.starts_with("https://"));
             assert!(price.image.is_some());
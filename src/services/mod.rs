@@ -3,17 +3,61 @@
 //! This module coordinates fetching prices from multiple e-commerce platforms
 //! concurrently and aggregates results with product matching.
 
+pub mod accessory;
 pub mod amazon;
+pub mod catalog;
 pub mod currency;
+pub mod detail;
+pub mod discounts;
 pub mod ebay;
+pub mod evaluation;
+pub mod feedback;
+pub mod gtin;
+pub mod history_writer;
+pub mod image_hash;
 pub mod jumia;
 pub mod konga;
+pub mod language;
+pub mod marketplace;
 pub mod matching;
 pub mod mock;
+pub mod money;
+pub mod normalize;
+pub mod price_history;
+pub mod price_insights;
+pub mod price_parser;
+pub mod rate_history;
+pub mod rate_providers;
+pub mod rate_refresher;
+pub mod specs;
+pub mod units;
 pub mod zenrows;
 
+use crate::models::RankBy;
 use crate::{AppError, AppState, PriceComparisonResult, ProductIdentifiers, SitePrice};
+use history_writer::{HistoryEntry, RequestContext};
+use matching::MatchExplanation;
 use std::sync::Arc;
+use uuid::Uuid;
+
+/// Per-request options for a price comparison.
+#[derive(Debug, Clone, Default)]
+pub struct CompareOptions {
+    /// Target currency for price conversion (e.g., "GBP")
+    pub target_currency: Option<String>,
+    /// Attach a `MatchExplanation` to each listing
+    pub explain: bool,
+    /// Most detail pages to fetch for borderline matches (capped by configuration)
+    pub detail_fetch_budget: Option<usize>,
+    /// Ranking order (`None` picks unit price for mixed pack sizes)
+    pub rank_by: Option<RankBy>,
+    /// Image of the source product, hashed to compare with listing images
+    pub source_image: Option<String>,
+    /// Site the user is browsing, recorded with the price history
+    pub current_site: Option<String>,
+    /// Product the request already resolved to (see `catalog::find_product`)
+    pub product_id: Option<Uuid>,
+}
 
 /// Compares prices across all supported platforms with product identifiers.
 ///
@@ -25,7 +69,7 @@ use std::sync::Arc;
 /// * `identifiers` - Product identifiers for matching
 /// * `search_query` - Search query text
 /// * `state` - Application state with configuration and HTTP client
-/// * `target_currency` - Optional target currency for price conversion
+/// * `options` - Target currency and match explanation options
 ///
 /// # Returns
 /// * `Ok(PriceComparisonResult)` - Comparison results with confidence scores
@@ -34,36 +78,39 @@ pub async fn compare_with_identifiers(
     identifiers: &ProductIdentifiers,
     search_query: &str,
     state: &Arc<AppState>,
-    target_currency: Option<&str>,
+    options: &CompareOptions,
 ) -> Result<PriceComparisonResult, AppError> {
     tracing::info!(
         query = %search_query,
-        has_upc = identifiers.upc.is_some(),
+        gtin = ?identifiers.gtin14(),
         has_asin = identifiers.asin.is_some(),
         has_model = identifiers.model_number.is_some(),
-        target_currency = ?target_currency,
+        target_currency = ?options.target_currency,
+        explain = options.explain,
         "Starting product comparison with identifiers"
     );
 
-    let mut all_prices: Vec<SitePrice> = Vec::new();
+    let mut scored: Vec<(SitePrice, MatchExplanation)> = Vec::new();
 
     // Use mock data if configured
     if state.config.scraper.use_mock_data {
         tracing::info!("Using mock data for demonstration");
 
-        if let Ok(price) = mock::generate_mock_price(search_query, "Amazon") {
-            all_prices.push(price);
-        }
-        if let Ok(price) = mock::generate_mock_price(search_query, "eBay") {
-            all_prices.push(price);
-        }
-        if let Ok(price) = mock::generate_mock_price(search_query, "Jumia") {
-            all_prices.push(price);
-        }
-        if let Ok(price) = mock::generate_mock_price(search_query, "Konga") {
-            all_prices.push(price);
+        for site in ["Amazon", "eBay", "Jumia", "Konga"] {
+            if let Ok(price) = mock::generate_mock_price(search_query, site) {
+                scored.push(score_candidate(price, identifiers, search_query, state));
+            }
         }
     } else {
+        let identifiers = &image_hash::hash_source(
+            identifiers,
+            options.source_image.as_deref(),
+            &state.http_client,
+            &state.redis_pool,
+            &state.config.scraper,
+        )
+        .await;
+
         // Use ZenRows for scraping
         let zenrows_config = state
             .config
@@ -105,53 +152,84 @@ pub async fn compare_with_identifiers(
         );
 
         // Collect successful results
-        if let Ok(mut price) = amazon_result {
-            // Calculate match confidence
-            if price.match_confidence.is_none() {
-                price.match_confidence =
-                    Some(matching::calculate_match_confidence(identifiers, &price));
+        let mut fetched: Vec<SitePrice> = Vec::new();
+        for (site, result) in [
+            ("Amazon", amazon_result),
+            ("eBay", ebay_result),
+            ("Jumia", jumia_result),
+            ("Konga", konga_result),
+        ] {
+            match result {
+                Ok(price) => fetched.push(price),
+                Err(e) => tracing::debug!(site = site, error = %e, "Fetch failed"),
             }
-            all_prices.push(price);
-        } else if let Err(e) = amazon_result {
-            tracing::debug!(error = %e, "Amazon fetch failed");
         }
 
-        if let Ok(mut price) = ebay_result {
-            if price.match_confidence.is_none() {
-                price.match_confidence =
-                    Some(matching::calculate_match_confidence(identifiers, &price));
-            }
-            all_prices.push(price);
-        } else if let Err(e) = ebay_result {
-            tracing::debug!(error = %e, "eBay fetch failed");
+        // Listing images are only worth hashing when the source image is known
+        if identifiers.image_hash.is_some() {
+            image_hash::hash_listings(
+                &mut fetched,
+                &state.http_client,
+                &state.redis_pool,
+                &state.config.scraper,
+            )
+            .await;
         }
+        scored.extend(
+            fetched
+                .into_iter()
+                .map(|price| score_candidate(price, identifiers, search_query, state)),
+        );
 
-        if let Ok(mut price) = jumia_result {
-            if price.match_confidence.is_none() {
-                price.match_confidence =
-                    Some(matching::calculate_match_confidence(identifiers, &price));
-            }
-            all_prices.push(price);
-        } else if let Err(e) = jumia_result {
-            tracing::debug!(error = %e, "Jumia fetch failed");
+        // Borderline search matches get a second look at their detail pages
+        let verification = &state.config.scraper.detail_verification;
+        let budget = options
+            .detail_fetch_budget
+            .map_or(verification.max_fetches, |budget| {
+                budget.min(verification.max_fetches)
+            });
+        if let (Some(zenrows_config), true) = (zenrows_config.as_ref(), budget > 0) {
+            scored = detail::verify_borderline(
+                scored,
+                identifiers,
+                search_query,
+                &state.http_client,
+                zenrows_config,
+                verification,
+                budget,
+                state.config.scraper.product_match_min_confidence,
+                &state.config.matching,
+            )
+            .await;
         }
+    }
 
-        if let Ok(mut price) = konga_result {
-            if price.match_confidence.is_none() {
-                price.match_confidence =
-                    Some(matching::calculate_match_confidence(identifiers, &price));
+    let mut all_prices: Vec<SitePrice> = scored
+        .into_iter()
+        .map(|(mut price, explanation)| {
+            price.match_confidence = Some(explanation.confidence);
+            if options.explain {
+                price.match_explanation = Some(explanation);
             }
-            all_prices.push(price);
-        } else if let Err(e) = konga_result {
-            tracing::debug!(error = %e, "Konga fetch failed");
-        }
-    }
+            price
+        })
+        .collect();
+
+    // Drop listings users have confirmed are a different product
+    let source_key = feedback::source_key(identifiers, search_query);
+    all_prices = feedback::suppress_rejected(all_prices, &source_key, &state.db_pool).await;
 
     // Filter by minimum confidence threshold
     let min_confidence = state.config.scraper.product_match_min_confidence;
     all_prices = matching::filter_by_confidence(all_prices, min_confidence);
 
-    // Return error if all scrapers failed or no matches above threshold
+    // Every listing in USD before anything compares prices across sites. This one
+    // snapshot also converts the listings and dates the rate metadata, since the
+    // refresher can replace the cached rates while the comparison runs.
+    let rates = state.currency_service.get_exchange_rates().await?;
+    all_prices = currency::normalize_to_usd(all_prices, &rates);
+
+    // Return error if all scrapers failed or no comparable matches above threshold
     if all_prices.is_empty() {
         tracing::error!(query = %search_query, "No products found above confidence threshold");
         return Err(AppError::Internal(format!(
@@ -162,34 +240,114 @@ pub async fn compare_with_identifiers(
         )));
     }
 
-    // Convert prices to target currency if specified
-    if let Some(target_curr) = target_currency {
-        all_prices = convert_prices_to_currency(all_prices, target_curr, state).await?;
+    // Convert prices to target currency if specified, with the rates used for USD
+    if let Some(target_curr) = options.target_currency.as_deref() {
+        all_prices = convert_prices_to_currency(all_prices, target_curr, &rates)?;
     }
 
     // Validate and filter out price outliers
     all_prices = validate_and_filter_prices(all_prices);
 
-    // Sort by price ascending (lowest price first)
-    all_prices.sort_by(|a, b| {
-        a.price_usd
-            .partial_cmp(&b.price_usd)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    // Price per item / 100 ml / kg, after conversion so converted unit prices are set
+    units::annotate_unit_prices(&mut all_prices);
+
+    // Historical low, percentiles and buy / wait advice from each listing's history
+    price_insights::annotate(&mut all_prices, &state.db_pool, &state.config.insights).await;
 
-    let best_deal = all_prices.first().cloned();
+    // Flag "was" prices the listing never actually sold at
+    discounts::annotate(&mut all_prices, &state.db_pool, &state.config.discounts).await;
+
+    let mut result = PriceComparisonResult::new(all_prices);
+    result.rank(options.rank_by);
+    result.rate_metadata = Some(
+        state
+            .currency_service
+            .rate_metadata(&result.rate_sources(), rates.updated_at),
+    );
 
     tracing::info!(
         query = %search_query,
-        total_results = all_prices.len(),
-        best_price = ?best_deal.as_ref().map(|p| p.price),
+        total_results = result.all_prices.len(),
+        best_price = ?result.best_deal.as_ref().map(|p| p.price),
+        ranked_by = ?result.ranked_by,
         "Price comparison completed"
     );
 
-    Ok(PriceComparisonResult {
-        best_deal,
-        all_prices,
-    })
+    // Catalog and price history are written in the background; the request never waits on them
+    result.product_id = options.product_id;
+    let context = RequestContext {
+        current_site: options.current_site.clone(),
+        target_currency: options.target_currency.clone(),
+        ranked_by: result.ranked_by,
+    };
+    tokio::spawn(record_comparison(
+        state.clone(),
+        identifiers.clone(),
+        search_query.to_string(),
+        result.all_prices.clone(),
+        context,
+    ));
+
+    Ok(result)
+}
+
+/// Clusters a comparison's listings onto a canonical product, then queues
+/// them for price history.
+///
+/// The catalog never blocks a comparison: a failure is logged and the
+/// history is recorded without a product.
+async fn record_comparison(
+    state: Arc<AppState>,
+    identifiers: ProductIdentifiers,
+    search_query: String,
+    prices: Vec<SitePrice>,
+    context: RequestContext,
+) {
+    let product_id = match catalog::record_comparison(
+        &state.db_pool,
+        &state.redis_pool,
+        &identifiers,
+        &search_query,
+        &prices,
+        state.config.cache.ttl_seconds,
+    )
+    .await
+    {
+        Ok(product_id) => Some(product_id),
+        Err(e) => {
+            tracing::warn!(query = %search_query, error = %e, "Failed to record product");
+            None
+        }
+    };
+
+    state.history_writer.record(HistoryEntry::for_comparison(
+        &prices,
+        &identifiers,
+        &search_query,
+        product_id,
+        &context,
+    ));
+}
+
+/// Scores a scraped listing.
+///
+/// Listings without a confidence are scored by `matching`; listings that
+/// arrive with one (ASIN lookups, search results) still go through the spec
+/// and accessory checks so a "128GB" or "Case for ..." result cannot ride on
+/// a preset score. The caller applies the confidence once verification is done.
+fn score_candidate(
+    price: SitePrice,
+    identifiers: &ProductIdentifiers,
+    search_query: &str,
+    state: &Arc<AppState>,
+) -> (SitePrice, MatchExplanation) {
+    let config = &state.config.matching;
+    let explanation = match price.match_confidence {
+        Some(preset) => matching::explain_preset(preset, identifiers, search_query, &price, config),
+        None => matching::explain_match(identifiers, search_query, &price, config),
+    };
+
+    (price, explanation)
 }
 
 /// Converts all prices in the result to a target currency.
@@ -197,15 +355,15 @@ pub async fn compare_with_identifiers(
 /// # Arguments
 /// * `prices` - Vector of site prices to convert
 /// * `target_currency` - Target currency code (e.g., "GBP", "EUR")
-/// * `state` - Application state with currency service
+/// * `rates` - Rate snapshot the prices were normalized to USD with
 ///
 /// # Returns
 /// * `Ok(Vec<SitePrice>)` - Prices with conversion applied
-/// * `Err(AppError)` - If currency conversion fails
-async fn convert_prices_to_currency(
+/// * `Err(AppError)` - If the target currency is unknown
+fn convert_prices_to_currency(
     mut prices: Vec<SitePrice>,
     target_currency: &str,
-    state: &Arc<AppState>,
+    rates: &currency::ExchangeRates,
 ) -> Result<Vec<SitePrice>, AppError> {
     use currency::Currency;
     use std::str::FromStr;
@@ -219,21 +377,22 @@ async fn convert_prices_to_currency(
     );
 
     for price in &mut prices {
-        // Parse the original currency
-        let source_curr = Currency::from_str(&price.currency).unwrap_or(Currency::USD);
-
-        // Convert from source currency to target currency
-        let converted = state
-            .currency_service
-            .convert(price.price, &source_curr, &target_curr)
-            .await
-            .unwrap_or(price.price); // Fallback to original if conversion fails
-
-        // Round to 2 decimal places for display
-        let rounded = converted.round_dp(2);
-
-        price.price_converted = Some(rounded);
-        price.target_currency = Some(target_currency.to_string());
+        // Missing rates leave the listing unconverted rather than
+        // reinterpreting its price in another currency
+        match rates.convert(price.price, &target_curr) {
+            Ok(conversion) => {
+                // Rounded to the target currency's minor units by the conversion
+                price.price_converted = Some(conversion.amount);
+                price.rate_sources = conversion.sources;
+            }
+            Err(e) => tracing::warn!(
+                site = %price.site,
+                currency = %price.price.currency(),
+                target_currency = %target_curr,
+                error = %e,
+                "Price not converted"
+            ),
+        }
     }
 
     Ok(prices)
@@ -258,7 +417,7 @@ fn validate_and_filter_prices(mut prices: Vec<SitePrice>) -> Vec<SitePrice> {
     let mut price_values: Vec<rust_decimal::Decimal> = prices.iter().map(|p| p.price_usd).collect();
     price_values.sort();
 
-    let median = if price_values.len() % 2 == 0 {
+    let median = if price_values.len().is_multiple_of(2) {
         let mid = price_values.len() / 2;
         (price_values[mid - 1] + price_values[mid]) / rust_decimal::Decimal::from(2)
     } else {
@@ -270,7 +429,7 @@ fn validate_and_filter_prices(mut prices: Vec<SitePrice>) -> Vec<SitePrice> {
         price_values.iter().map(|&p| (p - median).abs()).collect();
     deviations.sort();
 
-    let mad = if deviations.len() % 2 == 0 {
+    let mad = if deviations.len().is_multiple_of(2) {
         let mid = deviations.len() / 2;
         (deviations[mid - 1] + deviations[mid]) / rust_decimal::Decimal::from(2)
     } else {
@@ -322,6 +481,7 @@ fn validate_and_filter_prices(mut prices: Vec<SitePrice>) -> Vec<SitePrice> {
 /// # Arguments
 /// * `item` - Search query for the product
 /// * `state` - Application state with configuration and HTTP client
+/// * `options` - Target currency and match explanation options
 ///
 /// # Returns
 /// * `Ok(PriceComparisonResult)` - Comparison results with best deal and all prices
@@ -329,6 +489,7 @@ fn validate_and_filter_prices(mut prices: Vec<SitePrice>) -> Vec<SitePrice> {
 pub async fn compare_all(
     item: &str,
     state: &Arc<AppState>,
+    options: &CompareOptions,
 ) -> Result<PriceComparisonResult, AppError> {
     // Create basic identifiers from search query
     let identifiers = ProductIdentifiers {
@@ -341,7 +502,74 @@ pub async fn compare_all(
         model_number: None,
         brand: None,
         specifications: None,
+        image_hash: None,
     };
 
-    compare_with_identifiers(&identifiers, item, state, None).await
+    compare_with_identifiers(&identifiers, item, state, options).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::currency::{Currency, ExchangeRates};
+    use crate::services::money::Money;
+    use crate::services::rate_providers::RateSource;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_rates_come_from_one_snapshot() {
+        let ecb = RateSource {
+            provider: "ecb".to_string(),
+            published_at: chrono::Utc::now(),
+        };
+        let rates = ExchangeRates {
+            base: "USD".to_string(),
+            rates: HashMap::from([
+                ("USD".to_string(), Decimal::ONE),
+                ("NGN".to_string(), Decimal::from(1600)),
+                ("GBP".to_string(), Decimal::from_str("0.8").unwrap()),
+            ]),
+            updated_at: ecb.published_at,
+            sources: HashMap::from([
+                ("USD".to_string(), ecb.clone()),
+                ("NGN".to_string(), ecb.clone()),
+                ("GBP".to_string(), ecb.clone()),
+            ]),
+            outliers: Vec::new(),
+        };
+        let listing = |site: &str, price: i64, currency: Currency| {
+            SitePrice::new(
+                site.to_string(),
+                "Phone".to_string(),
+                Money::new(Decimal::from(price), currency),
+                Decimal::from(price),
+                format!("https://{}.example/phone", site),
+                None,
+                None,
+            )
+        };
+
+        let prices = currency::normalize_to_usd(
+            vec![
+                listing("jumia", 480_000, Currency::NGN),
+                listing("amazon", 320, Currency::USD),
+            ],
+            &rates,
+        );
+        let prices = convert_prices_to_currency(prices, "GBP", &rates).unwrap();
+
+        // USD and GBP amounts agree with each other through the snapshot's GBP rate
+        for price in &prices {
+            let converted = price.price_converted.unwrap();
+            assert_eq!(converted.currency(), Currency::GBP);
+            assert_eq!(
+                converted.amount(),
+                (price.price_usd * Decimal::from_str("0.8").unwrap()).round_dp(2)
+            );
+        }
+        let result = PriceComparisonResult::new(prices);
+        assert_eq!(result.rate_sources(), vec![ecb]);
+    }
 }
@@ -0,0 +1,193 @@
+//! Amounts of money tied to their currency.
+//!
+//! A `Money` only adds to, subtracts from and compares with amounts in its
+//! own currency; moving between currencies goes through `CurrencyService`
+//! (or `ExchangeRates`) so every conversion names the rate it used.
+
+use super::currency::{parse_price_with_currency, Currency};
+use crate::AppError;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Mul;
+
+/// An amount in a currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    amount: Decimal,
+    currency: Currency,
+}
+
+impl Money {
+    /// Creates an amount as given, without rounding.
+    ///
+    /// Listed prices keep their exact amount; use `round` for amounts
+    /// produced by arithmetic or conversion.
+    pub fn new(amount: Decimal, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    /// Creates an amount rounded to the currency's minor units.
+    pub fn rounded(amount: Decimal, currency: Currency) -> Self {
+        Self::new(amount, currency).round()
+    }
+
+    /// Creates a USD amount.
+    pub fn usd(amount: Decimal) -> Self {
+        Self::new(amount, Currency::USD)
+    }
+
+    /// Parses a price string, detecting its currency.
+    ///
+    /// # Arguments
+    /// * `price_str` - Price text (e.g., "£999", "¥1,299")
+    /// * `site_hint` - Optional site name or URL for currency detection
+    ///
+    /// # Returns
+    /// * `Ok(Money)` - Parsed amount in the detected currency
+    /// * `Err(AppError)` - If the text holds no price
+    pub fn parse(price_str: &str, site_hint: Option<&str>) -> Result<Self, AppError> {
+        let (amount, currency) = parse_price_with_currency(price_str, site_hint)?;
+        Ok(Self::new(amount, currency))
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Rounds to the currency's minor units (0 for JPY, 3 for KWD).
+    pub fn round(self) -> Self {
+        Self {
+            amount: self.amount.round_dp(self.currency.minor_units()),
+            ..self
+        }
+    }
+
+    /// Adds an amount in the same currency.
+    ///
+    /// # Returns
+    /// * `Ok(Money)` - Sum
+    /// * `Err(AppError)` - The currencies differ
+    pub fn checked_add(self, other: Money) -> Result<Self, AppError> {
+        self.same_currency(&other, "add")?;
+        Ok(Self::new(self.amount + other.amount, self.currency))
+    }
+
+    /// Subtracts an amount in the same currency.
+    ///
+    /// # Returns
+    /// * `Ok(Money)` - Difference
+    /// * `Err(AppError)` - The currencies differ
+    pub fn checked_sub(self, other: Money) -> Result<Self, AppError> {
+        self.same_currency(&other, "subtract")?;
+        Ok(Self::new(self.amount - other.amount, self.currency))
+    }
+
+    /// Formats the amount rounded to its minor units.
+    ///
+    /// # Arguments
+    /// * `locale` - BCP 47 tag (e.g., "de-DE"); `None` writes symbol and digits
+    pub fn format(&self, locale: Option<&str>) -> String {
+        self.currency.format(self.amount, locale)
+    }
+
+    /// Converts at a known rate, rounding to the target's minor units.
+    ///
+    /// Only the currency module applies rates, so every conversion goes
+    /// through a rate it can account for.
+    ///
+    /// # Arguments
+    /// * `rate` - Units of `to` per unit of this currency
+    /// * `to` - Target currency
+    pub(super) fn exchange(self, rate: Decimal, to: Currency) -> Self {
+        if self.currency == to {
+            return self;
+        }
+        Self::rounded(self.amount * rate, to)
+    }
+
+    fn same_currency(&self, other: &Money, operation: &str) -> Result<(), AppError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(AppError::Internal(format!(
+                "Cannot {} {} and {} amounts",
+                operation, self.currency, other.currency
+            )))
+        }
+    }
+}
+
+/// Scales an amount (e.g., a per-unit share of a pack price).
+impl Mul<Decimal> for Money {
+    type Output = Money;
+
+    fn mul(self, factor: Decimal) -> Money {
+        Money::new(self.amount * factor, self.currency)
+    }
+}
+
+/// Amounts in different currencies are unordered.
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self.currency == other.currency).then(|| self.amount.cmp(&other.amount))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_rounds_to_minor_units() {
+        let kwd = Currency::from_str("KWD").unwrap();
+
+        assert_eq!(
+            Money::rounded(dec("1299.6"), Currency::JPY).amount(),
+            dec("1300")
+        );
+        assert_eq!(Money::rounded(dec("12.3456"), kwd).amount(), dec("12.346"));
+        assert_eq!(
+            Money::usd(dec("10")).exchange(dec("149.256"), Currency::JPY),
+            Money::new(dec("1493"), Currency::JPY)
+        );
+        let yen = Money::new(dec("1299.6"), Currency::JPY);
+        assert_eq!(yen.to_string(), "¥1300");
+        assert_eq!(yen.format(Some("ja-JP")), "￥1,300");
+    }
+
+    #[test]
+    fn test_arithmetic_needs_same_currency() {
+        let usd = Money::usd(dec("10.50"));
+
+        assert_eq!(
+            usd.checked_add(Money::usd(dec("0.25"))).unwrap(),
+            Money::usd(dec("10.75"))
+        );
+        assert!(usd
+            .checked_sub(Money::new(dec("1"), Currency::GBP))
+            .is_err());
+        assert!(usd > Money::usd(dec("9")));
+        assert_eq!(usd.partial_cmp(&Money::new(dec("9"), Currency::GBP)), None);
+        assert_eq!(usd * dec("2"), Money::usd(dec("21.00")));
+
+        let price = Money::parse("£999", None).unwrap();
+        assert_eq!(price, Money::new(dec("999"), Currency::GBP));
+    }
+}
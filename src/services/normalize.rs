@@ -0,0 +1,372 @@
+//! Title normalization shared by both sides of product matching.
+//!
+//! Sites spell the same product differently: "SAMSUNG" vs "Samsung",
+//! "256 Gigabyte" vs "256GB", `15.6"` vs "15.6 inch", "Hewlett-Packard" vs
+//! "HP", plus HTML entities, emoji and "HOT SALE!!" prefixes. Normalizing
+//! source and candidate titles the same way lets matching compare words
+//! instead of spellings.
+//!
+//! The pipeline: decode HTML entities, Unicode NFKC, lowercase, fold Arabic
+//! digits and letter variants, tokenize (symbols and emoji become
+//! separators), split and canonicalize units, drop promotional phrases and
+//! replace brand aliases and translated spec words ("noir", "schwarz" and
+//! "اسود" become "black"). The dictionaries live in
+//! `data/matching/normalization.json`.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+
+/// Built-in dictionary, also the reference for custom dictionary files.
+const DEFAULT_DICTIONARY: &str = include_str!("../../data/matching/normalization.json");
+
+/// HTML entities decoded by name (numeric entities are always decoded).
+const NAMED_ENTITIES: [(&str, &str); 14] = [
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", " "),
+    ("ndash", "-"),
+    ("mdash", "-"),
+    ("trade", "™"),
+    ("reg", "®"),
+    ("copy", "©"),
+    ("eacute", "é"),
+    ("egrave", "è"),
+    ("rsquo", "'"),
+];
+
+/// Trademark-style marks removed before Unicode normalization.
+const MARKS: [char; 4] = ['™', '®', '©', '℠'];
+
+/// Word lists behind the normalizer, as stored on disk.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NormalizationDictionary {
+    /// Canonical brand mapped to its alternative spellings
+    #[serde(default)]
+    pub brands: HashMap<String, Vec<String>>,
+    /// Canonical unit mapped to its spellings (only applied after a number)
+    #[serde(default)]
+    pub units: HashMap<String, Vec<String>>,
+    /// Promotional phrases removed from titles
+    #[serde(default)]
+    pub promo_phrases: Vec<String>,
+    /// Spec words translated to English, keyed by language (e.g., "fr": {"noir": "black"})
+    #[serde(default)]
+    pub vocabulary: HashMap<String, HashMap<String, String>>,
+}
+
+/// Normalizes product titles for matching.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "NormalizationDictionary")]
+pub struct TitleNormalizer {
+    /// Unit spelling (canonical included) to canonical unit
+    units: HashMap<String, String>,
+    /// Token sequences to replace, longest first; `None` removes the phrase
+    phrases: Vec<(Vec<String>, Option<String>)>,
+}
+
+impl Default for TitleNormalizer {
+    fn default() -> Self {
+        let dictionary: NormalizationDictionary =
+            serde_json::from_str(DEFAULT_DICTIONARY).expect("built-in normalization dictionary");
+        Self::from(dictionary)
+    }
+}
+
+impl From<NormalizationDictionary> for TitleNormalizer {
+    fn from(dictionary: NormalizationDictionary) -> Self {
+        let mut units = HashMap::new();
+        let mut phrases: Vec<(Vec<String>, Option<String>)> = Vec::new();
+        let key = |text: &str| tokenize(&fold(text));
+
+        for (canonical, aliases) in &dictionary.units {
+            let canonical = canonical.to_lowercase();
+            for alias in aliases {
+                match key(alias).as_slice() {
+                    [word] => {
+                        units.insert(word.clone(), canonical.clone());
+                    }
+                    // Multi-word units ("milliamp hours") are unambiguous phrases
+                    words => phrases.push((words.to_vec(), Some(canonical.clone()))),
+                }
+            }
+            units.insert(canonical.clone(), canonical);
+        }
+        for (canonical, aliases) in &dictionary.brands {
+            let canonical = key(canonical).join(" ");
+            for alias in aliases {
+                phrases.push((key(alias), Some(canonical.clone())));
+            }
+        }
+        for words in dictionary.vocabulary.values() {
+            for (word, english) in words {
+                phrases.push((key(word), Some(key(english).join(" "))));
+            }
+        }
+        for phrase in &dictionary.promo_phrases {
+            phrases.push((key(phrase), None));
+        }
+        phrases.retain(|(tokens, _)| !tokens.is_empty());
+        // Longest first so "hewlett packard" wins over a shorter overlapping alias
+        phrases.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+
+        Self { units, phrases }
+    }
+}
+
+impl TitleNormalizer {
+    /// Normalizes a title into lowercase, space-separated words.
+    ///
+    /// # Arguments
+    /// * `title` - Product title, search query, brand or model number
+    ///
+    /// # Returns
+    /// * Normalized title (e.g., "HOT SALE!! SAMSUNG Galaxy A54 256 Gigabyte &amp; 6.4&quot;"
+    ///   becomes "samsung galaxy a54 256 gb 6.4 inch")
+    pub fn normalize(&self, title: &str) -> String {
+        let folded = fold(&decode_entities(title));
+        let tokens = self.canonicalize_units(tokenize(&folded));
+        self.replace_phrases(tokens).join(" ")
+    }
+
+    /// Splits "256gb" into "256 gb" and maps unit spellings that follow a number.
+    ///
+    /// A unit word between two bare numbers is a connective and stays ("2 in 1"),
+    /// unlike "8 go 128 go" where the next number carries its own unit.
+    fn canonicalize_units(&self, tokens: Vec<String>) -> Vec<String> {
+        let mut split: Vec<String> = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            let number_len = token
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(token.len());
+            let (number, suffix) = token.split_at(number_len);
+            if !number.is_empty() && !suffix.is_empty() && self.units.contains_key(suffix) {
+                split.push(number.to_string());
+                split.push(suffix.to_string());
+            } else {
+                split.push(token);
+            }
+        }
+
+        let mut out = Vec::with_capacity(split.len());
+        for (i, token) in split.iter().enumerate() {
+            let after_number = i > 0 && is_number(&split[i - 1]);
+            let connective = split.get(i + 1).is_some_and(|next| is_number(next))
+                && !split
+                    .get(i + 2)
+                    .is_some_and(|unit| self.units.contains_key(unit.as_str()));
+            match self.units.get(token.as_str()) {
+                Some(canonical) if after_number && !connective => out.push(canonical.clone()),
+                _ => out.push(token.clone()),
+            }
+        }
+        out
+    }
+
+    /// Replaces brand aliases and removes promotional phrases.
+    fn replace_phrases(&self, tokens: Vec<String>) -> Vec<String> {
+        let mut out = Vec::with_capacity(tokens.len());
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let matched = self
+                .phrases
+                .iter()
+                .find(|(phrase, _)| tokens[i..].starts_with(phrase));
+            match matched {
+                Some((phrase, replacement)) => {
+                    if let Some(replacement) = replacement {
+                        out.push(replacement.clone());
+                    }
+                    i += phrase.len();
+                }
+                None => {
+                    out.push(tokens[i].clone());
+                    i += 1;
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Applies NFKC, lowercases and folds Arabic script variants.
+///
+/// Arabic-Indic digits become ASCII digits, alef variants become a bare
+/// alef and short vowel marks and tatweel are removed, so "أسود ١٢٨" and
+/// "اسود 128" read the same.
+fn fold(text: &str) -> String {
+    // NFKC would turn "™" into "TM", so marks are dropped first
+    text.chars()
+        .filter(|c| !MARKS.contains(c))
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .filter_map(|c| match c {
+            '\u{0660}'..='\u{0669}' => char::from_digit(c as u32 - 0x0660, 10),
+            '\u{06F0}'..='\u{06F9}' => char::from_digit(c as u32 - 0x06F0, 10),
+            'أ' | 'إ' | 'آ' => Some('ا'),
+            '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{0640}' => None,
+            _ => Some(c),
+        })
+        .collect()
+}
+
+/// Decodes named and numeric HTML entities; unknown entities are kept as-is.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let decoded = after
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&after[..end]).map(|c| (c, end)));
+        match decoded {
+            Some((value, end)) => {
+                out.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<String> {
+    if let Some(code) = name.strip_prefix('#') {
+        let value = match code.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => code.parse().ok(),
+        };
+        return value.and_then(char::from_u32).map(String::from);
+    }
+
+    NAMED_ENTITIES
+        .iter()
+        .find(|(entity, _)| *entity == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Splits lowercase text into words.
+///
+/// Decimal points inside numbers are kept ("15.6"), an inch mark after a
+/// digit becomes "inch", "/" stays a separate token (specs read "8/256" as
+/// RAM/storage), apostrophes inside words are dropped ("de'longhi") and any
+/// other symbol or emoji separates words.
+fn tokenize(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = chars[..i].last().copied();
+        let next = chars.get(i + 1).copied();
+
+        match c {
+            _ if c.is_alphanumeric() => out.push(c),
+            '.' | ','
+                if out.ends_with(|p: char| p.is_ascii_digit())
+                    && next.is_some_and(|n| n.is_ascii_digit()) =>
+            {
+                out.push('.');
+            }
+            '"' | '″' | '”' | '“' if prev.is_some_and(|p| p.is_ascii_digit()) => {
+                out.push_str(" inch ");
+            }
+            '\'' | '’'
+                if prev.is_some_and(char::is_alphabetic)
+                    && next.is_some_and(char::is_alphabetic) => {}
+            '/' => out.push_str(" / "),
+            _ => out.push(' '),
+        }
+    }
+
+    out.split_whitespace().map(str::to_string).collect()
+}
+
+fn is_number(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_digit()) && token.parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_strips_noise() {
+        let normalizer = TitleNormalizer::default();
+
+        assert_eq!(
+            normalizer.normalize("🔥HOT SALE!! SAMSUNG Galaxy A54 &amp; Case&#x2122;"),
+            "samsung galaxy a54 case"
+        );
+        // Full-width characters fold to ASCII under NFKC
+        assert_eq!(normalizer.normalize("ＡＢＣ １２３"), "abc 123");
+        assert_eq!(normalizer.normalize("AT&T Phone"), "at t phone");
+    }
+
+    #[test]
+    fn test_normalize_units() {
+        let normalizer = TitleNormalizer::default();
+
+        assert_eq!(
+            normalizer.normalize("Dell 15.6\" Laptop 512 Gigabytes SSD, 1TB HDD"),
+            "dell 15.6 inch laptop 512 gb ssd 1 tb hdd"
+        );
+        assert_eq!(
+            normalizer.normalize("Samsung Galaxy A15 8/128 Go 6,5 pouces"),
+            "samsung galaxy a15 8 / 128 gb 6.5 inch"
+        );
+        // Unit words that are not measurements stay
+        assert_eq!(normalizer.normalize("HP 2 in 1 Laptop"), "hp 2 in 1 laptop");
+        assert_eq!(normalizer.normalize("Pixel 8 5G"), "pixel 8 5g");
+        assert_eq!(normalizer.normalize("8 Go 128 Go"), "8 gb 128 gb");
+    }
+
+    #[test]
+    fn test_normalize_brand_aliases() {
+        let normalizer = TitleNormalizer::default();
+
+        assert_eq!(
+            normalizer.normalize("Hewlett-Packard Pavilion 15"),
+            "hp pavilion 15"
+        );
+        assert_eq!(
+            normalizer.normalize("De'Longhi Dedica EC685"),
+            "delonghi dedica ec685"
+        );
+        assert_eq!(
+            normalizer.normalize("TP-Link Archer AX55"),
+            "tp link archer ax55"
+        );
+    }
+
+    #[test]
+    fn test_normalize_translates_spec_vocabulary() {
+        let normalizer = TitleNormalizer::default();
+
+        assert_eq!(
+            normalizer.normalize("iPhone 15 Gris Sidéral 128 Go"),
+            "iphone 15 space gray 128 gb"
+        );
+        assert_eq!(
+            normalizer.normalize("Galaxy A15 Schwarz 6,5 Zoll"),
+            "galaxy a15 black 6.5 inch"
+        );
+        assert_eq!(
+            normalizer.normalize("سامسونج A15 أسود ١٢٨ جيجابايت"),
+            "سامسونج a15 black 128 gb"
+        );
+        assert_eq!(normalizer.normalize("5000 milliamp hours"), "5000 mah");
+    }
+}
@@ -0,0 +1,299 @@
+//! Time-bucketed price history.
+//!
+//! History rows are converted to one currency (see `rate_history`) and
+//! folded into min / average / max / last prices per site and day, week or
+//! month. Buckets without rows are kept so charts have an even time axis;
+//! their `last` carries the previous bucket's price forward.
+
+use super::money::Money;
+use crate::db::PriceHistoryRecord;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Most buckets in one response.
+pub const MAX_BUCKETS: usize = 400;
+
+/// What a history request is keyed by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryKey {
+    /// Every listing recorded for a canonical product
+    Product(Uuid),
+    /// Listings recorded for an exact search query
+    Query(String),
+    /// One listing, by its normalized link (see `feedback::candidate_key`)
+    Listing(String),
+}
+
+/// Width of a history bucket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryInterval {
+    #[default]
+    Day,
+    /// ISO weeks, starting on Monday
+    Week,
+    Month,
+}
+
+impl HistoryInterval {
+    /// Returns the first day of the bucket a day falls in.
+    pub fn bucket_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Self::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// Returns the first day of the following bucket.
+    fn next(&self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => start + Duration::days(1),
+            Self::Week => start + Duration::days(7),
+            Self::Month => start
+                .checked_add_months(Months::new(1))
+                .unwrap_or(NaiveDate::MAX),
+        }
+    }
+
+    /// Returns the first day of the default range ending on `to`.
+    ///
+    /// # Returns
+    /// * 30 days, 26 weeks or 12 months before `to`
+    pub fn default_from(&self, to: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => to - Duration::days(29),
+            Self::Week => to - Duration::weeks(25),
+            Self::Month => to.checked_sub_months(Months::new(11)).unwrap_or(to),
+        }
+    }
+
+    /// Returns the start of every bucket from `from` to `to`, inclusive.
+    ///
+    /// # Returns
+    /// * `Some(Vec<NaiveDate>)` - Bucket starts, oldest first
+    /// * `None` - The range needs more than `MAX_BUCKETS` buckets
+    pub fn buckets(&self, from: NaiveDate, to: NaiveDate) -> Option<Vec<NaiveDate>> {
+        let mut starts = Vec::new();
+        let mut start = self.bucket_start(from);
+        while start <= to {
+            if starts.len() == MAX_BUCKETS {
+                return None;
+            }
+            starts.push(start);
+            start = self.next(start);
+        }
+        Some(starts)
+    }
+}
+
+/// Prices of one site in one bucket.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryBucket {
+    /// First day of the bucket
+    pub start: NaiveDate,
+    /// Prices recorded in the bucket (0 for a filled gap)
+    pub count: usize,
+    pub min: Option<Money>,
+    /// Mean, rounded to the currency's minor units
+    pub avg: Option<Money>,
+    pub max: Option<Money>,
+    /// Latest price recorded up to the end of the bucket
+    pub last: Option<Money>,
+}
+
+/// Bucketed prices of one site.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SiteHistory {
+    pub site: String,
+    pub buckets: Vec<HistoryBucket>,
+}
+
+/// A converted price and when it was recorded.
+type Observed = (DateTime<Utc>, Money);
+
+/// Folds converted history rows into buckets per site.
+///
+/// Rows without a conversion, or outside the buckets, are skipped.
+///
+/// # Arguments
+/// * `records` - History rows with `converted` set
+/// * `interval` - Bucket width
+/// * `starts` - Bucket starts from `HistoryInterval::buckets`
+///
+/// # Returns
+/// * One entry per site, ordered by site name, each with every bucket
+pub fn aggregate(
+    records: &[PriceHistoryRecord],
+    interval: HistoryInterval,
+    starts: &[NaiveDate],
+) -> Vec<SiteHistory> {
+    let mut grouped: BTreeMap<&str, BTreeMap<NaiveDate, Vec<Observed>>> = BTreeMap::new();
+    for record in records {
+        let Some(conversion) = &record.converted else {
+            continue;
+        };
+        let start = interval.bucket_start(record.scraped_at.date_naive());
+        if starts.binary_search(&start).is_err() {
+            continue;
+        }
+        grouped
+            .entry(record.site.as_str())
+            .or_default()
+            .entry(start)
+            .or_default()
+            .push((record.scraped_at, conversion.price));
+    }
+
+    grouped
+        .into_iter()
+        .map(|(site, mut by_bucket)| {
+            let mut last = None;
+            let buckets = starts
+                .iter()
+                .map(|start| {
+                    let mut rows = by_bucket.remove(start).unwrap_or_default();
+                    rows.sort_by_key(|(scraped_at, _)| *scraped_at);
+                    let prices: Vec<Money> = rows.iter().map(|(_, price)| *price).collect();
+                    if let Some(price) = prices.last() {
+                        last = Some(*price);
+                    }
+                    bucket(*start, &prices, last)
+                })
+                .collect();
+            SiteHistory {
+                site: site.to_string(),
+                buckets,
+            }
+        })
+        .collect()
+}
+
+/// Summarizes the prices of one bucket, all in the same currency.
+fn bucket(start: NaiveDate, prices: &[Money], last: Option<Money>) -> HistoryBucket {
+    let amounts: Vec<Decimal> = prices.iter().map(Money::amount).collect();
+    let currency = prices.first().map(Money::currency);
+    let money = |amount: Option<Decimal>| Some(Money::new(amount?, currency?));
+
+    let sum: Decimal = amounts.iter().sum();
+    let avg = (!amounts.is_empty()).then(|| sum / Decimal::from(amounts.len()));
+
+    HistoryBucket {
+        start,
+        count: prices.len(),
+        min: money(amounts.iter().min().copied()),
+        avg: money(avg).map(Money::round),
+        max: money(amounts.iter().max().copied()),
+        last,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rate_history::{HistoryConversion, HistoryRates};
+    use chrono::TimeZone;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn record(site: &str, day: NaiveDate, hour: u32, usd: i64) -> PriceHistoryRecord {
+        let scraped_at = Utc.from_utc_datetime(&day.and_hms_opt(hour, 0, 0).unwrap());
+        PriceHistoryRecord {
+            id: Uuid::new_v4(),
+            site: site.to_string(),
+            product_title: "Dell XPS 13".to_string(),
+            price: Money::usd(Decimal::from(usd)),
+            price_usd: Decimal::from(usd),
+            exchange_rate: Some(Decimal::ONE),
+            product_link: format!("https://{}.example/item", site.to_lowercase()),
+            image_url: None,
+            search_query: "dell xps 13".to_string(),
+            product_id: None,
+            scraped_at,
+            converted: Some(HistoryConversion {
+                price: Money::usd(Decimal::from(usd)),
+                rate: Decimal::ONE,
+                rates: HistoryRates::Observation,
+                rate_date: day,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_bucket_starts() {
+        let interval = HistoryInterval::Week;
+        // 2025-03-05 is a Wednesday
+        assert_eq!(interval.bucket_start(date(2025, 3, 5)), date(2025, 3, 3));
+        assert_eq!(
+            interval.buckets(date(2025, 3, 5), date(2025, 3, 17)),
+            Some(vec![date(2025, 3, 3), date(2025, 3, 10), date(2025, 3, 17)])
+        );
+        assert_eq!(
+            HistoryInterval::Month.buckets(date(2025, 1, 31), date(2025, 3, 1)),
+            Some(vec![date(2025, 1, 1), date(2025, 2, 1), date(2025, 3, 1)])
+        );
+        assert_eq!(
+            HistoryInterval::Day.buckets(date(2024, 1, 1), date(2025, 6, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_aggregate_fills_gaps() {
+        let starts = HistoryInterval::Day
+            .buckets(date(2025, 3, 1), date(2025, 3, 4))
+            .unwrap();
+        let mut unconverted = record("Jumia", date(2025, 3, 2), 10, 50);
+        unconverted.converted = None;
+        let records = vec![
+            unconverted,
+            record("Amazon", date(2025, 3, 1), 18, 110),
+            record("Amazon", date(2025, 3, 1), 9, 100),
+            record("Amazon", date(2025, 3, 1), 12, 95),
+            record("Amazon", date(2025, 3, 3), 10, 90),
+            record("eBay", date(2025, 3, 4), 10, 80),
+            record("eBay", date(2025, 2, 20), 10, 70),
+        ];
+
+        let history = aggregate(&records, HistoryInterval::Day, &starts);
+        assert_eq!(history.len(), 2);
+
+        let amazon = &history[0];
+        assert_eq!(amazon.site, "Amazon");
+        let first = &amazon.buckets[0];
+        assert_eq!(first.count, 3);
+        assert_eq!(first.min, Some(Money::usd(Decimal::from(95))));
+        assert_eq!(first.avg, Some(Money::usd(Decimal::new(10167, 2))));
+        assert_eq!(first.max, Some(Money::usd(Decimal::from(110))));
+        assert_eq!(first.last, Some(Money::usd(Decimal::from(110))));
+
+        // The gap on the 2nd carries the last price forward
+        let gap = &amazon.buckets[1];
+        assert_eq!((gap.count, gap.min, gap.avg), (0, None, None));
+        assert_eq!(gap.last, Some(Money::usd(Decimal::from(110))));
+        assert_eq!(amazon.buckets[3].last, Some(Money::usd(Decimal::from(90))));
+
+        // Unconverted rows and rows before the range are skipped
+        let ebay = &history[1];
+        assert_eq!(ebay.buckets.len(), 4);
+        assert_eq!(ebay.buckets[2].last, None);
+        assert_eq!(ebay.buckets[3].last, Some(Money::usd(Decimal::from(80))));
+    }
+
+    #[test]
+    fn test_record_wire_format() {
+        let mut row = record("Amazon", date(2025, 3, 3), 9, 95);
+        row.converted = None;
+        let json = serde_json::to_value(&row).unwrap();
+
+        assert_eq!(json["price_original"], serde_json::json!("95"));
+        assert_eq!(json["currency"], "USD");
+        assert!(json.get("price").is_none());
+        assert!(json.get("converted").is_none());
+    }
+}
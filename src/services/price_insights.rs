@@ -0,0 +1,316 @@
+//! Price insights from a listing's own history.
+//!
+//! Each listing is compared with the prices recorded for the same link (in
+//! the same currency, so exchange rate moves don't read as price moves):
+//! its all-time low and high, its 30- and 90-day lows, the share of recent
+//! prices below it, and how long ago it was last cheaper. A buy / wait
+//! recommendation is derived from those, with the reasons spelled out.
+
+use super::feedback;
+use super::money::Money;
+use crate::config::InsightConfig;
+use crate::db::{self, ListingPriceStats};
+use crate::SitePrice;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// Whether the current price is a good one to buy at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuyRecommendation {
+    /// At its lowest, or among the lowest recent prices
+    Buy,
+    /// Within the usual range
+    Fair,
+    /// Among the highest recent prices; it has been cheaper
+    Wait,
+    /// Too few prices recorded to judge
+    NotEnoughHistory,
+}
+
+/// How a listing's price compares with its history.
+///
+/// Amounts are in the listing's currency and cover the prices recorded
+/// before this comparison.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceInsight {
+    pub all_time_low: Money,
+    pub all_time_high: Money,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_30d: Option<Money>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_90d: Option<Money>,
+    /// Share of the last 30 days' prices below the current price (0 = cheapest)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentile_30d: Option<u8>,
+    /// Share of the last 90 days' prices below the current price (0 = cheapest)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentile_90d: Option<u8>,
+    /// Days since a lower price was recorded (absent if it was never lower)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days_since_lower: Option<i64>,
+    /// Prices recorded for the listing
+    pub observations: i64,
+    pub tracked_since: DateTime<Utc>,
+    pub recommendation: BuyRecommendation,
+    /// Why the recommendation was made (e.g., "Lowest price since 2025-01-04")
+    pub reasons: Vec<String>,
+}
+
+/// Sets `price_insight` on listings with recorded history.
+///
+/// A database failure is logged and the listings are left without
+/// insights, so history never blocks a comparison.
+///
+/// # Arguments
+/// * `prices` - Listings of the comparison
+/// * `pool` - Database connection pool
+/// * `config` - Recommendation thresholds
+pub async fn annotate(prices: &mut [SitePrice], pool: &PgPool, config: &InsightConfig) {
+    let mut listings: Vec<(String, String, Decimal)> = prices
+        .iter()
+        .map(|p| {
+            let link_key = feedback::candidate_key(&p.link);
+            (
+                link_key,
+                p.price.currency().code().to_string(),
+                p.price.amount(),
+            )
+        })
+        .collect();
+    listings.sort();
+    listings.dedup_by(|a, b| a.0 == b.0);
+
+    let now = Utc::now();
+    let stats = match db::get_listing_price_stats(pool, &listings, now).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load listing price history");
+            return;
+        }
+    };
+    let stats: HashMap<&str, &ListingPriceStats> =
+        stats.iter().map(|s| (s.link_key.as_str(), s)).collect();
+
+    for price in prices.iter_mut() {
+        let link_key = feedback::candidate_key(&price.link);
+        if let Some(stats) = stats.get(link_key.as_str()) {
+            price.price_insight = Some(insight(price.price, stats, now, config));
+        }
+    }
+}
+
+/// Builds the insight for a price from its listing's history.
+///
+/// # Arguments
+/// * `current` - Listed price
+/// * `stats` - Recorded prices of the listing, in `current`'s currency
+/// * `now` - Time of the comparison
+/// * `config` - Recommendation thresholds
+pub fn insight(
+    current: Money,
+    stats: &ListingPriceStats,
+    now: DateTime<Utc>,
+    config: &InsightConfig,
+) -> PriceInsight {
+    let currency = current.currency();
+    let money = |amount: Decimal| Money::new(amount, currency);
+    let percentile =
+        |lower: i64, total: i64| (total > 0).then(|| (lower * 100 / total).clamp(0, 100) as u8);
+
+    let low_30d = stats.low_30d.map(money);
+    let percentile_90d = percentile(stats.lower_90d, stats.observations_90d);
+    let days_since_lower = stats.last_lower_at.map(|at| (now - at).num_days().max(0));
+
+    let mut reasons = Vec::new();
+    let recommendation = if stats.observations < config.min_observations {
+        reasons.push(format!(
+            "Only {} prices recorded since {}",
+            stats.observations,
+            stats.first_seen.date_naive()
+        ));
+        BuyRecommendation::NotEnoughHistory
+    } else if current.amount() <= stats.all_time_low {
+        reasons.push(format!(
+            "Lowest price since tracking began on {}",
+            stats.first_seen.date_naive()
+        ));
+        BuyRecommendation::Buy
+    } else if let Some(p) = percentile_90d.filter(|p| *p <= config.buy_percentile) {
+        reasons.push(format!(
+            "Only {}% of prices in the last 90 days were lower",
+            p
+        ));
+        BuyRecommendation::Buy
+    } else if let Some(p) = percentile_90d.filter(|p| *p >= config.wait_percentile) {
+        reasons.push(format!("Higher than {}% of prices in the last 90 days", p));
+        if let Some(low) = low_30d.filter(|low| !low.amount().is_zero()) {
+            let above = (current.amount() - low.amount()) / low.amount() * Decimal::from(100);
+            if above > Decimal::ZERO {
+                reasons.push(format!(
+                    "{}% above the 30-day low of {}",
+                    above.round_dp(0),
+                    low
+                ));
+            }
+        }
+        BuyRecommendation::Wait
+    } else if percentile_90d.is_none() {
+        reasons.push("No prices recorded in the last 90 days".to_string());
+        BuyRecommendation::Fair
+    } else {
+        reasons.push("Within the usual range of the last 90 days".to_string());
+        BuyRecommendation::Fair
+    };
+
+    if recommendation != BuyRecommendation::NotEnoughHistory {
+        match days_since_lower {
+            Some(0) => reasons.push("Was cheaper earlier today".to_string()),
+            Some(days) => reasons.push(format!("Last cheaper {} days ago", days)),
+            None => {}
+        }
+    }
+
+    PriceInsight {
+        all_time_low: money(stats.all_time_low),
+        all_time_high: money(stats.all_time_high),
+        low_30d,
+        low_90d: stats.low_90d.map(money),
+        percentile_30d: percentile(stats.lower_30d, stats.observations_30d),
+        percentile_90d,
+        days_since_lower,
+        observations: stats.observations,
+        tracked_since: stats.first_seen,
+        recommendation,
+        reasons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::currency::Currency;
+    use chrono::TimeZone;
+
+    fn stats(observations: i64, lower_90d: i64, observations_90d: i64) -> ListingPriceStats {
+        ListingPriceStats {
+            link_key: "https://amazon.com/dp/b0c".to_string(),
+            all_time_low: Decimal::from(80),
+            all_time_high: Decimal::from(120),
+            observations,
+            first_seen: Utc.with_ymd_and_hms(2025, 1, 4, 0, 0, 0).unwrap(),
+            last_lower_at: Some(Utc.with_ymd_and_hms(2025, 3, 28, 0, 0, 0).unwrap()),
+            low_30d: Some(Decimal::from(90)),
+            lower_30d: lower_90d.min(observations_90d),
+            observations_30d: observations_90d,
+            low_90d: Some(Decimal::from(85)),
+            lower_90d,
+            observations_90d,
+        }
+    }
+
+    #[test]
+    fn test_recommendation() {
+        let config = InsightConfig::default();
+        let now = Utc.with_ymd_and_hms(2025, 4, 1, 0, 0, 0).unwrap();
+        let usd = |amount: i64| Money::new(Decimal::from(amount), Currency::USD);
+
+        let new_low = insight(usd(79), &stats(20, 0, 10), now, &config);
+        assert_eq!(new_low.recommendation, BuyRecommendation::Buy);
+        assert_eq!(
+            new_low.reasons[0],
+            "Lowest price since tracking began on 2025-01-04"
+        );
+
+        let high = insight(usd(108), &stats(20, 8, 10), now, &config);
+        assert_eq!(high.recommendation, BuyRecommendation::Wait);
+        assert_eq!(high.percentile_90d, Some(80));
+        assert_eq!(high.days_since_lower, Some(4));
+        assert_eq!(
+            high.reasons,
+            vec![
+                "Higher than 80% of prices in the last 90 days",
+                "20% above the 30-day low of $90.00",
+                "Last cheaper 4 days ago",
+            ]
+        );
+
+        let fair = insight(usd(95), &stats(20, 4, 10), now, &config);
+        assert_eq!(fair.recommendation, BuyRecommendation::Fair);
+
+        let sparse = insight(usd(95), &stats(2, 1, 2), now, &config);
+        assert_eq!(sparse.recommendation, BuyRecommendation::NotEnoughHistory);
+        assert_eq!(sparse.reasons.len(), 1);
+    }
+
+    #[test]
+    fn test_percentile_thresholds() {
+        let config = InsightConfig::default();
+        let now = Utc.with_ymd_and_hms(2025, 4, 1, 0, 0, 0).unwrap();
+        let usd = |amount: i64| Money::new(Decimal::from(amount), Currency::USD);
+        // Out of 100 recent prices, `lower` were below the current one
+        let at =
+            |lower: i64| insight(usd(100), &stats(100, lower, 100), now, &config).recommendation;
+
+        assert_eq!(at(config.buy_percentile as i64), BuyRecommendation::Buy);
+        assert_eq!(
+            at(config.buy_percentile as i64 + 1),
+            BuyRecommendation::Fair
+        );
+        assert_eq!(
+            at(config.wait_percentile as i64 - 1),
+            BuyRecommendation::Fair
+        );
+        assert_eq!(at(config.wait_percentile as i64), BuyRecommendation::Wait);
+
+        // Minimum observations is inclusive
+        let min = config.min_observations;
+        let enough = insight(usd(100), &stats(min, 50, 100), now, &config);
+        assert_eq!(enough.recommendation, BuyRecommendation::Fair);
+        let short = insight(usd(100), &stats(min - 1, 50, 100), now, &config);
+        assert_eq!(short.recommendation, BuyRecommendation::NotEnoughHistory);
+    }
+
+    #[test]
+    fn test_no_recent_history_and_new_low() {
+        let config = InsightConfig::default();
+        let now = Utc.with_ymd_and_hms(2025, 4, 1, 0, 0, 0).unwrap();
+        let usd = |amount: i64| Money::new(Decimal::from(amount), Currency::USD);
+
+        // Nothing recorded in the last 90 days
+        let mut old = stats(20, 0, 0);
+        old.low_30d = None;
+        old.low_90d = None;
+        let stale = insight(usd(95), &old, now, &config);
+        assert_eq!(stale.recommendation, BuyRecommendation::Fair);
+        assert_eq!(stale.percentile_90d, None);
+        assert_eq!(stale.low_90d, None);
+        assert_eq!(stale.reasons[0], "No prices recorded in the last 90 days");
+
+        // No history at all
+        let mut empty = stats(0, 0, 0);
+        empty.last_lower_at = None;
+        let untracked = insight(usd(95), &empty, now, &config);
+        assert_eq!(
+            untracked.recommendation,
+            BuyRecommendation::NotEnoughHistory
+        );
+        assert_eq!(untracked.percentile_30d, None);
+
+        // Below the all-time low, even when most recent prices were lower
+        let mut never_lower = stats(20, 9, 10);
+        never_lower.last_lower_at = None;
+        let below = insight(usd(70), &never_lower, now, &config);
+        assert_eq!(below.recommendation, BuyRecommendation::Buy);
+        assert_eq!(below.days_since_lower, None);
+        assert_eq!(below.all_time_low, usd(80));
+        assert_eq!(below.reasons.len(), 1);
+
+        // Matching the all-time low counts as a low
+        let at_low = insight(usd(80), &stats(20, 5, 10), now, &config);
+        assert_eq!(at_low.recommendation, BuyRecommendation::Buy);
+    }
+}
@@ -0,0 +1,418 @@
+//! Locale-aware parsing of scraped price text.
+//!
+//! Storefronts write amounts in their own conventions: "€1.299,99",
+//! "₹1,00,000", "¥1,500", "₦ 12,500 - ₦ 15,000", "From $9.99",
+//! "1 299,99 EUR". A separator is read as the decimal point only when the
+//! digit grouping, the currency's minor units and the site's locale agree
+//! on it; text that still reads two ways is an error rather than a guess.
+
+use super::currency::{Currency, LocaleFormat};
+use crate::AppError;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Words joining the two ends of a price range.
+const RANGE_CONNECTORS: [&str; 5] = ["-", "–", "—", "~", "to"];
+
+/// Characters that only ever group digits (spaces, apostrophes).
+const GROUP_ONLY_MARKS: [char; 6] = [' ', '\u{a0}', '\u{202f}', '\u{2009}', '\'', '’'];
+
+/// Amount or amount range parsed from price text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsedPrice {
+    /// Lowest amount (the amount itself unless the text is a range)
+    pub min: Decimal,
+    /// Highest amount (equal to `min` unless the text is a range)
+    pub max: Decimal,
+    /// Currency written in the text, if any
+    pub currency: Option<Currency>,
+}
+
+impl ParsedPrice {
+    /// Returns true when the text gave a range ("₦ 12,500 - ₦ 15,000").
+    pub fn is_range(&self) -> bool {
+        self.min != self.max
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Digits with the separators between them
+    Number(String),
+    /// Symbol, code or word
+    Word(String),
+}
+
+/// Parses price text into an amount or range and its currency.
+///
+/// # Arguments
+/// * `text` - Price text (e.g., "€1.299,99", "KSh 2,499", "From $9.99")
+/// * `locale` - BCP 47 tag of the site (e.g., "de-DE"), used when a
+///   separator could be either decimal point or digit grouping
+/// * `currency_hint` - Currency of the amount when already resolved (e.g.,
+///   from the marketplace); it decides how many decimals an amount can
+///   have, else the currency written in the text does
+///
+/// # Returns
+/// * `Ok(ParsedPrice)` - Amount (or range) and the currency written in the text
+/// * `Err(AppError)` - No amount, several amounts that are not a range,
+///   mixed currencies, or an amount that reads two ways
+pub fn parse_price(
+    text: &str,
+    locale: Option<&str>,
+    currency_hint: Option<Currency>,
+) -> Result<ParsedPrice, AppError> {
+    let ambiguous =
+        |reason: &str| AppError::Parse(format!("Ambiguous price {:?}: {}", text, reason));
+    let tokens = tokenize(text);
+
+    let currency = detect_currency(&tokens).map_err(|reason| ambiguous(&reason))?;
+    let minor_units = currency_hint.or(currency).map_or(2, |c| c.minor_units());
+    let locale = locale.and_then(LocaleFormat::for_locale);
+
+    let numbers: Vec<(usize, &str)> = tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, token)| match token {
+            Token::Number(raw) => Some((i, raw.as_str())),
+            Token::Word(_) => None,
+        })
+        .collect();
+
+    let (min, max) = match numbers.as_slice() {
+        [] => {
+            return Err(AppError::Parse(format!(
+                "No numeric value found in price: {}",
+                text
+            )))
+        }
+        [(_, raw)] => {
+            let amount = read_amount(raw, minor_units, locale).map_err(ambiguous)?;
+            (amount, amount)
+        }
+        [(first, low), (second, high)] => {
+            let connectors: Vec<&str> = tokens[first + 1..*second]
+                .iter()
+                .filter_map(|token| match token {
+                    Token::Word(word) if currency_of(word).is_none() => Some(word.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let is_range = matches!(connectors.as_slice(),
+                [connector] if RANGE_CONNECTORS.contains(&connector.to_lowercase().as_str()));
+            if !is_range {
+                return Err(ambiguous("several amounts"));
+            }
+
+            let low = read_amount(low, minor_units, locale).map_err(ambiguous)?;
+            let high = read_amount(high, minor_units, locale).map_err(ambiguous)?;
+            if low > high {
+                return Err(ambiguous("range ends below its start"));
+            }
+            (low, high)
+        }
+        _ => return Err(ambiguous("several amounts")),
+    };
+
+    Ok(ParsedPrice { min, max, currency })
+}
+
+/// Returns the currency symbol or code written in price text, if any.
+///
+/// # Arguments
+/// * `text` - Price text (e.g., "C$100", "1.299,99 EUR")
+///
+/// # Returns
+/// * `Some(String)` - First symbol or code next to an amount, as written
+/// * `None` - No currency written
+pub fn currency_mark(text: &str) -> Option<String> {
+    let tokens = tokenize(text);
+    let mark = currency_words(&tokens)
+        .next()
+        .map(|(word, _)| word.to_string());
+    mark
+}
+
+/// Splits text into numbers and words.
+///
+/// A space or apostrophe is kept inside a number only when it is followed
+/// by a three-digit group ("1 299,99"), so "2 1,299" stays two numbers.
+fn tokenize(text: &str) -> Vec<Token> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut i = 0;
+
+    let flush = |word: &mut String, tokens: &mut Vec<Token>| {
+        if !word.is_empty() {
+            tokens.push(Token::Word(std::mem::take(word)));
+        }
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_digit() {
+            flush(&mut word, &mut tokens);
+            let mut number = String::new();
+            while i < chars.len() {
+                let c = chars[i];
+                let next_is_digit = chars.get(i + 1).is_some_and(|n| n.is_ascii_digit());
+                if c.is_ascii_digit() || ((c == '.' || c == ',') && next_is_digit) {
+                    number.push(c);
+                } else if GROUP_ONLY_MARKS.contains(&c) && starts_group(&chars[i + 1..]) {
+                    number.push(' ');
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            tokens.push(Token::Number(number));
+            continue;
+        }
+
+        if c.is_whitespace() {
+            flush(&mut word, &mut tokens);
+        } else if RANGE_CONNECTORS.iter().any(|r| r.starts_with(c)) && !c.is_alphabetic() {
+            flush(&mut word, &mut tokens);
+            tokens.push(Token::Word(c.to_string()));
+        } else if c == '(' || c == ')' || c == '/' {
+            flush(&mut word, &mut tokens);
+        } else {
+            word.push(c);
+        }
+        i += 1;
+    }
+    flush(&mut word, &mut tokens);
+
+    tokens
+}
+
+/// Returns true when `rest` starts with exactly three digits.
+fn starts_group(rest: &[char]) -> bool {
+    rest.len() >= 3
+        && rest[..3].iter().all(|c| c.is_ascii_digit())
+        && !rest.get(3).is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Reads a currency symbol or ISO code ("₦", "C$", "KSh", "EUR", "Rs.").
+fn currency_of(word: &str) -> Option<Currency> {
+    if word.len() == 3 && word.chars().all(|c| c.is_ascii_uppercase()) {
+        if let Ok(currency) = Currency::from_str(word) {
+            return Some(currency);
+        }
+    }
+    Currency::from_symbol(word).or_else(|| Currency::from_symbol(word.trim_end_matches('.')))
+}
+
+/// Currencies written next to an amount, in text order.
+///
+/// Only words touching a number count, so "ALL SIZES" is not Albanian lek.
+fn currency_words(tokens: &[Token]) -> impl Iterator<Item = (&str, Currency)> + '_ {
+    tokens.iter().enumerate().filter_map(|(i, token)| {
+        let Token::Word(word) = token else {
+            return None;
+        };
+        let is_number = |t: Option<&Token>| matches!(t, Some(Token::Number(_)));
+        let touches_number =
+            is_number(tokens.get(i + 1)) || (i > 0 && is_number(tokens.get(i - 1)));
+        touches_number
+            .then(|| currency_of(word))
+            .flatten()
+            .map(|currency| (word.as_str(), currency))
+    })
+}
+
+/// Returns the single currency written in the tokens.
+fn detect_currency(tokens: &[Token]) -> Result<Option<Currency>, String> {
+    let mut found: Option<Currency> = None;
+    for (_, currency) in currency_words(tokens) {
+        match found {
+            Some(existing) if existing != currency => {
+                return Err(format!("mixed currencies {} and {}", existing, currency));
+            }
+            _ => found = Some(currency),
+        }
+    }
+    Ok(found)
+}
+
+/// Reads one number token.
+///
+/// The last '.' or ',' is the decimal point when it occurs once and
+/// another separator groups the digits, or when it is not followed by
+/// exactly three digits. Three trailing digits are a group unless the
+/// currency has three minor units or the locale writes that mark as its
+/// decimal point; then only the locale can decide.
+fn read_amount(
+    raw: &str,
+    minor_units: u32,
+    locale: Option<&LocaleFormat>,
+) -> Result<Decimal, &'static str> {
+    let marks: Vec<(usize, char)> = raw
+        .char_indices()
+        .filter(|(_, c)| !c.is_ascii_digit())
+        .collect();
+    let is_point = |c: char| c == '.' || c == ',';
+    let locale_decimal = |mark: char| locale.is_some_and(|f| f.decimal == mark.to_string());
+    let locale_group = |mark: char| locale.is_some_and(|f| f.group == mark.to_string());
+
+    let decimal = match marks.iter().rev().find(|(_, c)| is_point(*c)) {
+        None => None,
+        Some(&(pos, mark)) => {
+            let occurrences = marks.iter().filter(|(_, c)| *c == mark).count();
+            let other_marks = marks.iter().any(|(_, c)| *c != mark);
+            let fraction_len = raw.len() - pos - 1;
+
+            if occurrences > 1 {
+                None
+            } else if other_marks || fraction_len != 3 {
+                Some(pos)
+            } else if minor_units < 3 {
+                if locale_decimal(mark) {
+                    return Err("three digits after the locale's decimal point");
+                }
+                None
+            } else if locale_decimal(mark) {
+                Some(pos)
+            } else if locale_group(mark) {
+                None
+            } else {
+                return Err("separator could be a decimal point or digit grouping");
+            }
+        }
+    };
+
+    let (integer, fraction) = match decimal {
+        Some(pos) => (&raw[..pos], &raw[pos + 1..]),
+        None => (raw, ""),
+    };
+    if minor_units == 0 && fraction.chars().any(|c| c != '0') {
+        return Err("decimals in a currency without minor units");
+    }
+    if fraction.len() > 3.max(minor_units as usize) {
+        return Err("more decimals than the currency has");
+    }
+
+    let group_marks: Vec<char> = integer.chars().filter(|c| !c.is_ascii_digit()).collect();
+    if group_marks.windows(2).any(|pair| pair[0] != pair[1]) {
+        return Err("digits grouped with different separators");
+    }
+    let groups: Vec<&str> = integer.split(|c: char| !c.is_ascii_digit()).collect();
+    if !valid_grouping(&groups) {
+        return Err("digits are not grouped in thousands or lakhs");
+    }
+
+    let digits: String = groups.concat();
+    let number = if fraction.is_empty() {
+        digits
+    } else {
+        format!("{}.{}", digits, fraction)
+    };
+    Decimal::from_str(&number).map_err(|_| "not a number")
+}
+
+/// Accepts "1,299,999" (groups of three) and "1,00,000" (Indian lakh grouping).
+fn valid_grouping(groups: &[&str]) -> bool {
+    let Some((first, rest)) = groups.split_first() else {
+        return false;
+    };
+    let Some((last, middle)) = rest.split_last() else {
+        return !first.is_empty();
+    };
+
+    let thousands = (1..=3).contains(&first.len()) && rest.iter().all(|g| g.len() == 3);
+    let lakhs =
+        (1..=2).contains(&first.len()) && last.len() == 3 && middle.iter().all(|g| g.len() == 2);
+    thousands || lakhs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn amount(text: &str, locale: Option<&str>) -> (Decimal, Option<Currency>) {
+        let parsed = parse_price(text, locale, None).unwrap();
+        (parsed.min, parsed.currency)
+    }
+
+    #[test]
+    fn test_parses_locale_formats() {
+        assert_eq!(
+            amount("€1.299,99", None),
+            (dec("1299.99"), Some(Currency::EUR))
+        );
+        assert_eq!(
+            amount("$1,299.99", None),
+            (dec("1299.99"), Some(Currency::USD))
+        );
+        assert_eq!(
+            amount("₹1,00,000", None),
+            (dec("100000"), Some(Currency::INR))
+        );
+        assert_eq!(
+            amount("₹12,34,567.50", None),
+            (dec("1234567.50"), Some(Currency::INR))
+        );
+        assert_eq!(amount("¥1,500", None), (dec("1500"), Some(Currency::JPY)));
+        assert_eq!(
+            amount("₦ 12,500", None),
+            (dec("12500"), Some(Currency::NGN))
+        );
+        assert_eq!(
+            amount("From $9.99", None),
+            (dec("9.99"), Some(Currency::USD))
+        );
+        let (kes, currency) = amount("KSh 2,499", None);
+        assert_eq!(
+            (kes, currency.map(|c| c.code())),
+            (dec("2499"), Some("KES"))
+        );
+        assert_eq!(
+            amount("1 299,99 EUR", None),
+            (dec("1299.99"), Some(Currency::EUR))
+        );
+        assert_eq!(
+            amount("1\u{a0}299,99\u{a0}€", None),
+            (dec("1299.99"), Some(Currency::EUR))
+        );
+        assert_eq!(amount("1\u{202f}299,99 €", Some("fr-FR")).0, dec("1299.99"));
+        assert_eq!(amount("CHF 1’299.50", None).0, dec("1299.50"));
+        assert_eq!(amount("12,50 €", None).0, dec("12.50"));
+        assert_eq!(amount("1.299", Some("de-DE")).0, dec("1299"));
+        assert_eq!(amount("1299", None), (dec("1299"), None));
+    }
+
+    #[test]
+    fn test_parses_ranges() {
+        let range = parse_price("₦ 12,500 - ₦ 15,000", None, None).unwrap();
+        assert_eq!((range.min, range.max), (dec("12500"), dec("15000")));
+        assert_eq!(range.currency, Some(Currency::NGN));
+        assert!(range.is_range());
+
+        let range = parse_price("$20.00 to $30.00", None, None).unwrap();
+        assert_eq!((range.min, range.max), (dec("20.00"), dec("30.00")));
+        assert!(!parse_price("$9.99", None, None).unwrap().is_range());
+    }
+
+    #[test]
+    fn test_reports_ambiguity() {
+        // Three digits after the locale's decimal comma
+        assert!(parse_price("1,299", Some("de-DE"), Some(Currency::EUR)).is_err());
+        // Kuwaiti dinar has three minor units: "1,250" reads two ways without a locale
+        let kwd = Currency::from_str("KWD").unwrap();
+        assert!(parse_price("1,250", None, Some(kwd)).is_err());
+        assert_eq!(
+            parse_price("1,250", Some("en-US"), Some(kwd)).unwrap().min,
+            dec("1250")
+        );
+        assert!(parse_price("¥1.5", None, None).is_err());
+        assert!(parse_price("Was $20 now $15", None, None).is_err());
+        assert!(parse_price("$10 - €12", None, None).is_err());
+        assert!(parse_price("$1,29,9.99", None, None).is_err());
+        assert!(parse_price("Free", None, None).is_err());
+    }
+}
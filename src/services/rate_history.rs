@@ -0,0 +1,525 @@
+//! Daily exchange rate snapshots and rate-correct history conversion.
+//!
+//! Every fetch of live rates is saved as the day's snapshot, and each
+//! price history row stores the USD rate it was recorded with. A price
+//! from March can then be converted at March's rate ("observation") or at
+//! today's ("current"); converting an old NGN price at today's rate would
+//! turn a devaluation into an apparent price drop. The backfill job loads
+//! the ECB reference rate history and fills rows recorded without a rate.
+
+use super::currency::{Currency, CurrencyService, ExchangeRates, UsdRate};
+use super::money::Money;
+use super::rate_providers::{self, ProviderRates};
+use crate::db::{self, PriceHistoryRecord, RateSnapshot};
+use crate::utils::fetch_with_retry;
+use crate::AppError;
+use chrono::{Duration, NaiveDate};
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+
+/// Snapshots saved per query by the backfill.
+const BACKFILL_BATCH_SIZE: usize = 5000;
+
+/// Which day's rates a history conversion uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryRates {
+    /// Rates of the day each price was recorded
+    Observation,
+    /// Today's rates for every price
+    Current,
+}
+
+/// A history price converted to another currency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryConversion {
+    /// Converted price (sent as `amount` and `currency`)
+    #[serde(flatten)]
+    pub price: Money,
+    /// Units of the converted currency per unit of the recorded currency
+    pub rate: Decimal,
+    pub rates: HistoryRates,
+    /// Day of the oldest rate used
+    pub rate_date: NaiveDate,
+}
+
+/// Outcome of a backfill run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BackfillReport {
+    /// Days of rates in the history feed
+    pub days: usize,
+    /// Snapshots added (days already snapshotted are kept)
+    pub snapshots_saved: u64,
+    /// History rows that received an exchange rate
+    pub rows_updated: u64,
+    /// History rows still without one (no snapshot close enough to their date)
+    pub rows_without_rate: i64,
+}
+
+/// Daily rates per USD of several currencies.
+#[derive(Debug, Clone, Default)]
+pub struct RateTimeline {
+    rates: HashMap<String, BTreeMap<NaiveDate, Decimal>>,
+    max_age_days: i64,
+}
+
+impl RateTimeline {
+    /// Builds a timeline from snapshots.
+    ///
+    /// # Arguments
+    /// * `snapshots` - Snapshots of any currencies and days
+    /// * `max_age_days` - Oldest snapshot accepted for a day (covers weekends and holidays)
+    pub fn new(snapshots: &[RateSnapshot], max_age_days: i64) -> Self {
+        let mut rates: HashMap<String, BTreeMap<NaiveDate, Decimal>> = HashMap::new();
+        for snapshot in snapshots {
+            rates
+                .entry(snapshot.currency.clone())
+                .or_default()
+                .insert(snapshot.rate_date, snapshot.rate_per_usd);
+        }
+        Self {
+            rates,
+            max_age_days,
+        }
+    }
+
+    /// Returns the rate per USD in effect on a day.
+    ///
+    /// # Returns
+    /// * `Some((NaiveDate, Decimal))` - Day of the latest snapshot on or before `date`, and its rate
+    /// * `None` - No snapshot within `max_age_days` of `date`
+    pub fn rate_on(&self, currency: &str, date: NaiveDate) -> Option<(NaiveDate, Decimal)> {
+        if currency == Currency::USD.code() {
+            return Some((date, Decimal::ONE));
+        }
+        let (day, rate) = self.rates.get(currency)?.range(..=date).next_back()?;
+        (date - *day <= Duration::days(self.max_age_days) && !rate.is_zero())
+            .then_some((*day, *rate))
+    }
+}
+
+/// Turns live rates into the day's snapshots.
+///
+/// Fallback rates are skipped: they are not rates of the day.
+///
+/// # Arguments
+/// * `rates` - Merged provider rates
+///
+/// # Returns
+/// * One snapshot per currency, dated by its provider's publication time
+pub fn snapshots_from_rates(rates: &ExchangeRates) -> Vec<RateSnapshot> {
+    rates
+        .rates
+        .iter()
+        .filter(|(code, rate)| code.len() == 3 && **rate > Decimal::ZERO)
+        .filter_map(|(code, rate)| {
+            let (provider, published_at) = match rates.sources.get(code) {
+                Some(source) if source.provider == "fallback" => return None,
+                Some(source) => (source.provider.clone(), source.published_at),
+                None => ("unknown".to_string(), rates.updated_at),
+            };
+            Some(RateSnapshot {
+                rate_date: published_at.date_naive(),
+                currency: code.clone(),
+                rate_per_usd: *rate,
+                provider,
+                published_at,
+            })
+        })
+        .collect()
+}
+
+/// Turns one day of provider rates into snapshots.
+///
+/// # Returns
+/// * USD-based snapshots, or none when the provider has no USD rate
+fn snapshots_from_provider(rates: ProviderRates) -> Vec<RateSnapshot> {
+    let Some(rates) = rates.rebased_to_usd() else {
+        return Vec::new();
+    };
+    let rate_date = rates.published_at.date_naive();
+    rates
+        .rates
+        .iter()
+        .filter(|(code, rate)| code.len() == 3 && **rate > Decimal::ZERO)
+        .map(|(code, rate)| RateSnapshot {
+            rate_date,
+            currency: code.clone(),
+            rate_per_usd: *rate,
+            provider: rates.provider.as_str().to_string(),
+            published_at: rates.published_at,
+        })
+        .collect()
+}
+
+/// Saves live rates as the day's snapshot, replacing an earlier one.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `rates` - Merged provider rates
+///
+/// # Returns
+/// * `Ok(u64)` - Number of snapshots written
+/// * `Err(AppError)` - Database error
+pub async fn record_snapshots(pool: &PgPool, rates: &ExchangeRates) -> Result<u64, AppError> {
+    let snapshots = snapshots_from_rates(rates);
+    if snapshots.is_empty() {
+        return Ok(0);
+    }
+    db::save_rate_snapshots(pool, &snapshots, true).await
+}
+
+/// Returns the USD rate to store with a history row.
+///
+/// # Arguments
+/// * `usd_rate` - Rate the listing was normalized to USD with
+///
+/// # Returns
+/// * `Some(Decimal)` - USD per unit of the listing's currency
+/// * `None` - No rate or only a fallback rate (the row is left for the backfill)
+pub fn history_rate(usd_rate: Option<&UsdRate>) -> Option<Decimal> {
+    usd_rate
+        .filter(|usd_rate| usd_rate.source.provider != "fallback")
+        .map(|usd_rate| usd_rate.rate)
+}
+
+/// Converts a history price at the rates of the day it was recorded.
+///
+/// The row's stored rate is used for its own currency; snapshots supply
+/// the rest.
+///
+/// # Returns
+/// * `Some(HistoryConversion)` - Converted price
+/// * `None` - No snapshot close enough to the row's date
+pub fn convert_observed(
+    record: &PriceHistoryRecord,
+    target: &Currency,
+    timeline: &RateTimeline,
+) -> Option<HistoryConversion> {
+    let date = record.scraped_at.date_naive();
+    let (from_date, usd_per_unit) = match record.exchange_rate {
+        Some(rate) => (date, rate),
+        None => {
+            let (day, rate_per_usd) = timeline.rate_on(record.price.currency().code(), date)?;
+            (day, Decimal::ONE / rate_per_usd)
+        }
+    };
+    let (to_date, to_rate) = timeline.rate_on(target.code(), date)?;
+
+    converted(
+        record,
+        target,
+        usd_per_unit * to_rate,
+        HistoryRates::Observation,
+        from_date.min(to_date),
+    )
+}
+
+/// Converts a history price at current rates.
+///
+/// # Returns
+/// * `Some(HistoryConversion)` - Converted price
+/// * `None` - No rate for the recorded or target currency
+pub fn convert_current(
+    record: &PriceHistoryRecord,
+    target: &Currency,
+    rates: &ExchangeRates,
+) -> Option<HistoryConversion> {
+    let from = record.price.currency();
+    let (from_rate, from_source) = rates.rate_per_usd(&from)?;
+    let (to_rate, to_source) = rates.rate_per_usd(target)?;
+    let rate_date = from_source.published_at.min(to_source.published_at);
+
+    converted(
+        record,
+        target,
+        to_rate / from_rate,
+        HistoryRates::Current,
+        rate_date.date_naive(),
+    )
+}
+
+fn converted(
+    record: &PriceHistoryRecord,
+    target: &Currency,
+    rate: Decimal,
+    rates: HistoryRates,
+    rate_date: NaiveDate,
+) -> Option<HistoryConversion> {
+    let price = record.price;
+    if price.currency() == *target {
+        return Some(HistoryConversion {
+            price,
+            rate: Decimal::ONE,
+            rates,
+            rate_date: record.scraped_at.date_naive(),
+        });
+    }
+    Some(HistoryConversion {
+        price: price.exchange(rate, *target),
+        rate,
+        rates,
+        rate_date,
+    })
+}
+
+/// Sets `converted` on history rows.
+///
+/// Rows that cannot be converted at the requested rates are left without a
+/// conversion rather than converted at other dates' rates.
+///
+/// # Arguments
+/// * `records` - History rows
+/// * `target` - Currency to convert to
+/// * `rates` - Observation-date or current rates
+/// * `pool` - Database pool with the rate snapshots
+/// * `currency_service` - Source of current rates
+/// * `max_age_days` - Oldest snapshot accepted for a row
+///
+/// # Returns
+/// * `Ok(())` - Rows converted where possible
+/// * `Err(AppError)` - Database or rate lookup failure
+pub async fn convert_history(
+    records: &mut [PriceHistoryRecord],
+    target: &Currency,
+    rates: HistoryRates,
+    pool: &PgPool,
+    currency_service: &CurrencyService,
+    max_age_days: i32,
+) -> Result<(), AppError> {
+    let (Some(first), Some(last)) = (
+        records.iter().map(|r| r.scraped_at.date_naive()).min(),
+        records.iter().map(|r| r.scraped_at.date_naive()).max(),
+    ) else {
+        return Ok(());
+    };
+
+    match rates {
+        HistoryRates::Current => {
+            let current = currency_service.get_exchange_rates().await?;
+            for record in records.iter_mut() {
+                record.converted = convert_current(record, target, &current);
+            }
+        }
+        HistoryRates::Observation => {
+            let mut codes: Vec<String> = records
+                .iter()
+                .map(|r| r.price.currency().code().to_string())
+                .collect();
+            codes.push(target.code().to_string());
+            codes.sort();
+            codes.dedup();
+
+            let from = first - Duration::days(max_age_days as i64);
+            let snapshots = db::get_rate_snapshots(pool, &codes, from, last).await?;
+            let timeline = RateTimeline::new(&snapshots, max_age_days as i64);
+            for record in records.iter_mut() {
+                record.converted = convert_observed(record, target, &timeline);
+            }
+        }
+    }
+
+    let unconverted = records.iter().filter(|r| r.converted.is_none()).count();
+    if unconverted > 0 {
+        tracing::debug!(
+            currency = %target,
+            rates = ?rates,
+            unconverted,
+            "History rows without rates for conversion"
+        );
+    }
+    Ok(())
+}
+
+/// Loads historical reference rates and fills history rows recorded without a rate.
+///
+/// Days already snapshotted keep their live rates. The ECB history covers
+/// about 30 currencies (not NGN); rows in other currencies are filled only
+/// from live snapshots.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `client` - HTTP client
+/// * `history_url` - ECB history feed (e.g., `eurofxref-hist.xml`)
+/// * `since` - Skip rates published before this day
+/// * `max_age_days` - Oldest snapshot accepted for a row
+///
+/// # Returns
+/// * `Ok(BackfillReport)` - Counts of saved snapshots and updated rows
+/// * `Err(AppError)` - Feed or database failure
+pub async fn backfill(
+    pool: &PgPool,
+    client: &Client,
+    history_url: &str,
+    since: Option<NaiveDate>,
+    max_age_days: i32,
+) -> Result<BackfillReport, AppError> {
+    let body = fetch_with_retry(client, history_url, 3).await?;
+    let days: Vec<ProviderRates> = rate_providers::parse_ecb_history(&body)?
+        .into_iter()
+        .filter(|day| since.is_none_or(|since| day.published_at.date_naive() >= since))
+        .collect();
+
+    let mut report = BackfillReport {
+        days: days.len(),
+        ..Default::default()
+    };
+    let snapshots: Vec<RateSnapshot> = days.into_iter().flat_map(snapshots_from_provider).collect();
+    for batch in snapshots.chunks(BACKFILL_BATCH_SIZE) {
+        report.snapshots_saved += db::save_rate_snapshots(pool, batch, false).await?;
+    }
+
+    report.rows_updated = db::backfill_history_exchange_rates(pool, max_age_days).await?;
+    report.rows_without_rate = db::count_history_without_exchange_rate(pool).await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::rate_providers::RateSource;
+    use chrono::{DateTime, TimeZone, Utc};
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn day(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        day(value).and_hms_opt(12, 0, 0).unwrap().and_utc()
+    }
+
+    fn snapshot(date: &str, currency: &str, rate: &str) -> RateSnapshot {
+        RateSnapshot {
+            rate_date: day(date),
+            currency: currency.to_string(),
+            rate_per_usd: dec(rate),
+            provider: "exchangerate_api".to_string(),
+            published_at: at(date),
+        }
+    }
+
+    fn record(currency: &str, price: &str, date: &str, rate: Option<&str>) -> PriceHistoryRecord {
+        PriceHistoryRecord {
+            id: Uuid::nil(),
+            site: "Jumia".to_string(),
+            product_title: "Tecno Spark 10".to_string(),
+            price: Money::new(dec(price), currency.parse().unwrap()),
+            price_usd: dec(price),
+            exchange_rate: rate.map(dec),
+            product_link: "https://www.jumia.com.ng/spark-10".to_string(),
+            image_url: None,
+            search_query: "tecno spark 10".to_string(),
+            product_id: None,
+            scraped_at: at(date),
+            converted: None,
+        }
+    }
+
+    #[test]
+    fn test_timeline_uses_latest_snapshot_within_max_age() {
+        let timeline = RateTimeline::new(
+            &[
+                snapshot("2024-01-05", "NGN", "900"),
+                snapshot("2024-01-08", "NGN", "950"),
+            ],
+            3,
+        );
+
+        // Weekend takes Friday's rate
+        assert_eq!(
+            timeline.rate_on("NGN", day("2024-01-07")),
+            Some((day("2024-01-05"), dec("900")))
+        );
+        assert_eq!(
+            timeline.rate_on("NGN", day("2024-01-08")),
+            Some((day("2024-01-08"), dec("950")))
+        );
+        assert_eq!(timeline.rate_on("NGN", day("2024-01-04")), None);
+        assert_eq!(timeline.rate_on("NGN", day("2024-01-20")), None);
+        assert_eq!(
+            timeline.rate_on("USD", day("2024-01-04")),
+            Some((day("2024-01-04"), Decimal::ONE))
+        );
+    }
+
+    #[test]
+    fn test_observation_and_current_rates_differ_after_devaluation() {
+        // 900,000 NGN recorded at 900 NGN/USD, before the naira fell to 1,500
+        let old = record("NGN", "900000", "2024-01-05", Some("0.001111111111"));
+        let unrated = record("NGN", "900000", "2024-01-05", None);
+        let timeline = RateTimeline::new(&[snapshot("2024-01-05", "NGN", "900")], 7);
+
+        let observed = convert_observed(&old, &Currency::USD, &timeline).unwrap();
+        assert_eq!(observed.price.amount(), dec("1000.00"));
+        assert_eq!(observed.rates, HistoryRates::Observation);
+        let from_snapshot = convert_observed(&unrated, &Currency::USD, &timeline).unwrap();
+        assert_eq!(from_snapshot.price.amount(), dec("1000.00"));
+        assert_eq!(from_snapshot.rate_date, day("2024-01-05"));
+        // No EUR snapshot that week: left unconverted rather than guessed
+        assert!(convert_observed(&old, &Currency::EUR, &timeline).is_none());
+
+        let current = ExchangeRates {
+            base: "USD".to_string(),
+            rates: HashMap::from([("NGN".to_string(), dec("1500"))]),
+            updated_at: at("2024-06-03"),
+            sources: HashMap::from([(
+                "NGN".to_string(),
+                RateSource {
+                    provider: "exchangerate_api".to_string(),
+                    published_at: at("2024-06-03"),
+                },
+            )]),
+            outliers: Vec::new(),
+        };
+        let today = convert_current(&old, &Currency::USD, &current).unwrap();
+        assert_eq!(today.price.amount(), dec("600.00"));
+        assert_eq!(today.rates, HistoryRates::Current);
+    }
+
+    #[test]
+    fn test_snapshots_skip_fallback_rates() {
+        let rates = ExchangeRates {
+            base: "USD".to_string(),
+            rates: HashMap::from([
+                ("NGN".to_string(), dec("1500")),
+                ("XAF".to_string(), dec("600")),
+            ]),
+            updated_at: Utc.with_ymd_and_hms(2024, 6, 3, 18, 0, 0).unwrap(),
+            sources: HashMap::from([
+                (
+                    "NGN".to_string(),
+                    RateSource {
+                        provider: "exchangerate_api".to_string(),
+                        published_at: at("2024-06-03"),
+                    },
+                ),
+                (
+                    "XAF".to_string(),
+                    RateSource {
+                        provider: "fallback".to_string(),
+                        published_at: at("2023-06-01"),
+                    },
+                ),
+            ]),
+            outliers: Vec::new(),
+        };
+
+        let snapshots = snapshots_from_rates(&rates);
+        assert_eq!(snapshots, vec![snapshot("2024-06-03", "NGN", "1500")]);
+        let history = |code: &str| {
+            let usd_rate = rates.usd_rate(&code.parse().unwrap());
+            history_rate(usd_rate.as_ref())
+        };
+        assert_eq!(history("NGN"), Some(Decimal::ONE / dec("1500")));
+        assert_eq!(history("XAF"), None);
+    }
+}
@@ -0,0 +1,503 @@
+//! Exchange rate providers and the cross-checked rate chain.
+//!
+//! Each provider is a parser over a configurable URL (so a local stand-in
+//! can serve it in development): ExchangeRate-API and Open Exchange Rates
+//! publish USD-based JSON, the ECB publishes a EUR-based daily XML feed.
+//! The chain fetches every provider, rebases them to USD, takes each rate
+//! from the first provider in chain order that agrees with the others, and
+//! records which provider and publication time each rate came from.
+
+use crate::AppError;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Format of an exchange rate feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    /// ExchangeRate-API JSON (v4 `rates` or v6 `conversion_rates`)
+    ExchangeRateApi,
+    /// Open Exchange Rates `latest.json`
+    OpenExchangeRates,
+    /// European Central Bank `eurofxref-daily.xml` (EUR-based)
+    Ecb,
+}
+
+impl ProviderKind {
+    /// Returns the provider name used in configuration and rate sources.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ExchangeRateApi => "exchangerate_api",
+            Self::OpenExchangeRates => "open_exchange_rates",
+            Self::Ecb => "ecb",
+        }
+    }
+}
+
+impl FromStr for ProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "exchangerate_api" => Ok(Self::ExchangeRateApi),
+            "open_exchange_rates" => Ok(Self::OpenExchangeRates),
+            "ecb" => Ok(Self::Ecb),
+            other => Err(format!("Unknown exchange rate provider: {}", other)),
+        }
+    }
+}
+
+/// An exchange rate provider: a feed format and the URL serving it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RateProvider {
+    pub kind: ProviderKind,
+    pub url: String,
+}
+
+/// Rates published by one provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderRates {
+    pub provider: ProviderKind,
+    /// Currency the rates are quoted against
+    pub base: String,
+    /// Units of each currency per unit of `base`
+    pub rates: HashMap<String, Decimal>,
+    /// When the provider published the rates
+    pub published_at: DateTime<Utc>,
+}
+
+/// Where a rate came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateSource {
+    /// Provider name (e.g., "ecb"), or "fallback" for the built-in rates
+    pub provider: String,
+    pub published_at: DateTime<Utc>,
+}
+
+/// A provider rate that disagreed with the other providers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateOutlier {
+    pub currency: String,
+    pub provider: String,
+    /// The provider's rate per USD
+    pub rate: Decimal,
+    /// Median rate per USD across providers
+    pub median: Decimal,
+}
+
+/// USD-based rates merged from several providers.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MergedRates {
+    /// Units of each currency per USD
+    pub rates: HashMap<String, Decimal>,
+    /// Provider and publication time of each rate
+    pub sources: HashMap<String, RateSource>,
+    /// Rates rejected by the cross-check
+    pub outliers: Vec<RateOutlier>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExchangeRateApiResponse {
+    #[serde(default)]
+    result: Option<String>,
+    #[serde(alias = "base_code")]
+    base: String,
+    #[serde(alias = "conversion_rates")]
+    rates: HashMap<String, f64>,
+    #[serde(default, alias = "time_last_update_unix")]
+    time_last_updated: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenExchangeRatesResponse {
+    base: String,
+    rates: HashMap<String, f64>,
+    timestamp: i64,
+}
+
+impl RateProvider {
+    /// Fetches and parses the provider's feed.
+    ///
+    /// # Arguments
+    /// * `client` - HTTP client
+    ///
+    /// # Returns
+    /// * `Ok(ProviderRates)` - Rates as published (not yet rebased to USD)
+    /// * `Err(AppError)` - Network error, HTTP error or unparseable feed
+    pub async fn fetch(&self, client: &Client) -> Result<ProviderRates, AppError> {
+        let name = self.kind.as_str();
+        let response = client.get(&self.url).send().await.map_err(|e| {
+            AppError::Network(format!("Exchange rate provider {} failed: {}", name, e))
+        })?;
+        if !response.status().is_success() {
+            return Err(AppError::Network(format!(
+                "Exchange rate provider {} returned {}",
+                name,
+                response.status()
+            )));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to read {} response: {}", name, e)))?;
+
+        self.parse(&body)
+    }
+
+    /// Parses a feed body in the provider's format.
+    pub fn parse(&self, body: &str) -> Result<ProviderRates, AppError> {
+        match self.kind {
+            ProviderKind::ExchangeRateApi => parse_exchange_rate_api(body),
+            ProviderKind::OpenExchangeRates => parse_open_exchange_rates(body),
+            ProviderKind::Ecb => parse_ecb(body),
+        }
+    }
+}
+
+impl ProviderRates {
+    /// Re-expresses the rates as units per USD.
+    ///
+    /// # Returns
+    /// * `Some(ProviderRates)` - USD-based rates (including the old base at 1 / USD rate)
+    /// * `None` - The feed has no USD rate to rebase with
+    pub fn rebased_to_usd(mut self) -> Option<Self> {
+        if self.base == "USD" {
+            self.rates.insert("USD".to_string(), Decimal::ONE);
+            return Some(self);
+        }
+
+        let usd = self
+            .rates
+            .get("USD")
+            .copied()
+            .filter(|rate| !rate.is_zero())?;
+        let mut rates: HashMap<String, Decimal> = self
+            .rates
+            .iter()
+            .map(|(code, rate)| (code.clone(), *rate / usd))
+            .collect();
+        rates.insert(self.base.clone(), Decimal::ONE / usd);
+        rates.insert("USD".to_string(), Decimal::ONE);
+
+        self.base = "USD".to_string();
+        self.rates = rates;
+        Some(self)
+    }
+}
+
+/// Merges USD-based provider rates, in chain order, into one rate table.
+///
+/// For each currency, the median of the providers' rates is the reference;
+/// a rate more than `tolerance_percent` away from it is an outlier when at
+/// least two other providers agree with each other. The first provider in
+/// chain order that is not an outlier supplies the rate.
+///
+/// # Arguments
+/// * `chain` - USD-based rates, highest priority first
+/// * `tolerance_percent` - Largest accepted deviation from the median, in percent
+///
+/// # Returns
+/// * Merged rates with their sources and the rejected outliers
+pub fn merge(chain: &[ProviderRates], tolerance_percent: Decimal) -> MergedRates {
+    let mut merged = MergedRates::default();
+    let mut codes: Vec<&String> = chain.iter().flat_map(|p| p.rates.keys()).collect();
+    codes.sort();
+    codes.dedup();
+
+    for code in codes {
+        let quotes: Vec<(&ProviderRates, Decimal)> = chain
+            .iter()
+            .filter_map(|provider| Some((provider, *provider.rates.get(code)?)))
+            .filter(|(_, rate)| *rate > Decimal::ZERO)
+            .collect();
+        if quotes.is_empty() {
+            continue;
+        }
+
+        let median = median(quotes.iter().map(|(_, rate)| *rate).collect());
+        let is_outlier = |rate: Decimal| {
+            quotes.len() >= 3
+                && ((rate - median).abs() / median) * Decimal::ONE_HUNDRED > tolerance_percent
+        };
+
+        for (provider, rate) in &quotes {
+            if is_outlier(*rate) {
+                tracing::warn!(
+                    currency = %code,
+                    provider = provider.provider.as_str(),
+                    rate = %rate,
+                    median = %median,
+                    "Exchange rate disagrees with other providers"
+                );
+                merged.outliers.push(RateOutlier {
+                    currency: code.clone(),
+                    provider: provider.provider.as_str().to_string(),
+                    rate: *rate,
+                    median,
+                });
+            }
+        }
+
+        if let Some((provider, rate)) = quotes.iter().find(|(_, rate)| !is_outlier(*rate)) {
+            merged.rates.insert(code.clone(), *rate);
+            merged.sources.insert(
+                code.clone(),
+                RateSource {
+                    provider: provider.provider.as_str().to_string(),
+                    published_at: provider.published_at,
+                },
+            );
+        }
+    }
+
+    merged
+}
+
+fn median(mut values: Vec<Decimal>) -> Decimal {
+    values.sort();
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / Decimal::TWO
+    } else {
+        values[mid]
+    }
+}
+
+/// Converts JSON float rates to Decimal, dropping values that cannot be represented.
+fn decimal_rates(rates: HashMap<String, f64>) -> HashMap<String, Decimal> {
+    rates
+        .into_iter()
+        .filter_map(|(code, rate)| Some((code.to_uppercase(), Decimal::from_f64_retain(rate)?)))
+        .collect()
+}
+
+fn parse_exchange_rate_api(body: &str) -> Result<ProviderRates, AppError> {
+    let response: ExchangeRateApiResponse = serde_json::from_str(body)
+        .map_err(|e| AppError::Parse(format!("Invalid ExchangeRate-API response: {}", e)))?;
+    if let Some(result) = response.result.as_deref().filter(|r| *r != "success") {
+        return Err(AppError::Parse(format!(
+            "ExchangeRate-API returned result {}",
+            result
+        )));
+    }
+
+    Ok(ProviderRates {
+        provider: ProviderKind::ExchangeRateApi,
+        base: response.base.to_uppercase(),
+        rates: decimal_rates(response.rates),
+        published_at: response
+            .time_last_updated
+            .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+            .unwrap_or_else(Utc::now),
+    })
+}
+
+fn parse_open_exchange_rates(body: &str) -> Result<ProviderRates, AppError> {
+    let response: OpenExchangeRatesResponse = serde_json::from_str(body)
+        .map_err(|e| AppError::Parse(format!("Invalid Open Exchange Rates response: {}", e)))?;
+    let published_at = Utc
+        .timestamp_opt(response.timestamp, 0)
+        .single()
+        .ok_or_else(|| AppError::Parse("Invalid Open Exchange Rates timestamp".to_string()))?;
+
+    Ok(ProviderRates {
+        provider: ProviderKind::OpenExchangeRates,
+        base: response.base.to_uppercase(),
+        rates: decimal_rates(response.rates),
+        published_at,
+    })
+}
+
+/// Parses the ECB daily feed: `<Cube time='2024-01-05'>` holding
+/// `<Cube currency='USD' rate='1.0946'/>` entries, rates per EUR.
+fn parse_ecb(body: &str) -> Result<ProviderRates, AppError> {
+    parse_ecb_history(body)?
+        .into_iter()
+        .max_by_key(|day| day.published_at)
+        .ok_or_else(|| AppError::Parse("ECB feed has no rates".to_string()))
+}
+
+/// Parses an ECB feed with one or more days (`eurofxref-hist.xml`,
+/// `eurofxref-hist-90d.xml` or the daily feed).
+///
+/// # Arguments
+/// * `body` - Feed XML
+///
+/// # Returns
+/// * `Ok(Vec<ProviderRates>)` - EUR-based rates of each day, in feed order
+/// * `Err(AppError)` - Invalid date or rate, or a feed without rates
+pub fn parse_ecb_history(body: &str) -> Result<Vec<ProviderRates>, AppError> {
+    let mut days = Vec::new();
+    let mut current: Option<ProviderRates> = None;
+
+    for element in body.split("<Cube").skip(1) {
+        let element = element.split('>').next().unwrap_or("");
+        if let Some(time) = xml_attribute(element, "time") {
+            let date = NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                .map_err(|e| AppError::Parse(format!("Invalid ECB date {}: {}", time, e)))?;
+            // Reference rates are published around 16:00 CET
+            let published_at = date
+                .and_hms_opt(15, 0, 0)
+                .map(|t| t.and_utc())
+                .ok_or_else(|| AppError::Parse(format!("Invalid ECB date {}", time)))?;
+            days.extend(current.take());
+            current = Some(ProviderRates {
+                provider: ProviderKind::Ecb,
+                base: "EUR".to_string(),
+                rates: HashMap::new(),
+                published_at,
+            });
+        }
+        if let (Some(currency), Some(rate)) = (
+            xml_attribute(element, "currency"),
+            xml_attribute(element, "rate"),
+        ) {
+            let rate = Decimal::from_str(rate)
+                .map_err(|e| AppError::Parse(format!("Invalid ECB rate {}: {}", rate, e)))?;
+            let day = current
+                .as_mut()
+                .ok_or_else(|| AppError::Parse("ECB feed has no date".to_string()))?;
+            day.rates.insert(currency.to_uppercase(), rate);
+        }
+    }
+    days.extend(current);
+    days.retain(|day| !day.rates.is_empty());
+
+    if days.is_empty() {
+        return Err(AppError::Parse("ECB feed has no rates".to_string()));
+    }
+    Ok(days)
+}
+
+/// Reads `name='value'` or `name="value"` from an XML start tag.
+fn xml_attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let start = element.find(&format!("{}=", name))? + name.len() + 1;
+    let quote = element[start..].chars().next()?;
+    if quote != '\'' && quote != '"' {
+        return None;
+    }
+    let value = &element[start + 1..];
+    Some(&value[..value.find(quote)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ECB_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01">
+  <gesmes:subject>Reference rates</gesmes:subject>
+  <Cube>
+    <Cube time='2024-01-05'>
+      <Cube currency='USD' rate='1.0946'/>
+      <Cube currency='JPY' rate='158.13'/>
+      <Cube currency='GBP' rate='0.86075'/>
+    </Cube>
+  </Cube>
+</gesmes:Envelope>"#;
+
+    fn provider(kind: ProviderKind) -> RateProvider {
+        RateProvider {
+            kind,
+            url: "http://localhost/rates".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_feeds() {
+        let v4 = provider(ProviderKind::ExchangeRateApi)
+            .parse(r#"{"base":"USD","time_last_updated":1704412800,"rates":{"USD":1,"NGN":905.5}}"#)
+            .unwrap();
+        assert_eq!(v4.rates["NGN"], Decimal::from_str("905.5").unwrap());
+        assert_eq!(v4.published_at.timestamp(), 1704412800);
+
+        let v6 = provider(ProviderKind::ExchangeRateApi)
+            .parse(r#"{"result":"success","base_code":"USD","conversion_rates":{"EUR":0.91}}"#)
+            .unwrap();
+        assert_eq!(v6.base, "USD");
+        assert!(provider(ProviderKind::ExchangeRateApi)
+            .parse(r#"{"result":"error","base_code":"USD","conversion_rates":{}}"#)
+            .is_err());
+
+        let oxr = provider(ProviderKind::OpenExchangeRates)
+            .parse(r#"{"timestamp":1704412800,"base":"USD","rates":{"GBP":0.787}}"#)
+            .unwrap();
+        assert_eq!(oxr.provider, ProviderKind::OpenExchangeRates);
+
+        let ecb = provider(ProviderKind::Ecb).parse(ECB_FEED).unwrap();
+        assert_eq!(ecb.base, "EUR");
+        assert_eq!(ecb.rates.len(), 3);
+        assert_eq!(ecb.published_at.date_naive().to_string(), "2024-01-05");
+    }
+
+    #[test]
+    fn test_ecb_rebased_to_usd() {
+        let usd = provider(ProviderKind::Ecb)
+            .parse(ECB_FEED)
+            .unwrap()
+            .rebased_to_usd()
+            .unwrap();
+
+        assert_eq!(usd.base, "USD");
+        assert_eq!(usd.rates["USD"], Decimal::ONE);
+        // 1 EUR = 1.0946 USD, so 1 USD = 0.9136 EUR
+        assert_eq!(
+            usd.rates["EUR"].round_dp(4),
+            Decimal::from_str("0.9136").unwrap()
+        );
+        assert_eq!(
+            usd.rates["JPY"].round_dp(2),
+            Decimal::from_str("144.46").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_ecb_history() {
+        let history = r#"<Cube>
+  <Cube time="2024-01-05"><Cube currency="USD" rate="1.0946"/><Cube currency="GBP" rate="0.86075"/></Cube>
+  <Cube time="2024-01-04"><Cube currency="USD" rate="1.0953"/></Cube>
+</Cube>"#;
+        let days = parse_ecb_history(history).unwrap();
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[1].published_at.date_naive().to_string(), "2024-01-04");
+        assert_eq!(days[1].rates["USD"], Decimal::from_str("1.0953").unwrap());
+        // The daily parser keeps the latest day
+        let latest = provider(ProviderKind::Ecb).parse(history).unwrap();
+        assert_eq!(latest.rates.len(), 2);
+        assert!(parse_ecb_history("<Cube></Cube>").is_err());
+    }
+
+    #[test]
+    fn test_merge_prefers_chain_order_and_rejects_outliers() {
+        let rates = |kind: ProviderKind, ngn: &str, gbp: Option<&str>| ProviderRates {
+            provider: kind,
+            base: "USD".to_string(),
+            rates: [Some(("NGN", ngn)), gbp.map(|gbp| ("GBP", gbp))]
+                .into_iter()
+                .flatten()
+                .map(|(code, rate)| (code.to_string(), Decimal::from_str(rate).unwrap()))
+                .collect(),
+            published_at: Utc::now(),
+        };
+        let chain = [
+            // Stale naira rate from the first provider
+            rates(ProviderKind::ExchangeRateApi, "770", Some("0.79")),
+            rates(ProviderKind::OpenExchangeRates, "1540", None),
+            rates(ProviderKind::Ecb, "1545", Some("0.788")),
+        ];
+
+        let merged = merge(&chain, Decimal::from(2));
+
+        assert_eq!(merged.rates["NGN"], Decimal::from(1540));
+        assert_eq!(merged.sources["NGN"].provider, "open_exchange_rates");
+        assert_eq!(merged.outliers.len(), 1);
+        assert_eq!(merged.outliers[0].provider, "exchangerate_api");
+        // Two providers cannot outvote each other: chain order decides
+        assert_eq!(merged.sources["GBP"].provider, "exchangerate_api");
+    }
+}
@@ -0,0 +1,202 @@
+//! Background exchange rate refresher.
+//!
+//! Refreshes the cached rates on a schedule well inside the cache TTL, so
+//! no request waits on the providers. Failed refreshes are retried with
+//! exponential backoff while the cached (or last good) rates keep serving.
+//! Rates that move more than the alert threshold between two fetches are
+//! logged and counted in `exchange_rate_changes_total`.
+
+use super::currency::{CurrencyService, ExchangeRates};
+use crate::config::CurrencyConfig;
+use crate::observability::metrics;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// A rate that moved more than the alert threshold between two fetches.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RateChange {
+    pub currency: String,
+    /// Previous units per USD
+    pub previous: Decimal,
+    /// New units per USD
+    pub current: Decimal,
+    /// Signed change, rounded to 2 decimal places
+    pub change_percent: Decimal,
+}
+
+/// Outcome of a successful refresh.
+#[derive(Debug, Clone)]
+pub struct RateRefresh {
+    pub rates: ExchangeRates,
+    /// Rates that moved more than the alert threshold since the last fetch
+    pub changes: Vec<RateChange>,
+}
+
+/// Finds the rates that moved more than a threshold.
+///
+/// # Arguments
+/// * `previous` - Rates of the last successful fetch
+/// * `current` - Newly fetched rates
+/// * `threshold_percent` - Smallest change reported
+///
+/// # Returns
+/// * Changes ordered by currency code
+pub fn rate_changes(
+    previous: &ExchangeRates,
+    current: &ExchangeRates,
+    threshold_percent: Decimal,
+) -> Vec<RateChange> {
+    let mut changes: Vec<RateChange> = current
+        .rates
+        .iter()
+        .filter_map(|(currency, &rate)| {
+            let before = *previous.rates.get(currency)?;
+            if before.is_zero() {
+                return None;
+            }
+            let change_percent = (rate - before) / before * Decimal::from(100);
+            (change_percent.abs() >= threshold_percent).then(|| RateChange {
+                currency: currency.clone(),
+                previous: before,
+                current: rate,
+                change_percent: change_percent.round_dp(2),
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| a.currency.cmp(&b.currency));
+    changes
+}
+
+/// Logs rate changes and records them as metrics.
+pub fn report_changes(changes: &[RateChange]) {
+    for change in changes {
+        tracing::warn!(
+            currency = %change.currency,
+            previous = %change.previous,
+            current = %change.current,
+            change_percent = %change.change_percent,
+            "Exchange rate moved past the alert threshold"
+        );
+        metrics::record_exchange_rate_change(
+            &change.currency,
+            change.change_percent.to_f64().unwrap_or_default(),
+        );
+    }
+}
+
+/// Delay before the next retry: doubled, up to `max`.
+fn next_backoff(current: Duration, max: Duration) -> Duration {
+    current.saturating_mul(2).min(max)
+}
+
+/// Starts the refresher, unless the refresh interval is 0.
+///
+/// The first refresh runs immediately, so the cache is warm before the
+/// first request.
+///
+/// # Arguments
+/// * `currency_service` - Service whose rates are refreshed
+/// * `config` - Refresh interval and initial retry delay
+///
+/// # Returns
+/// * `Some(JoinHandle)` - The running task
+/// * `None` - Refreshing is disabled
+pub fn spawn(
+    currency_service: Arc<CurrencyService>,
+    config: &CurrencyConfig,
+) -> Option<JoinHandle<()>> {
+    if config.refresh_interval_minutes == 0 {
+        tracing::info!("Background exchange rate refresh disabled");
+        return None;
+    }
+    let interval = Duration::from_secs(config.refresh_interval_minutes * 60);
+    let retry = Duration::from_secs(config.refresh_retry_seconds.max(1));
+
+    Some(tokio::spawn(run(currency_service, interval, retry)))
+}
+
+/// Refreshes rates every `interval`, retrying failures with backoff from `retry`.
+async fn run(currency_service: Arc<CurrencyService>, interval: Duration, retry: Duration) {
+    loop {
+        let mut backoff = retry;
+        loop {
+            match currency_service.refresh_rates().await {
+                Ok(refresh) => {
+                    metrics::record_exchange_rate_refresh(true);
+                    tracing::info!(
+                        currencies = refresh.rates.rates.len(),
+                        alerts = refresh.changes.len(),
+                        "Exchange rates refreshed"
+                    );
+                    break;
+                }
+                Err(e) => {
+                    metrics::record_exchange_rate_refresh(false);
+                    tracing::warn!(
+                        error = %e,
+                        retry_in_seconds = backoff.as_secs(),
+                        "Exchange rate refresh failed, keeping cached rates"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = next_backoff(backoff, interval);
+                }
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    fn rates(entries: &[(&str, &str)]) -> ExchangeRates {
+        ExchangeRates {
+            base: "USD".to_string(),
+            rates: entries
+                .iter()
+                .map(|(code, rate)| (code.to_string(), Decimal::from_str(rate).unwrap()))
+                .collect(),
+            updated_at: chrono::Utc::now(),
+            sources: HashMap::new(),
+            outliers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rate_changes_past_threshold() {
+        let previous = rates(&[("EUR", "0.92"), ("NGN", "1500"), ("GBP", "0.79")]);
+        let current = rates(&[
+            ("EUR", "0.93"),
+            ("NGN", "1650"),
+            ("GBP", "0.75"),
+            ("JPY", "150"),
+        ]);
+
+        let changes = rate_changes(&previous, &current, Decimal::from(5));
+        let moved: Vec<(&str, Decimal)> = changes
+            .iter()
+            .map(|c| (c.currency.as_str(), c.change_percent))
+            .collect();
+        assert_eq!(
+            moved,
+            vec![
+                ("GBP", Decimal::from_str("-5.06").unwrap()),
+                ("NGN", Decimal::from(10)),
+            ]
+        );
+
+        let max = Duration::from_secs(60);
+        assert_eq!(
+            next_backoff(Duration::from_secs(10), max),
+            Duration::from_secs(20)
+        );
+        assert_eq!(next_backoff(Duration::from_secs(40), max), max);
+    }
+}
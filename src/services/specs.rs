@@ -0,0 +1,486 @@
+//! Structured specification extraction from product titles.
+//!
+//! Pulls normalized storage, RAM, screen size, color, battery capacity,
+//! wattage and pack size out of free-text titles so that "8GB RAM 256GB"
+//! and "256GB ROM, 8GB RAM" compare equal, and "128GB" vs "256GB" is
+//! recognized as a different variant rather than a weaker match.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Words that label the GB value next to them as RAM.
+const RAM_LABELS: [&str; 2] = ["ram", "memory"];
+
+/// Words that label the GB value next to them as storage.
+const STORAGE_LABELS: [&str; 6] = ["rom", "storage", "ssd", "hdd", "emmc", "internal"];
+
+/// Color words recognized in titles, mapped to a canonical spelling.
+const COLORS: [(&str, &str); 18] = [
+    ("black", "black"),
+    ("white", "white"),
+    ("silver", "silver"),
+    ("gold", "gold"),
+    ("blue", "blue"),
+    ("red", "red"),
+    ("green", "green"),
+    ("purple", "purple"),
+    ("pink", "pink"),
+    ("gray", "gray"),
+    ("grey", "gray"),
+    ("yellow", "yellow"),
+    ("orange", "orange"),
+    ("graphite", "graphite"),
+    ("midnight", "midnight"),
+    ("starlight", "starlight"),
+    ("titanium", "titanium"),
+    ("violet", "purple"),
+];
+
+/// Specifications extracted from a title or a specification map.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProductSpecs {
+    pub storage_gb: Option<u32>,
+    pub ram_gb: Option<u32>,
+    pub screen_inches: Option<f64>,
+    pub color: Option<String>,
+    pub capacity_mah: Option<u32>,
+    pub wattage_w: Option<u32>,
+    pub pack_size: Option<u32>,
+}
+
+impl ProductSpecs {
+    /// Extracts specifications from a free-text product title.
+    pub fn from_title(title: &str) -> Self {
+        let tokens = tokenize(title);
+        let mut specs = Self::default();
+
+        specs.extract_memory(&tokens);
+
+        for (i, token) in tokens.iter().enumerate() {
+            let next = tokens.get(i + 1).map(String::as_str);
+
+            if let Some(value) = parse_number(token) {
+                match next {
+                    Some("inch" | "inches") if (1.0..=120.0).contains(&value) => {
+                        specs.screen_inches.get_or_insert(value);
+                    }
+                    Some("mah") => {
+                        specs.capacity_mah.get_or_insert(value as u32);
+                    }
+                    Some("w" | "watt" | "watts") => {
+                        specs.wattage_w.get_or_insert(value as u32);
+                    }
+                    Some("pack" | "pk" | "pcs" | "pc" | "pieces" | "count" | "ct") => {
+                        specs.pack_size.get_or_insert(value as u32);
+                    }
+                    _ => {}
+                }
+            }
+
+            // "pack of 6", "set of 6"
+            if matches!(token.as_str(), "pack" | "set") && next == Some("of") {
+                if let Some(value) = tokens.get(i + 2).and_then(|t| parse_number(t)) {
+                    specs.pack_size.get_or_insert(value as u32);
+                }
+            }
+
+            if specs.color.is_none() {
+                specs.color = COLORS
+                    .iter()
+                    .find(|(word, _)| token == word)
+                    .map(|(_, canonical)| canonical.to_string());
+            }
+        }
+
+        specs
+    }
+
+    /// Extracts specifications from a key/value map sent by the extension.
+    ///
+    /// Each entry is parsed as "<value> <key>", so `{"ram": "8GB"}` reads as
+    /// "8GB ram" and is labeled correctly.
+    pub fn from_map(map: &HashMap<String, String>) -> Self {
+        let mut entries: Vec<(&String, &String)> = map.iter().collect();
+        entries.sort();
+
+        entries
+            .into_iter()
+            .map(|(key, value)| Self::from_title(&format!("{} {}", value, key)))
+            .fold(Self::default(), |acc, specs| acc.or(specs))
+    }
+
+    /// Fills fields missing from `self` with values from `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            storage_gb: self.storage_gb.or(other.storage_gb),
+            ram_gb: self.ram_gb.or(other.ram_gb),
+            screen_inches: self.screen_inches.or(other.screen_inches),
+            color: self.color.or(other.color),
+            capacity_mah: self.capacity_mah.or(other.capacity_mah),
+            wattage_w: self.wattage_w.or(other.wattage_w),
+            pack_size: self.pack_size.or(other.pack_size),
+        }
+    }
+
+    /// Assigns GB values to RAM and storage using labels and ordering.
+    ///
+    /// Labeled values win ("8GB RAM"), "8/256GB" is RAM/storage, and the
+    /// smallest/largest unlabeled values fill whatever is left.
+    fn extract_memory(&mut self, tokens: &[String]) {
+        let mut unlabeled: Vec<u32> = Vec::new();
+        let mut consumed_until = 0;
+
+        for (i, token) in tokens.iter().enumerate() {
+            if i < consumed_until {
+                continue;
+            }
+            let Some(value) = parse_number(token) else {
+                continue;
+            };
+
+            // "8/256GB" or "8+256GB": RAM then storage, unit only on the second
+            if tokens.get(i + 1).map(String::as_str) == Some("/") {
+                if let (Some(second), Some(multiplier)) = (
+                    tokens.get(i + 2).and_then(|t| parse_number(t)),
+                    tokens.get(i + 3).and_then(|t| gigabyte_multiplier(t)),
+                ) {
+                    self.ram_gb.get_or_insert(value as u32);
+                    self.storage_gb.get_or_insert((second * multiplier) as u32);
+                    consumed_until = i + 4;
+                    continue;
+                }
+            }
+
+            let Some(multiplier) = tokens.get(i + 1).and_then(|t| gigabyte_multiplier(t)) else {
+                continue;
+            };
+            let gb = (value * multiplier) as u32;
+
+            match memory_label(tokens, i) {
+                Some(MemoryLabel::Ram) => {
+                    self.ram_gb.get_or_insert(gb);
+                }
+                Some(MemoryLabel::Storage) => {
+                    self.storage_gb.get_or_insert(gb);
+                }
+                None => unlabeled.push(gb),
+            }
+        }
+
+        unlabeled.sort_unstable();
+
+        match (self.ram_gb, self.storage_gb) {
+            (None, None) if unlabeled.len() >= 2 => {
+                self.ram_gb = unlabeled.first().copied().filter(|gb| *gb <= 64);
+                self.storage_gb = unlabeled.last().copied();
+            }
+            (_, None) => self.storage_gb = unlabeled.last().copied(),
+            (None, Some(_)) => {
+                self.ram_gb = unlabeled.first().copied().filter(|gb| *gb <= 64);
+            }
+            _ => {}
+        }
+
+        // RAM larger than storage is a seller typo ("256GB RAM 8GB storage")
+        if let (Some(ram), Some(storage)) = (self.ram_gb, self.storage_gb) {
+            if ram > storage {
+                self.ram_gb = Some(storage);
+                self.storage_gb = Some(ram);
+            }
+        }
+    }
+}
+
+/// Result of comparing source and candidate specifications.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpecComparison {
+    /// Specs present on both sides with equal values (e.g., "storage: 256GB")
+    pub matched: Vec<String>,
+    /// Specs present on both sides with different values (e.g., "storage: 128GB vs 256GB")
+    pub mismatched: Vec<String>,
+}
+
+impl SpecComparison {
+    /// True when at least one spec matched and none conflicted.
+    pub fn confirms_variant(&self) -> bool {
+        !self.matched.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// Compares the specs both sides declare; specs known on one side only are ignored.
+///
+/// # Arguments
+/// * `source` - Specs of the product being compared
+/// * `candidate` - Specs of the candidate listing
+///
+/// # Returns
+/// * Matched and mismatched specs, formatted for logs and explanations
+pub fn compare(source: &ProductSpecs, candidate: &ProductSpecs) -> SpecComparison {
+    let mut comparison = SpecComparison::default();
+
+    let mut check = |name: &str, source: Option<String>, candidate: Option<String>| {
+        if let (Some(s), Some(c)) = (source, candidate) {
+            if s == c {
+                comparison.matched.push(format!("{}: {}", name, s));
+            } else {
+                comparison
+                    .mismatched
+                    .push(format!("{}: {} vs {}", name, c, s));
+            }
+        }
+    };
+
+    check(
+        "storage",
+        source.storage_gb.map(format_gb),
+        candidate.storage_gb.map(format_gb),
+    );
+    check(
+        "ram",
+        source.ram_gb.map(format_gb),
+        candidate.ram_gb.map(format_gb),
+    );
+    // Round to one decimal so 6.1 and 6.10 compare equal
+    check(
+        "screen",
+        source.screen_inches.map(|v| format!("{:.1}in", v)),
+        candidate.screen_inches.map(|v| format!("{:.1}in", v)),
+    );
+    check("color", source.color.clone(), candidate.color.clone());
+    check(
+        "capacity",
+        source.capacity_mah.map(|v| format!("{}mAh", v)),
+        candidate.capacity_mah.map(|v| format!("{}mAh", v)),
+    );
+    check(
+        "wattage",
+        source.wattage_w.map(|v| format!("{}W", v)),
+        candidate.wattage_w.map(|v| format!("{}W", v)),
+    );
+    check(
+        "pack",
+        source.pack_size.map(|v| format!("{}-pack", v)),
+        candidate.pack_size.map(|v| format!("{}-pack", v)),
+    );
+
+    comparison
+}
+
+enum MemoryLabel {
+    Ram,
+    Storage,
+}
+
+/// Finds the RAM/storage label for the GB value whose number is at `i`.
+///
+/// A label right after the unit ("8GB RAM") wins. A label right before the
+/// number ("RAM 8GB") counts only if it does not trail a previous quantity.
+fn memory_label(tokens: &[String], i: usize) -> Option<MemoryLabel> {
+    let classify = |token: &str| {
+        if RAM_LABELS.contains(&token) {
+            Some(MemoryLabel::Ram)
+        } else if STORAGE_LABELS.contains(&token) {
+            Some(MemoryLabel::Storage)
+        } else {
+            None
+        }
+    };
+
+    if let Some(label) = tokens.get(i + 2).and_then(|t| classify(t)) {
+        return Some(label);
+    }
+
+    let before = i.checked_sub(1).and_then(|j| tokens.get(j))?;
+    let trails_quantity = i
+        .checked_sub(2)
+        .and_then(|j| tokens.get(j))
+        .is_some_and(|t| gigabyte_multiplier(t).is_some());
+
+    if trails_quantity {
+        None
+    } else {
+        classify(before)
+    }
+}
+
+/// Returns the GB multiplier for a storage unit token.
+fn gigabyte_multiplier(token: &str) -> Option<f64> {
+    match token {
+        "gb" | "gig" | "gigs" | "gigabyte" | "gigabytes" => Some(1.0),
+        "tb" | "terabyte" | "terabytes" => Some(1024.0),
+        _ => None,
+    }
+}
+
+/// Formats a GB amount, using TB for whole terabytes.
+fn format_gb(gb: u32) -> String {
+    if gb >= 1024 && gb.is_multiple_of(1024) {
+        format!("{}TB", gb / 1024)
+    } else {
+        format!("{}GB", gb)
+    }
+}
+
+fn parse_number(token: &str) -> Option<f64> {
+    if !token.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// Lowercases a title and splits it into number, unit and word tokens.
+///
+/// Digits and letters are separated ("256gb" -> "256 gb"), inch marks
+/// after a number become "inch" and "+" is read as "/". A comma or point
+/// between digits is a thousands separator when exactly three digits follow
+/// ("20,000mAh"), otherwise a decimal point ("6,1").
+fn tokenize(title: &str) -> Vec<String> {
+    let chars: Vec<char> = title.to_lowercase().chars().collect();
+    let mut out = String::with_capacity(chars.len() * 2);
+
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = out.trim_end().chars().last();
+        let next = chars.get(i + 1).copied();
+
+        match c {
+            '"' | '″' | '”' | '“' if prev.is_some_and(|p| p.is_ascii_digit()) => {
+                out.push_str(" inch ");
+            }
+            '/' | '+' => out.push_str(" / "),
+            ',' | '.'
+                if out.ends_with(|p: char| p.is_ascii_digit())
+                    && next.is_some_and(|n| n.is_ascii_digit()) =>
+            {
+                let digits = chars[i + 1..]
+                    .iter()
+                    .take_while(|n| n.is_ascii_digit())
+                    .count();
+                if digits != 3 {
+                    out.push('.');
+                }
+            }
+            _ if c.is_alphanumeric() => {
+                let boundary = out.chars().last().is_some_and(|p| {
+                    (p.is_ascii_digit() && c.is_alphabetic())
+                        || (p.is_alphabetic() && c.is_ascii_digit())
+                });
+                if boundary {
+                    out.push(' ');
+                }
+                out.push(c);
+            }
+            _ => out.push(' '),
+        }
+    }
+
+    out.split_whitespace().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ram_and_storage_labels() {
+        let specs = ProductSpecs::from_title("Samsung Galaxy A54 8GB RAM 256GB");
+        assert_eq!(specs.ram_gb, Some(8));
+        assert_eq!(specs.storage_gb, Some(256));
+
+        let specs = ProductSpecs::from_title("Galaxy A54 (256GB ROM, 8GB RAM)");
+        assert_eq!(specs.ram_gb, Some(8));
+        assert_eq!(specs.storage_gb, Some(256));
+
+        // Swapped labels are corrected
+        let specs = ProductSpecs::from_title("256GB RAM 8GB storage");
+        assert_eq!(specs.ram_gb, Some(8));
+        assert_eq!(specs.storage_gb, Some(256));
+    }
+
+    #[test]
+    fn test_slash_pair_and_unlabeled_values() {
+        let specs = ProductSpecs::from_title("Tecno Spark 20 8/256GB");
+        assert_eq!(specs.ram_gb, Some(8));
+        assert_eq!(specs.storage_gb, Some(256));
+
+        let specs = ProductSpecs::from_title("iPhone 15 128GB");
+        assert_eq!(specs.ram_gb, None);
+        assert_eq!(specs.storage_gb, Some(128));
+
+        let specs = ProductSpecs::from_title("MacBook Air 16GB 1TB");
+        assert_eq!(specs.ram_gb, Some(16));
+        assert_eq!(specs.storage_gb, Some(1024));
+    }
+
+    #[test]
+    fn test_screen_and_other_specs() {
+        let a = ProductSpecs::from_title("Phone 6.1 inch Black");
+        let b = ProductSpecs::from_title("Phone 6.1\" - black");
+        assert_eq!(a.screen_inches, Some(6.1));
+        assert_eq!(a, b);
+
+        let specs = ProductSpecs::from_title("Oraimo 20000mAh 22.5W Power Bank Grey, Pack of 2");
+        assert_eq!(specs.capacity_mah, Some(20000));
+        assert_eq!(specs.wattage_w, Some(22));
+        assert_eq!(specs.color.as_deref(), Some("gray"));
+        assert_eq!(specs.pack_size, Some(2));
+    }
+
+    #[test]
+    fn test_thousands_separators() {
+        let grouped = ProductSpecs::from_title("Oraimo 20,000mAh Power Bank");
+        let plain = ProductSpecs::from_title("Oraimo 20000mAh Power Bank");
+        assert_eq!(grouped.capacity_mah, Some(20000));
+        assert_eq!(grouped, plain);
+
+        assert_eq!(
+            ProductSpecs::from_title("Anker 10.000 mAh").capacity_mah,
+            Some(10000)
+        );
+        assert_eq!(
+            ProductSpecs::from_title("Binatone 1,000W Blender").wattage_w,
+            Some(1000)
+        );
+        assert_eq!(
+            ProductSpecs::from_title("Binatone 1000W Blender").wattage_w,
+            Some(1000)
+        );
+
+        // Fewer or more than three digits after the separator is a decimal part
+        assert_eq!(
+            ProductSpecs::from_title("Phone 6,1 inch").screen_inches,
+            Some(6.1)
+        );
+        assert_eq!(
+            ProductSpecs::from_title("Charger 22.5W").wattage_w,
+            Some(22)
+        );
+    }
+
+    #[test]
+    fn test_from_map() {
+        let mut map = HashMap::new();
+        map.insert("ram".to_string(), "8GB".to_string());
+        map.insert("storage".to_string(), "256 GB".to_string());
+        map.insert("color".to_string(), "Midnight".to_string());
+
+        let specs = ProductSpecs::from_map(&map);
+        assert_eq!(specs.ram_gb, Some(8));
+        assert_eq!(specs.storage_gb, Some(256));
+        assert_eq!(specs.color.as_deref(), Some("midnight"));
+    }
+
+    #[test]
+    fn test_compare() {
+        let source = ProductSpecs::from_title("iPhone 15 256GB Black");
+        let same = ProductSpecs::from_title("Apple iPhone 15 (256 GB) - Black");
+        let other = ProductSpecs::from_title("Apple iPhone 15 128GB Black");
+
+        let comparison = compare(&source, &same);
+        assert!(comparison.confirms_variant());
+        assert_eq!(comparison.matched.len(), 2);
+
+        let comparison = compare(&source, &other);
+        assert_eq!(comparison.mismatched, vec!["storage: 128GB vs 256GB"]);
+        assert!(!comparison.confirms_variant());
+    }
+}
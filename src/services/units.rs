@@ -0,0 +1,420 @@
+//! Pack size and quantity extraction for per-unit prices.
+//!
+//! Groceries and consumables are listed as "Pack of 6", "2 x 500ml" or
+//! "1kg", so the cheapest listing is not necessarily the cheapest per
+//! bottle or per kilo. This module reads the quantity a listing sells and
+//! expresses its price per item, per 100 ml or per kg, so listings of
+//! different pack sizes can be ranked against each other.
+
+use super::specs::ProductSpecs;
+use crate::SitePrice;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Basis a unit price is quoted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnitBasis {
+    #[serde(rename = "item")]
+    Item,
+    #[serde(rename = "100ml")]
+    Per100Ml,
+    #[serde(rename = "kg")]
+    Kilogram,
+}
+
+/// Total quantity sold by a listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quantity {
+    /// Number of items in the pack (1 when not stated)
+    pub count: u32,
+    /// Total quantity expressed in `basis` units (e.g., 10 for 2 x 500ml per 100ml)
+    pub amount: Decimal,
+    pub basis: UnitBasis,
+}
+
+/// Listing price per base unit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnitPrice {
+    pub quantity: Quantity,
+    /// Price per basis unit in the listing currency
+    pub price: Decimal,
+    /// Price per basis unit in USD
+    pub price_usd: Decimal,
+    /// Price per basis unit in the target currency (if conversion was requested)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_converted: Option<Decimal>,
+}
+
+/// Volume and weight units: (spellings, basis, size of one unit in ml or g).
+const MEASURES: [(&[&str], UnitBasis, &str); 8] = [
+    (
+        &["ml", "millilitre", "milliliter"],
+        UnitBasis::Per100Ml,
+        "1",
+    ),
+    (&["cl"], UnitBasis::Per100Ml, "10"),
+    (&["dl"], UnitBasis::Per100Ml, "100"),
+    (
+        &["l", "ltr", "litre", "liter", "litres", "liters"],
+        UnitBasis::Per100Ml,
+        "1000",
+    ),
+    (&["mg"], UnitBasis::Kilogram, "0.001"),
+    (
+        &["g", "gr", "gram", "grams", "gramme", "grammes"],
+        UnitBasis::Kilogram,
+        "1",
+    ),
+    (
+        &["kg", "kgs", "kilo", "kilos", "kilogram", "kilograms"],
+        UnitBasis::Kilogram,
+        "1000",
+    ),
+    (
+        &["lb", "lbs", "pound", "pounds"],
+        UnitBasis::Kilogram,
+        "453.592",
+    ),
+];
+
+/// Fluid ounces in ml, read from "fl oz".
+const FLUID_OUNCE_ML: &str = "29.5735";
+
+/// Ounces in g.
+const OUNCE_G: &str = "28.3495";
+
+/// "4G"/"5G" in a title is the mobile network, not a weight.
+const NETWORK_GENERATIONS: [&str; 4] = ["2", "3", "4", "5"];
+
+/// Words that introduce a pack count ("lot de 6", "box of 12").
+const COUNT_PREFIXES: [&str; 5] = ["lot", "paquet", "box", "case", "carton"];
+
+impl Quantity {
+    /// Extracts the quantity sold from a listing title.
+    ///
+    /// # Arguments
+    /// * `title` - Listing title (e.g., "Coca-Cola 2 x 1.5L", "Pampers Pack of 3")
+    ///
+    /// # Returns
+    /// * `Some(Quantity)` - A volume, weight or pack count was found
+    /// * `None` - The title states no quantity (a single item is assumed)
+    pub fn from_title(title: &str) -> Option<Self> {
+        let tokens = tokenize(title);
+        let measure = find_measure(&tokens);
+
+        let count = multiplier(&tokens, measure.as_ref().map(|m| m.position))
+            .or_else(|| prefixed_count(&tokens))
+            .or(ProductSpecs::from_title(title).pack_size)
+            .filter(|&count| count > 0);
+
+        match (measure, count) {
+            (Some(measure), count) => {
+                let count = count.unwrap_or(1);
+                let total = measure.size * Decimal::from(count);
+                let amount = match measure.basis {
+                    UnitBasis::Per100Ml => total / Decimal::from(100),
+                    UnitBasis::Kilogram => total / Decimal::from(1000),
+                    UnitBasis::Item => total,
+                };
+                Some(Self {
+                    count,
+                    amount: amount.normalize(),
+                    basis: measure.basis,
+                })
+            }
+            (None, Some(count)) => Some(Self {
+                count,
+                amount: Decimal::from(count),
+                basis: UnitBasis::Item,
+            }),
+            (None, None) => None,
+        }
+    }
+
+    /// Extracts the quantity from a specification map (e.g., a detail page's).
+    ///
+    /// Each entry is read as "<value> <key>", so `{"pack size": "6"}` reads
+    /// as "6 pack size" and `{"volume": "500ml"}` as "500ml volume".
+    pub fn from_map(map: &HashMap<String, String>) -> Option<Self> {
+        let mut entries: Vec<(&String, &String)> = map.iter().collect();
+        entries.sort();
+        let text = entries
+            .into_iter()
+            .map(|(key, value)| format!("{} {}", value, key))
+            .collect::<Vec<_>>()
+            .join(" , ");
+        Self::from_title(&text)
+    }
+}
+
+impl UnitPrice {
+    /// Computes the unit price of a listing.
+    ///
+    /// # Arguments
+    /// * `price` - Listing with its price, USD price and any converted price
+    /// * `quantity` - Quantity the listing sells
+    ///
+    /// # Returns
+    /// * `Some(UnitPrice)` - Prices per basis unit, rounded to 4 decimal places
+    /// * `None` - The quantity is zero
+    pub fn new(price: &SitePrice, quantity: Quantity) -> Option<Self> {
+        if quantity.amount.is_zero() {
+            return None;
+        }
+        let per_unit = |amount: Decimal| (amount / quantity.amount).round_dp(4);
+
+        Some(Self {
+            quantity,
+            price: per_unit(price.price.amount()),
+            price_usd: per_unit(price.price_usd),
+            price_converted: price.price_converted.map(|money| per_unit(money.amount())),
+        })
+    }
+}
+
+/// Sets `unit_price` on listings whose title states a quantity.
+///
+/// Listings that already carry a unit price (e.g., from a detail page) keep
+/// their quantity; their prices are recomputed, since conversion may have
+/// changed them.
+pub fn annotate_unit_prices(prices: &mut [SitePrice]) {
+    for price in prices {
+        let quantity = price
+            .unit_price
+            .as_ref()
+            .map(|unit| unit.quantity)
+            .or_else(|| Quantity::from_title(&price.title));
+        price.unit_price = quantity.and_then(|quantity| UnitPrice::new(price, quantity));
+    }
+}
+
+/// Whether listings should be ranked by unit price rather than total price.
+///
+/// True when every listing has a unit price in the same basis and their
+/// quantities differ, i.e. the same product in different pack sizes.
+pub fn prefer_unit_price(prices: &[SitePrice]) -> bool {
+    let quantities: Option<Vec<Quantity>> = prices
+        .iter()
+        .map(|p| p.unit_price.as_ref().map(|unit| unit.quantity))
+        .collect();
+    let Some(quantities) = quantities else {
+        return false;
+    };
+
+    quantities.windows(2).all(|w| w[0].basis == w[1].basis)
+        && quantities.windows(2).any(|w| w[0].amount != w[1].amount)
+}
+
+/// A volume or weight found in a title.
+struct Measure {
+    /// Size of one item in ml or g
+    size: Decimal,
+    basis: UnitBasis,
+    /// Index of the number token
+    position: usize,
+}
+
+/// Finds the first "<number> <unit>" volume or weight.
+fn find_measure(tokens: &[String]) -> Option<Measure> {
+    tokens.windows(2).enumerate().find_map(|(i, pair)| {
+        let value = Decimal::from_str(&pair[0]).ok()?;
+        if pair[1] == "g" && NETWORK_GENERATIONS.contains(&pair[0].as_str()) {
+            return None;
+        }
+        let (basis, factor) = match pair[1].as_str() {
+            "fl" if tokens.get(i + 2).is_some_and(|t| t == "oz") => {
+                (UnitBasis::Per100Ml, FLUID_OUNCE_ML)
+            }
+            "oz" => (UnitBasis::Kilogram, OUNCE_G),
+            unit => MEASURES
+                .iter()
+                .find(|(spellings, _, _)| spellings.contains(&unit))
+                .map(|(_, basis, factor)| (*basis, *factor))?,
+        };
+
+        Some(Measure {
+            size: value * Decimal::from_str(factor).ok()?,
+            basis,
+            position: i,
+        })
+    })
+}
+
+/// Reads "N x 500ml" or "500ml x N" around the measure, or a leading "N x".
+///
+/// Without a measure only a title that starts with "N x" counts, so
+/// resolutions ("3840x2160") and model names ("Redmi 12 X") are ignored.
+fn multiplier(tokens: &[String], measure: Option<usize>) -> Option<u32> {
+    let is_times = |t: &String| matches!(t.as_str(), "x" | "×" | "*");
+
+    if let Some(position) = measure {
+        if position >= 2 && is_times(&tokens[position - 1]) {
+            return tokens[position - 2].parse().ok();
+        }
+        // "500 ml x 6"
+        let after = position + 2;
+        if tokens.get(after).is_some_and(is_times) {
+            return tokens.get(after + 1)?.parse().ok();
+        }
+    }
+
+    match tokens {
+        [count, times, ..] if is_times(times) => count.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Reads "lot de 6", "box of 12" and similar.
+fn prefixed_count(tokens: &[String]) -> Option<u32> {
+    tokens.windows(3).find_map(|w| {
+        (COUNT_PREFIXES.contains(&w[0].as_str()) && matches!(w[1].as_str(), "de" | "of"))
+            .then(|| w[2].parse().ok())
+            .flatten()
+    })
+}
+
+/// Splits a title into lowercase words and numbers.
+///
+/// Numbers are separated from units ("500ml" is "500 ml"), decimal commas
+/// become points ("1,5l" is "1.5 l") and "x" between numbers is its own word.
+fn tokenize(title: &str) -> Vec<String> {
+    let chars: Vec<char> = title.to_lowercase().chars().collect();
+    let mut out = String::with_capacity(chars.len() * 2);
+
+    for (i, &c) in chars.iter().enumerate() {
+        let next = chars.get(i + 1).copied();
+
+        match c {
+            ',' | '.'
+                if out.ends_with(|p: char| p.is_ascii_digit())
+                    && next.is_some_and(|n| n.is_ascii_digit()) =>
+            {
+                out.push('.');
+            }
+            '×' | '*' => out.push_str(" x "),
+            _ if c.is_alphanumeric() => {
+                let boundary = out.chars().last().is_some_and(|p| {
+                    (p.is_ascii_digit() && c.is_alphabetic())
+                        || (p.is_alphabetic() && c.is_ascii_digit())
+                });
+                if boundary {
+                    out.push(' ');
+                }
+                out.push(c);
+            }
+            _ => out.push(' '),
+        }
+    }
+
+    out.split_whitespace().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::money::Money;
+
+    fn quantity(title: &str) -> Option<(u32, Decimal, UnitBasis)> {
+        Quantity::from_title(title).map(|q| (q.count, q.amount, q.basis))
+    }
+
+    #[test]
+    fn test_volume_and_weight() {
+        assert_eq!(
+            quantity("Coca-Cola 2 x 1,5L"),
+            Some((2, Decimal::from(30), UnitBasis::Per100Ml))
+        );
+        assert_eq!(
+            quantity("Heineken 330ml x 24"),
+            Some((24, Decimal::new(792, 1), UnitBasis::Per100Ml))
+        );
+        assert_eq!(
+            quantity("Golden Penny Semovita 1kg"),
+            Some((1, Decimal::ONE, UnitBasis::Kilogram))
+        );
+        assert_eq!(
+            quantity("Milo 6x400g"),
+            Some((6, Decimal::new(24, 1), UnitBasis::Kilogram))
+        );
+        assert_eq!(
+            quantity("Eau minérale Lot de 6 bouteilles 50cl"),
+            Some((6, Decimal::from(30), UnitBasis::Per100Ml))
+        );
+    }
+
+    #[test]
+    fn test_pack_counts_and_no_quantity() {
+        assert_eq!(
+            quantity("Pampers Baby Dry Diapers, Pack of 3"),
+            Some((3, Decimal::from(3), UnitBasis::Item))
+        );
+        assert_eq!(
+            quantity("Duracell AA Batteries 12-Pack"),
+            Some((12, Decimal::from(12), UnitBasis::Item))
+        );
+        // Capacities and sizes are not quantities sold
+        assert_eq!(quantity("Samsung Galaxy A54 8GB 256GB"), None);
+        assert_eq!(quantity("LG 55 inch 4K TV"), None);
+        assert_eq!(quantity("Pixel 8 5G"), None);
+        assert_eq!(quantity("Hisense 65 inch 3840x2160 UHD"), None);
+        assert_eq!(
+            quantity("2 x Gillette Mach3 Blades"),
+            Some((2, Decimal::from(2), UnitBasis::Item))
+        );
+    }
+
+    #[test]
+    fn test_unit_price_and_ranking() {
+        let mut prices = vec![
+            SitePrice::new(
+                "Jumia".to_string(),
+                "Peak Milk 400g".to_string(),
+                Money::usd(Decimal::from(4)),
+                Decimal::from(4),
+                String::new(),
+                None,
+                None,
+            ),
+            SitePrice::new(
+                "Konga".to_string(),
+                "Peak Milk 2 x 400g".to_string(),
+                Money::usd(Decimal::from(7)),
+                Decimal::from(7),
+                String::new(),
+                None,
+                None,
+            ),
+        ];
+        annotate_unit_prices(&mut prices);
+
+        assert_eq!(
+            prices[0].unit_price.as_ref().unwrap().price_usd,
+            Decimal::from(10)
+        );
+        assert_eq!(
+            prices[1].unit_price.as_ref().unwrap().price_usd,
+            Decimal::new(875, 2)
+        );
+        assert!(prefer_unit_price(&prices));
+
+        // Same pack size everywhere: total price already compares like for like
+        prices[1].title = "Peak Milk 400g".to_string();
+        prices[1].unit_price = None;
+        annotate_unit_prices(&mut prices);
+        assert!(!prefer_unit_price(&prices));
+    }
+
+    #[test]
+    fn test_from_map() {
+        let map = HashMap::from([
+            ("volume".to_string(), "500ml".to_string()),
+            ("pack size".to_string(), "6".to_string()),
+        ]);
+        assert_eq!(
+            Quantity::from_map(&map).map(|q| (q.count, q.amount)),
+            Some((6, Decimal::from(30)))
+        );
+    }
+}
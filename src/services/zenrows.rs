@@ -3,11 +3,13 @@
 //! Provides integration with ZenRows API for scraping e-commerce sites
 //! with automatic proxy rotation, JavaScript rendering, and CAPTCHA solving.
 
-use crate::services::currency::parse_price_with_currency;
+use crate::services::currency::{parse_price_in_context, Currency};
+use crate::services::discounts;
 use crate::{AppError, SitePrice};
 use reqwest::Client;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 use serde::Deserialize;
+use std::str::FromStr;
 
 /// ZenRows API configuration.
 #[derive(Debug, Clone)]
@@ -54,6 +56,9 @@ pub struct AmazonProductResponse {
 
     // Pricing details
     pub price_currency_symbol: Option<String>,
+    /// ISO 4217 code of the price, when the API declares one
+    #[serde(alias = "price_currency_code")]
+    pub price_currency: Option<String>,
     pub list_price: Option<String>,
     pub savings_amount: Option<String>,
 }
@@ -164,17 +169,27 @@ pub async fn fetch_amazon_product(
         .price
         .ok_or_else(|| AppError::MissingField("Amazon product price".to_string()))?;
 
-    let (price, currency) = parse_price_with_currency(&price_str, Some("amazon.com"))?;
-    // For now, assume USD for Amazon.com - would need marketplace detection for other regions
-    let price_usd = price; // Since we detected USD
-
     let link = product
         .product_url
         .unwrap_or_else(|| format!("https://www.amazon.com/dp/{}", asin));
 
+    // A declared code wins; otherwise the product URL names the marketplace
+    // ("$" on amazon.ca is CAD)
+    let declared = product
+        .price_currency
+        .as_deref()
+        .and_then(|code| Currency::from_str(code).ok());
+    let (price, resolved) = parse_price_in_context(&price_str, declared, Some(&link))?;
+    let price_usd = price.amount(); // Normalized to USD by the comparison pipeline
+
     // Get first image from array if available
     let image = product.image.and_then(|images| images.into_iter().next());
 
+    let list_price = product
+        .list_price
+        .as_deref()
+        .and_then(|text| discounts::reference_price(text, &price, Some(&link)));
+
     // Validate product is available and price is reasonable
     if let Some(false) = product.is_available {
         tracing::warn!(
@@ -198,13 +213,20 @@ pub async fn fetch_amazon_product(
         site: "Amazon".to_string(),
         title,
         price,
-        currency: currency.code().to_string(),
         price_usd,
+        usd_rate: None,
         price_converted: None,
-        target_currency: None,
         link,
         image,
         match_confidence: Some(100), // ASIN is exact match
+        match_explanation: None,
+        unit_price: None,
+        image_hash: None,
+        rate_sources: Vec::new(),
+        price_insight: None,
+        list_price,
+        discount_check: None,
+        currency_resolution: Some(resolved),
     })
 }
 
@@ -263,6 +285,37 @@ pub struct ProductSelectors {
     pub price: String,
     pub link: String,
     pub image: String,
+    /// Reference ("was" or strike-through) price, where the site shows one
+    pub list_price: Option<String>,
+}
+
+/// Reads the currency code a search result declares in its markup.
+///
+/// Looks at the element and its descendants for microdata
+/// (`itemprop="priceCurrency"`, as `content` or text) and `data-currency`
+/// attributes. Codes outside the ISO table are ignored.
+///
+/// # Returns
+/// * `Some(Currency)` - First declared code that names a currency
+/// * `None` - Nothing declared
+fn declared_currency(element: ElementRef) -> Option<Currency> {
+    let selector = Selector::parse(r#"[itemprop="priceCurrency"], [data-currency]"#)
+        .expect("currency selector is valid");
+
+    std::iter::once(element)
+        .chain(element.select(&selector))
+        .filter_map(|el| {
+            let value = el.value();
+            if value.attr("itemprop") == Some("priceCurrency") {
+                value
+                    .attr("content")
+                    .map(str::to_string)
+                    .or_else(|| Some(el.text().collect()))
+            } else {
+                value.attr("data-currency").map(str::to_string)
+            }
+        })
+        .find_map(|code| Currency::from_str(code.trim()).ok())
 }
 
 /// Extracts the first product from search results HTML.
@@ -300,15 +353,29 @@ fn extract_first_product(
         .trim()
         .to_string();
 
-    let price_text = container
+    let price_element = container
         .select(&price_selector)
         .next()
-        .ok_or_else(|| AppError::MissingField("Product price".to_string()))?
-        .text()
-        .collect::<String>();
-
-    let (price, currency) = parse_price_with_currency(&price_text, None)?;
-    let price_usd = price; // Default to same as price - caller should convert if needed
+        .ok_or_else(|| AppError::MissingField("Product price".to_string()))?;
+    let price_text = price_element.text().collect::<String>();
+
+    // Currency the markup declares next to the price, else anywhere in the result
+    let declared = declared_currency(price_element).or_else(|| declared_currency(container));
+    let (price, resolved) = parse_price_in_context(&price_text, declared, Some(base_url))?;
+    let price_usd = price.amount(); // Normalized to USD by the comparison pipeline
+
+    let list_price = match &selectors.list_price {
+        Some(selector) => {
+            let selector = Selector::parse(selector)
+                .map_err(|e| AppError::Internal(format!("Invalid list price selector: {}", e)))?;
+            container
+                .select(&selector)
+                .next()
+                .map(|el| el.text().collect::<String>())
+                .and_then(|text| discounts::reference_price(&text, &price, Some(base_url)))
+        }
+        None => None,
+    };
 
     let link = container
         .select(&link_selector)
@@ -319,16 +386,10 @@ fn extract_first_product(
     // Convert relative link to absolute URL
     let link = if link.starts_with("http://") || link.starts_with("https://") {
         link.to_string()
+    } else if link.starts_with('/') {
+        format!("{}{}", base_url, link)
     } else {
-        format!(
-            "{}{}",
-            base_url,
-            if link.starts_with('/') {
-                link
-            } else {
-                &format!("/{}", link)
-            }
-        )
+        format!("{}/{}", base_url, link)
     };
 
     let image = container
@@ -347,8 +408,6 @@ fn extract_first_product(
         site = "Search result",
         title = %title,
         price = %price,
-        currency = %currency.code(),
-        price_usd = %price_usd,
         link = %link,
         "Extracted product from search results"
     );
@@ -357,12 +416,80 @@ fn extract_first_product(
         site: "Unknown".to_string(),
         title,
         price,
-        currency: currency.code().to_string(),
         price_usd,
+        usd_rate: None,
         price_converted: None,
-        target_currency: None,
         link,
         image,
         match_confidence: Some(70), // Search-based match has lower confidence
+        match_explanation: None,
+        unit_price: None,
+        image_hash: None,
+        rate_sources: Vec::new(),
+        price_insight: None,
+        list_price,
+        discount_check: None,
+        currency_resolution: Some(resolved),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selectors() -> ProductSelectors {
+        ProductSelectors {
+            container: "article.prd".to_string(),
+            title: "h3".to_string(),
+            price: ".prc".to_string(),
+            link: "a".to_string(),
+            image: "img".to_string(),
+            list_price: None,
+        }
+    }
+
+    fn extract(html: &str, base_url: &str) -> SitePrice {
+        extract_first_product(&Html::parse_document(html), &selectors(), base_url).unwrap()
+    }
+
+    #[test]
+    fn test_declared_currency_overrides_ambiguous_dollar() {
+        // "$" alone reads as USD on a storefront with no marketplace currency
+        let plain = extract(
+            r#"<article class="prd"><a href="/p/1"><h3>Phone</h3></a>
+               <div class="prc">$1,299.00</div></article>"#,
+            "https://shop.example",
+        );
+        assert_eq!(plain.price.currency().code(), "USD");
+
+        let attribute = extract(
+            r#"<article class="prd"><a href="/p/1"><h3>Phone</h3></a>
+               <div class="prc" data-currency="CAD">$1,299.00</div></article>"#,
+            "https://shop.example",
+        );
+        assert_eq!(attribute.price.currency().code(), "CAD");
+        assert_eq!(attribute.price.amount().to_string(), "1299.00");
+
+        // Microdata beats the marketplace currency of the host
+        let microdata = extract(
+            r#"<article class="prd" itemscope itemtype="https://schema.org/Product">
+               <a href="/dp/B0C"><h3>Phone</h3></a>
+               <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                 <meta itemprop="priceCurrency" content="AUD">
+                 <span class="prc">$1,299.00</span>
+               </div></article>"#,
+            "https://www.amazon.com",
+        );
+        assert_eq!(microdata.price.currency().code(), "AUD");
+    }
+
+    #[test]
+    fn test_unknown_declared_code_is_ignored() {
+        let listing = extract(
+            r#"<article class="prd"><a href="/p/1"><h3>Phone</h3></a>
+               <div class="prc" data-currency="points">₦250,000</div></article>"#,
+            "https://shop.example",
+        );
+        assert_eq!(listing.price.currency().code(), "NGN");
+    }
+}
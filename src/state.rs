@@ -10,6 +10,7 @@ use std::sync::Arc;
 
 use crate::Config;
 use crate::services::currency::CurrencyService;
+use crate::services::history_writer::HistoryWriter;
 
 /// Shared application state passed to all route handlers.
 #[derive(Clone)]
@@ -28,6 +29,9 @@ pub struct AppState {
 
     /// Currency service for exchange rates and conversions
     pub currency_service: Arc<CurrencyService>,
+
+    /// Queue for batched price history writes
+    pub history_writer: HistoryWriter,
 }
 
 impl AppState {
@@ -38,18 +42,20 @@ impl AppState {
     /// * `redis_pool` - Redis connection pool
     /// * `http_client` - HTTP client
     /// * `config` - Application configuration
+    /// * `history_writer` - Queue for price history writes
     pub fn new(
         db_pool: PgPool,
         redis_pool: RedisPool,
         http_client: Client,
         config: Config,
+        history_writer: HistoryWriter,
     ) -> Self {
         // Initialize currency service
         let currency_service = Arc::new(CurrencyService::new(
             redis_pool.clone(),
+            db_pool.clone(),
             http_client.clone(),
-            config.currency.api_url.clone(),
-            config.currency.cache_ttl_hours,
+            &config.currency,
         ));
 
         Self {
@@ -58,6 +64,7 @@ impl AppState {
             http_client,
             config,
             currency_service,
+            history_writer,
         }
     }
 }
@@ -53,16 +53,60 @@ pub async fn fetch_with_retry(
     url: &str,
     max_retries: u32,
 ) -> Result<String, AppError> {
+    send_with_retry(client, url, None, max_retries)
+        .await?
+        .text()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to read response: {}", e)))
+}
+
+/// Accept header for image downloads.
+const IMAGE_ACCEPT: &str = "image/webp,image/png,image/jpeg,*/*;q=0.8";
+
+/// Performs an HTTP GET request for binary content (e.g., an image) with retries.
+///
+/// Same retry and backoff behavior as `fetch_with_retry`.
+///
+/// # Arguments
+/// * `client` - HTTP client
+/// * `url` - URL to fetch
+/// * `max_retries` - Maximum number of retry attempts
+///
+/// # Returns
+/// * `Ok(Vec<u8>)` - Response body
+/// * `Err(AppError)` - Network or HTTP error
+pub async fn fetch_bytes_with_retry(
+    client: &Client,
+    url: &str,
+    max_retries: u32,
+) -> Result<Vec<u8>, AppError> {
+    send_with_retry(client, url, Some(IMAGE_ACCEPT), max_retries)
+        .await?
+        .bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| AppError::Network(format!("Failed to read response: {}", e)))
+}
+
+/// Sends a GET request, retrying rate-limited (429) and failed requests with backoff.
+async fn send_with_retry(
+    client: &Client,
+    url: &str,
+    accept: Option<&'static str>,
+    max_retries: u32,
+) -> Result<reqwest::Response, AppError> {
     let mut attempt = 0;
 
     loop {
-        match client.get(url).send().await {
+        let mut request = client.get(url);
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+
+        match request.send().await {
             Ok(response) => {
                 if response.status().is_success() {
-                    return response
-                        .text()
-                        .await
-                        .map_err(|e| AppError::Network(format!("Failed to read response: {}", e)));
+                    return Ok(response);
                 } else if response.status().as_u16() == 429 {
                     // Rate limited - apply exponential backoff
                     if attempt >= max_retries {